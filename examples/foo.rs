@@ -1,12 +1,12 @@
-use dismal::Inst;
+use dismal::{Inst, Mode};
 
 fn test(bytes: &[u8]) {
     println!("---");
     println!("bytes = {bytes:02X?}");
 
-    if let Some(inst) = Inst::from_bytes(bytes) {
+    if let Some(inst) = Inst::from_bytes(bytes, Mode::Long64) {
         println!("inst = {inst:0X?}");
-        println!("reenc = {:02X?}", inst.to_bytes());
+        println!("reenc = {:02X?}", inst.to_bytes(Mode::Long64));
     } else {
         println!("failed to decode");
     }
@@ -28,4 +28,12 @@ fn main() {
     test(&[0xC3]);
 
     test(&[0x41, 0x5F]);
+
+    test(&[0xE9, 0x0B, 0x01, 0x00, 0x00]);
+
+    test(&[0xEB, 0xFE]);
+
+    test(&[0x74, 0x10]);
+
+    test(&[0x0F, 0x85, 0x2A, 0x01, 0x00, 0x00]);
 }
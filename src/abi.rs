@@ -0,0 +1,82 @@
+// ty https://gitlab.com/x86-psABIs/x86-64-ABI and https://learn.microsoft.com/en-us/cpp/build/x64-calling-convention
+
+use crate::Reg;
+
+/// Integer argument registers of the System V AMD64 ABI, in order.
+const SYSV_ARGS: [Reg; 6] = [Reg::Rdi, Reg::Rsi, Reg::Rdx, Reg::Rcx, Reg::R8, Reg::R9];
+
+/// Integer argument registers of the Microsoft x64 ABI, in order.
+const WIN64_ARGS: [Reg; 4] = [Reg::Rcx, Reg::Rdx, Reg::R8, Reg::R9];
+
+/// A 64-bit calling convention.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Abi {
+    /// System V AMD64 ABI, as on Linux, macOS and the BSDs.
+    SysV,
+    /// Microsoft x64 ABI, as on Windows.
+    Win64,
+}
+
+impl Abi {
+    /// Returns the integer argument registers, in order.
+    #[inline]
+    pub const fn args(self) -> &'static [Reg] {
+        match self {
+            Abi::SysV => &SYSV_ARGS,
+            Abi::Win64 => &WIN64_ARGS,
+        }
+    }
+
+    /// Returns the register of integer argument `index`, if it is passed in a register.
+    #[inline]
+    pub const fn arg(self, index: usize) -> Option<Reg> {
+        let args = self.args();
+
+        if index < args.len() {
+            Some(args[index])
+        } else {
+            None
+        }
+    }
+}
+
+impl Reg {
+    /// Returns `true` if this register passes an integer argument in `abi`.
+    #[inline]
+    pub const fn is_arg(self, abi: Abi) -> bool {
+        self.arg_index(abi).is_some()
+    }
+
+    /// Returns the index of the integer argument this register passes in `abi`.
+    #[inline]
+    pub const fn arg_index(self, abi: Abi) -> Option<usize> {
+        let args = abi.args();
+        let mut index = 0;
+
+        while index < args.len() {
+            if args[index] as u8 == self as u8 {
+                return Some(index);
+            }
+
+            index += 1;
+        }
+
+        None
+    }
+
+    /// Returns `true` if this register must be preserved by a callee in `abi` (non-volatile).
+    #[inline]
+    pub const fn is_callee_saved(self, abi: Abi) -> bool {
+        match self {
+            Reg::Rbx | Reg::Rsp | Reg::Rbp | Reg::R12 | Reg::R13 | Reg::R14 | Reg::R15 => true,
+            Reg::Rsi | Reg::Rdi => matches!(abi, Abi::Win64),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this register may be clobbered by a callee in `abi` (caller saved).
+    #[inline]
+    pub const fn is_volatile(self, abi: Abi) -> bool {
+        !self.is_callee_saved(abi)
+    }
+}
@@ -0,0 +1,1643 @@
+//! Analyses of decoded instructions.
+
+#[cfg(feature = "alloc")]
+use crate::Cond;
+use crate::{
+    Abi, Arg, Base, Inst, InstIter, Kind, Mem, MemAccess, Mode, Reg, RegSet, Size, Target,
+    TargetError, WithIp,
+};
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::ops;
+
+/// A basic block, instructions only entered at the first and left after the last.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Block {
+    /// Addresses the block occupies.
+    pub range: ops::Range<usize>,
+    /// Indices of the instructions of the block.
+    pub insts: ops::Range<usize>,
+    /// Addresses of the blocks which may be executed next, which may be outside of the
+    /// instructions partitioned.
+    pub succs: Vec<usize>,
+}
+
+/// Returns the instruction of `insts`, in order, at `ip`.
+#[inline]
+pub fn inst_at(insts: &[WithIp], ip: usize) -> Option<&WithIp> {
+    match insts.binary_search_by_key(&ip, |inst| inst.ip()) {
+        Ok(index) => Some(&insts[index]),
+        Err(_) => None,
+    }
+}
+
+/// Returns the address reached from `ip` by following the unconditional relative `jmp`s of
+/// `insts`, in order, which is `ip` if it is not a `jmp`.
+///
+/// Returns `None` if the `jmp`s loop forever.
+pub fn jmp_chain_end(insts: &[WithIp], mut ip: usize) -> Option<usize> {
+    // a chain longer than the instructions revisits one of them
+    for _ in 0..=insts.len() {
+        let Some(inst) = inst_at(insts, ip) else {
+            return Some(ip);
+        };
+
+        match (inst.kind(), inst.target()) {
+            (Kind::Jmp, Target::Absolute(target)) => ip = target,
+            _ => return Some(ip),
+        }
+    }
+
+    None
+}
+
+/// Retarget each relative branch of `insts`, in order, to the end of the chain of `jmp`s it
+/// targets, returning the number retargeted.
+///
+/// A short branch becomes a near branch where the end is out of range, overlapping the
+/// instructions which follow it, so the block is to be re-encoded with [`rebase`].
+///
+/// [`rebase`]: crate::rebase
+pub fn collapse_jmp_chains(insts: &mut [WithIp]) -> Result<usize, TargetError> {
+    let mut count = 0;
+
+    for index in 0..insts.len() {
+        let inst = insts[index];
+
+        let Target::Absolute(target) = inst.target() else {
+            continue;
+        };
+
+        if !inst.is_branch() {
+            continue;
+        }
+
+        let end = match jmp_chain_end(insts, target) {
+            Some(end) if end != target => end,
+            _ => continue,
+        };
+
+        let retargeted = inst.with_target(end, inst.ip(), inst.mode())?;
+
+        insts[index] = WithIp::new(inst.ip(), retargeted, inst.mode())?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Returns the address of the slot an import stub, `bytes` at `ip` in `mode`, jumps through.
+///
+/// Recognises `jmp [rip + disp]` of a PLT entry, or `jmp [disp]` of an IAT thunk outside of long
+/// mode, optionally preceded by `endbr64` or `endbr32`, and with a `bnd` prefix. The GOT or IAT
+/// slot holds the address of the imported function.
+pub fn import_slot(bytes: &[u8], ip: usize, mode: Mode) -> Option<usize> {
+    let mut iter = InstIter::from_bytes(ip, bytes, mode);
+    let mut inst = iter.next()?;
+
+    if let Inst::Endbr64 | Inst::Endbr32 = *inst {
+        inst = iter.next()?;
+    }
+
+    if !matches!(*inst, Inst::Jmp(_)) {
+        return None;
+    }
+
+    match inst.target() {
+        Target::RipRelativeMem(addr) => Some(addr),
+        Target::Memory(Mem {
+            seg: None,
+            base: None,
+            index: None,
+            disp,
+            ..
+        }) => match mode {
+            Mode::Long64 => Some(disp as isize as usize),
+            _ => Some(disp as u32 as usize),
+        },
+        _ => None,
+    }
+}
+
+/// Partition `insts`, in order, into basic blocks, split at the targets of relative branches, and
+/// after terminators.
+///
+/// Blocks are also split where the instructions are not contiguous.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn basic_blocks(insts: &[WithIp]) -> Vec<Block> {
+    basic_blocks_with_tables(insts, &[])
+}
+
+/// Partition `insts`, in order, into basic blocks, as by [`basic_blocks`], the targets of each of
+/// `tables`, as by [`jump_tables`], also being successors of its `jmp`.
+#[cfg(feature = "alloc")]
+pub fn basic_blocks_with_tables(insts: &[WithIp], tables: &[JumpTable]) -> Vec<Block> {
+    let mut leaders = BTreeSet::new();
+
+    for (index, inst) in insts.iter().enumerate() {
+        if let (true, Target::Absolute(target)) = (inst.is_branch(), inst.target()) {
+            if inst_at(insts, target).is_some() {
+                leaders.insert(target);
+            }
+        }
+
+        if let Some(next) = insts.get(index + 1) {
+            if inst.is_terminator() || inst.next_ip() != next.ip() {
+                leaders.insert(next.ip());
+            }
+        }
+    }
+
+    for table in tables {
+        let targets = table.targets.iter().copied();
+
+        leaders.extend(targets.filter(|&target| inst_at(insts, target).is_some()));
+    }
+
+    let mut blocks = Vec::new();
+    let mut start = 0;
+
+    for (index, inst) in insts.iter().enumerate() {
+        let ends = match insts.get(index + 1) {
+            Some(next) => leaders.contains(&next.ip()),
+            None => true,
+        };
+
+        if !ends {
+            continue;
+        }
+
+        let mut succs = Vec::new();
+
+        if let (Kind::Jmp | Kind::Jcc, Target::Absolute(target)) = (inst.kind(), inst.target()) {
+            succs.push(target);
+        }
+
+        if let Some(table) = tables.iter().find(|table| table.jmp == inst.ip()) {
+            for &target in &table.targets {
+                if !succs.contains(&target) {
+                    succs.push(target);
+                }
+            }
+        }
+
+        if !matches!(inst.kind(), Kind::Jmp | Kind::Ret | Kind::Trap) {
+            succs.push(inst.next_ip());
+        }
+
+        blocks.push(Block {
+            range: insts[start].ip()..inst.next_ip(),
+            insts: start..index + 1,
+            succs,
+        });
+
+        start = index + 1;
+    }
+
+    blocks
+}
+
+/// A call graph, the functions each function calls.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CallGraph {
+    /// Starts of the functions called or tail called directly by each function, keyed by its
+    /// start.
+    pub calls: BTreeMap<usize, BTreeSet<usize>>,
+    /// Addresses of the `call`s whose target is not known, keyed by the start of the function
+    /// containing them.
+    pub indirect: BTreeMap<usize, Vec<usize>>,
+}
+
+/// Build the call graph of `insts`, in order, the whole of a code section.
+///
+/// Functions are those of [`functions`], each calling the targets of its relative `call`s and
+/// tail calls, see [`is_tail_call`]. Functions outside of `insts`, such as import stubs, are only
+/// called.
+#[cfg(feature = "alloc")]
+pub fn call_graph(insts: &[WithIp]) -> CallGraph {
+    let functions = functions(insts);
+    let mut graph = CallGraph::default();
+
+    for function in &functions {
+        graph.calls.insert(function.start, BTreeSet::new());
+    }
+
+    for inst in insts {
+        let Some(function) = function_at(&functions, inst.ip()) else {
+            continue;
+        };
+
+        let callee = match (inst.kind(), inst.target()) {
+            (Kind::Call, Target::Absolute(target)) => target,
+            (Kind::Call, _) => {
+                let sites = graph.indirect.entry(function.start).or_default();
+
+                sites.push(inst.ip());
+
+                continue;
+            }
+            (Kind::Jmp, Target::Absolute(target)) if is_tail_call(inst, &functions) => target,
+            _ => continue,
+        };
+
+        if let Some(callees) = graph.calls.get_mut(&function.start) {
+            callees.insert(callee);
+        }
+    }
+
+    graph
+}
+
+/// Returns `true` if `inst` pads between functions, `int3` or a `nop`.
+#[cfg(feature = "alloc")]
+#[inline]
+const fn is_padding(inst: &Inst) -> bool {
+    matches!(inst, Inst::Int3 | Inst::Nop(_))
+}
+
+/// Returns the probable functions of `insts`, in order, as the addresses each occupies.
+///
+/// Functions start at the first instruction, at the targets of relative `call`s, at `endbr64` or
+/// `endbr32`, after padding following a return or `jmp`, and at the targets of tail calls. Each
+/// extends to the next, excluding any padding it ends with.
+#[cfg(feature = "alloc")]
+pub fn functions(insts: &[WithIp]) -> Vec<ops::Range<usize>> {
+    let mut starts = BTreeSet::new();
+    let mut padded = false;
+
+    if let Some(first) = insts.first() {
+        starts.insert(first.ip());
+    }
+
+    for (index, inst) in insts.iter().enumerate() {
+        // padding continues until the next instruction
+        if let Some(prev) = index.checked_sub(1).map(|prev| &insts[prev]) {
+            padded = match (is_padding(inst), is_padding(prev)) {
+                (true, false) => matches!(prev.kind(), Kind::Ret | Kind::Jmp),
+                (true, true) => padded,
+                (false, true) if padded => {
+                    starts.insert(inst.ip());
+
+                    false
+                }
+                _ => false,
+            };
+        }
+
+        if let Inst::Endbr64 | Inst::Endbr32 = **inst {
+            starts.insert(inst.ip());
+        }
+
+        if let (Kind::Call, Target::Absolute(target)) = (inst.kind(), inst.target()) {
+            if inst_at(insts, target).is_some() {
+                starts.insert(target);
+            }
+        }
+    }
+
+    // a tail call target starts a function, which may reveal more tail calls
+    loop {
+        let functions = extents(insts, &starts);
+        let mut grown = false;
+
+        for inst in insts {
+            let Target::Absolute(target) = inst.target() else {
+                continue;
+            };
+
+            if is_tail_call(inst, &functions) && inst_at(insts, target).is_some() {
+                grown |= starts.insert(target);
+            }
+        }
+
+        if !grown {
+            break functions;
+        }
+    }
+}
+
+/// Returns the functions of `insts`, in order, starting at `starts`, each extending to the next,
+/// excluding any padding it ends with.
+#[cfg(feature = "alloc")]
+fn extents(insts: &[WithIp], starts: &BTreeSet<usize>) -> Vec<ops::Range<usize>> {
+    let mut functions: Vec<ops::Range<usize>> = Vec::with_capacity(starts.len());
+
+    for inst in insts {
+        if starts.contains(&inst.ip()) {
+            functions.push(inst.ip()..inst.ip());
+        }
+
+        if let (Some(function), false) = (functions.last_mut(), is_padding(inst)) {
+            function.end = inst.next_ip();
+        }
+    }
+
+    // a function of only padding
+    functions.retain(|function| !function.is_empty());
+
+    functions
+}
+
+/// Returns the function of `functions`, in order, occupying `addr`.
+#[inline]
+fn function_at(functions: &[ops::Range<usize>], addr: usize) -> Option<&ops::Range<usize>> {
+    let index = functions.partition_point(|function| function.start <= addr);
+    let function = &functions[index.checked_sub(1)?];
+
+    function.contains(&addr).then_some(function)
+}
+
+/// Returns `true` if `inst` is a `jmp` used as a tail call, rather than control flow within a
+/// function, given `functions`, in order, as by [`functions`].
+///
+/// Its target is outside of the function containing it, or the start of a function if it is in
+/// none.
+pub fn is_tail_call(inst: &WithIp, functions: &[ops::Range<usize>]) -> bool {
+    let (Kind::Jmp, Target::Absolute(target)) = (inst.kind(), inst.target()) else {
+        return false;
+    };
+
+    match function_at(functions, inst.ip()) {
+        Some(function) => !function.contains(&target),
+        None => function_at(functions, target).is_some_and(|function| function.start == target),
+    }
+}
+
+/// How a function sets up its stack frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Frame {
+    /// No frame pointer.
+    None,
+    /// `rbp` is the frame pointer, `push rbp; mov rbp, rsp`, torn down by `mov rsp, rbp; pop rbp`
+    /// or `leave`.
+    Rbp,
+}
+
+/// The instructions setting up the stack frame of a function.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Prologue {
+    /// How the stack frame is set up.
+    pub frame: Frame,
+    /// Callee saved registers pushed, other than the frame pointer.
+    pub saved: RegSet,
+    /// Bytes allocated by `sub rsp, imm`.
+    pub stack: usize,
+    /// Length of the prologue, including any `endbr64` or `endbr32`.
+    pub len: usize,
+}
+
+/// The instructions tearing down the stack frame of a function, and returning.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Epilogue {
+    /// How the stack frame is torn down.
+    pub frame: Frame,
+    /// Callee saved registers popped, other than the frame pointer.
+    pub restored: RegSet,
+    /// Bytes freed by `add rsp, imm`.
+    pub stack: usize,
+    /// Length of the epilogue, including the return.
+    pub len: usize,
+}
+
+/// Returns the number of bytes the immediate `imm` adjusts `rsp` by.
+#[inline]
+fn stack_imm(imm: Arg) -> Option<usize> {
+    usize::try_from(imm.imm()?).ok()
+}
+
+/// Returns the prologue of the function starting with `insts`, in order.
+///
+/// Recognises callee saved pushes before or after `push rbp; mov rbp, rsp`, followed by
+/// `sub rsp, imm`.
+pub fn prologue(insts: &[WithIp]) -> Prologue {
+    let mut prologue = Prologue {
+        frame: Frame::None,
+        saved: RegSet::EMPTY,
+        stack: 0,
+        len: 0,
+    };
+
+    let mut pushed_rbp = false;
+
+    for inst in insts {
+        match **inst {
+            Inst::Endbr64 | Inst::Endbr32 if prologue.len == 0 => {}
+            Inst::Push(Arg::Reg(Reg::Rbp)) if prologue.frame == Frame::None && !pushed_rbp => {
+                pushed_rbp = true;
+            }
+            Inst::Mov(_, Arg::Reg(Reg::Rbp), Arg::Reg(Reg::Rsp))
+                if pushed_rbp && prologue.frame == Frame::None =>
+            {
+                prologue.frame = Frame::Rbp;
+            }
+            Inst::Push(Arg::Reg(reg)) if prologue.stack == 0 => {
+                prologue.saved = prologue.saved.with(reg);
+            }
+            Inst::Sub(_, Arg::Reg(Reg::Rsp), imm) => match stack_imm(imm) {
+                Some(imm) => prologue.stack += imm,
+                None => break,
+            },
+            _ => break,
+        }
+
+        prologue.len += inst.len();
+    }
+
+    // rbp is only a callee saved register if it is not the frame pointer
+    if pushed_rbp && prologue.frame == Frame::None {
+        prologue.saved = prologue.saved.with(Reg::Rbp);
+    }
+
+    prologue
+}
+
+/// Returns the epilogue of the function ending with `insts`, in order, which end with a return.
+///
+/// Recognises `add rsp, imm`, followed by callee saved pops, before or after `leave`, or
+/// `mov rsp, rbp; pop rbp`.
+pub fn epilogue(insts: &[WithIp]) -> Option<Epilogue> {
+    let (ret, rest) = insts.split_last()?;
+
+    if !matches!(**ret, Inst::Ret) {
+        return None;
+    }
+
+    let mut epilogue = Epilogue {
+        frame: Frame::None,
+        restored: RegSet::EMPTY,
+        stack: 0,
+        len: ret.len(),
+    };
+
+    let mut popped_rbp = false;
+
+    // walk backwards from the return
+    for inst in rest.iter().rev() {
+        match **inst {
+            Inst::Leave if epilogue.frame == Frame::None && !popped_rbp => {
+                epilogue.frame = Frame::Rbp;
+            }
+            Inst::Pop(Reg::Rbp) if epilogue.frame == Frame::None && !popped_rbp => {
+                popped_rbp = true;
+            }
+            Inst::Mov(_, Arg::Reg(Reg::Rsp), Arg::Reg(Reg::Rbp))
+                if popped_rbp && epilogue.frame == Frame::None =>
+            {
+                epilogue.frame = Frame::Rbp;
+            }
+            Inst::Pop(reg) if epilogue.stack == 0 => {
+                epilogue.restored = epilogue.restored.with(reg);
+            }
+            Inst::Add(_, Arg::Reg(Reg::Rsp), imm) => match stack_imm(imm) {
+                Some(imm) => epilogue.stack += imm,
+                None => break,
+            },
+            _ => break,
+        }
+
+        epilogue.len += inst.len();
+    }
+
+    if popped_rbp && epilogue.frame == Frame::None {
+        epilogue.restored = epilogue.restored.with(Reg::Rbp);
+    }
+
+    Some(epilogue)
+}
+
+/// Maximum number of entries of a jump table, see [`jump_table`].
+#[cfg(feature = "alloc")]
+const MAX_JUMP_TABLE_LEN: usize = 4096;
+
+/// A jump table, the targets of an indirect `jmp` implementing a `switch`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct JumpTable {
+    /// Address of the `jmp`.
+    pub jmp: usize,
+    /// Address of the table.
+    pub table: usize,
+    /// Targets of the `jmp`, successors of its block, in the order of the table.
+    pub targets: Vec<usize>,
+}
+
+/// Returns the jump table of the `jmp reg` ending `insts`, in order, reading each entry of the
+/// table with `read`, which fills the buffer with the bytes at an address, returning `false` if
+/// they cannot be read.
+///
+/// Recognises the position independent idiom of entries relative to the table, within the block
+/// of the `jmp`, bounded by `cmp` of the index and `ja` or `jae` ending the block before it.
+/// Tables of more than 4096 entries are not recognised.
+///
+/// ```text
+/// cmp ecx, imm
+/// ja default
+/// lea rdx, [rip + table]
+/// movsxd rax, dword [rdx + rcx * 4]
+/// add rax, rdx
+/// jmp rax
+/// ```
+#[cfg(feature = "alloc")]
+pub fn jump_table<F>(insts: &[WithIp], mut read: F) -> Option<JumpTable>
+where
+    F: FnMut(usize, &mut [u8; 4]) -> bool,
+{
+    let (jmp, rest) = insts.split_last()?;
+
+    let Inst::Jmp(Arg::Reg(reg)) = **jmp else {
+        return None;
+    };
+
+    let start = rest.iter().rposition(|inst| inst.is_terminator())?;
+    let mut block = rest[start + 1..].iter().rev();
+
+    let base = block.find_map(|inst| match **inst {
+        Inst::Add(Size::Qword, Arg::Reg(dst), Arg::Reg(src)) if dst == reg => Some(src),
+        Inst::Add(Size::Qword, Arg::Reg(dst), Arg::Reg(src)) if src == reg => Some(dst),
+        _ => None,
+    })?;
+
+    let index = block.find_map(|inst| match **inst {
+        Inst::Movsx(Size::Qword, dst, Size::Dword, Arg::Mem(mem))
+            if dst == reg
+                && mem.base == Some(Base::Reg(base))
+                && mem.scale == 4
+                && mem.disp == 0 =>
+        {
+            mem.index
+        }
+        _ => None,
+    })?;
+
+    let table = block.find_map(|inst| match (**inst, inst.target()) {
+        (Inst::Lea(Size::Qword, dst, _), Target::RipRelativeMem(addr)) if dst == base => Some(addr),
+        _ => None,
+    })?;
+
+    let Inst::Cmp(_, Arg::Reg(bounded), imm) = **rest.get(start.checked_sub(1)?)? else {
+        return None;
+    };
+
+    if bounded != index {
+        return None;
+    }
+
+    let bound = usize::try_from(imm.imm()?).ok()?;
+
+    let len = match *rest[start] {
+        Inst::Jcc(Cond::A, _) | Inst::JccShort(Cond::A, _) => bound.checked_add(1)?,
+        Inst::Jcc(Cond::Ae, _) | Inst::JccShort(Cond::Ae, _) => bound,
+        _ => return None,
+    };
+
+    if len > MAX_JUMP_TABLE_LEN {
+        return None;
+    }
+
+    let mut targets = Vec::new();
+    let mut entry = [0; 4];
+
+    for index in 0..len {
+        if !read(table.wrapping_add(index.checked_mul(4)?), &mut entry) {
+            return None;
+        }
+
+        let rel = i32::from_le_bytes(entry) as isize;
+
+        targets.push(table.wrapping_add_signed(rel));
+    }
+
+    Some(JumpTable {
+        jmp: jmp.ip(),
+        table,
+        targets,
+    })
+}
+
+/// Returns the jump tables of `insts`, in order, see [`jump_table`].
+#[cfg(feature = "alloc")]
+pub fn jump_tables<F>(insts: &[WithIp], mut read: F) -> Vec<JumpTable>
+where
+    F: FnMut(usize, &mut [u8; 4]) -> bool,
+{
+    (0..insts.len())
+        .filter(|&index| matches!(*insts[index], Inst::Jmp(Arg::Reg(_))))
+        .filter_map(|index| jump_table(&insts[..=index], &mut read))
+        .collect()
+}
+
+/// Returns the instructions of `insts` whose relative target, or address referenced by a `rip`
+/// relative memory operand, is in `addrs`, `addr..=addr` for a single address.
+pub fn xrefs_to<R>(addrs: R, insts: &[WithIp]) -> impl Iterator<Item = &WithIp>
+where
+    R: ops::RangeBounds<usize>,
+{
+    insts
+        .iter()
+        .filter(move |inst| inst.abs_addr().is_some_and(|addr| addrs.contains(&addr)))
+}
+
+/// Returns the data addresses referenced by `rip` relative memory operands of `insts`, other than
+/// those of branches, with the instruction referencing each.
+///
+/// [`Inst::mem_access`] returns how the data is accessed, if it is not only the address taken by
+/// `lea`.
+pub fn data_refs(insts: &[WithIp]) -> impl Iterator<Item = (usize, &WithIp)> {
+    insts.iter().filter_map(|inst| match inst.target() {
+        Target::RipRelativeMem(addr) if !inst.is_branch() => Some((addr, inst)),
+        _ => None,
+    })
+}
+
+/// Returns the addresses of the instructions of `insts` referencing a string, with the string,
+/// read with `read`, which fills the buffer with the bytes at an address, returning the number
+/// filled, fewer at the end of the data.
+///
+/// Strings are NUL terminated UTF-8 of printable characters and whitespace, at most 4096 bytes
+/// long, see [`data_refs`].
+#[cfg(feature = "alloc")]
+pub fn string_refs<F>(insts: &[WithIp], mut read: F) -> Vec<(usize, String)>
+where
+    F: FnMut(usize, &mut [u8]) -> usize,
+{
+    data_refs(insts)
+        .filter_map(|(addr, inst)| Some((inst.ip(), read_str(addr, &mut read)?)))
+        .collect()
+}
+
+/// Returns the string at `addr`, read with `read`, see [`string_refs`].
+#[cfg(feature = "alloc")]
+fn read_str<F>(addr: usize, read: &mut F) -> Option<String>
+where
+    F: FnMut(usize, &mut [u8]) -> usize,
+{
+    const MAX_LEN: usize = 4096;
+
+    let mut bytes = Vec::new();
+    let mut buf = [0; 64];
+
+    loop {
+        let len = read(addr.wrapping_add(bytes.len()), &mut buf).min(buf.len());
+        let chunk = &buf[..len];
+
+        if let Some(end) = chunk.iter().position(|&byte| byte == 0) {
+            bytes.extend_from_slice(&chunk[..end]);
+
+            break;
+        }
+
+        // unterminated
+        if len == 0 || bytes.len() + len > MAX_LEN {
+            return None;
+        }
+
+        bytes.extend_from_slice(chunk);
+    }
+
+    let string = String::from_utf8(bytes).ok()?;
+    let printable = string
+        .chars()
+        .all(|char| !char.is_control() || matches!(char, '\t' | '\n' | '\r'));
+
+    (!string.is_empty() && printable).then_some(string)
+}
+
+/// Known constant values of the general purpose registers, those holding a single value as
+/// tracked by [`RegValues`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Consts {
+    values: RegValues,
+}
+
+impl Consts {
+    /// No known values.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            values: RegValues::UNKNOWN,
+        }
+    }
+
+    /// Returns the value of `reg`, if it is known.
+    #[inline]
+    pub const fn get(&self, reg: Reg) -> Option<usize> {
+        match self.values.get(reg) {
+            ValueSet {
+                values: [value, ..],
+                len: 1,
+            } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Update the values with the effect of `inst`, a `call` clobbering the volatile registers of
+    /// `abi`, see [`RegValues::step`].
+    #[inline]
+    pub fn step(&mut self, inst: &WithIp, abi: Abi) {
+        self.values.step(inst, abi);
+    }
+}
+
+impl Default for Consts {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the `call reg` and `jmp reg` of `block`, a basic block in order, whose target is known
+/// from constants, with their target, a `call` clobbering the volatile registers of `abi`.
+pub fn indirect_targets(block: &[WithIp], abi: Abi) -> impl Iterator<Item = (&WithIp, usize)> {
+    let mut consts = Consts::new();
+
+    block.iter().filter_map(move |inst| {
+        let target = match (inst.kind(), inst.target()) {
+            (Kind::Call | Kind::Jmp, Target::Register(reg)) => consts.get(reg),
+            _ => None,
+        };
+
+        consts.step(inst, abi);
+
+        Some((inst, target?))
+    })
+}
+
+/// The values a register may hold, unknown if there are more than [`ValueSet::MAX`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ValueSet {
+    values: [usize; ValueSet::MAX],
+    /// Number of values, greater than `MAX` if they are unknown.
+    len: usize,
+}
+
+impl ValueSet {
+    /// Maximum number of values tracked.
+    pub const MAX: usize = 8;
+
+    /// Unknown values.
+    pub const UNKNOWN: Self = Self {
+        values: [0; Self::MAX],
+        len: Self::MAX + 1,
+    };
+
+    /// No values, as of a register which is never reached.
+    pub const EMPTY: Self = Self {
+        values: [0; Self::MAX],
+        len: 0,
+    };
+
+    /// Construct the set of only `value`.
+    #[inline]
+    pub const fn of(value: usize) -> Self {
+        let mut values = [0; Self::MAX];
+
+        values[0] = value;
+
+        Self { values, len: 1 }
+    }
+
+    /// Returns the values in ascending order, if they are known.
+    #[inline]
+    pub fn values(&self) -> Option<&[usize]> {
+        self.values.get(..self.len)
+    }
+
+    /// Returns `true` if the values are unknown.
+    #[inline]
+    pub const fn is_unknown(&self) -> bool {
+        self.len > Self::MAX
+    }
+
+    /// Returns the values in either set.
+    pub fn union(self, other: Self) -> Self {
+        let (Some(values), Some(other)) = (self.values(), other.values()) else {
+            return Self::UNKNOWN;
+        };
+
+        values
+            .iter()
+            .chain(other)
+            .fold(Self::EMPTY, |set, &value| set.with(value))
+    }
+
+    /// Returns this set with `value` added.
+    pub fn with(mut self, value: usize) -> Self {
+        let Some(values) = self.values() else {
+            return self;
+        };
+
+        let Err(index) = values.binary_search(&value) else {
+            return self;
+        };
+
+        if self.len == Self::MAX {
+            return Self::UNKNOWN;
+        }
+
+        self.values.copy_within(index..self.len, index + 1);
+        self.values[index] = value;
+        self.len += 1;
+
+        self
+    }
+
+    /// Returns the result of `f` applied to each value.
+    pub fn map(self, f: impl Fn(usize) -> usize) -> Self {
+        match self.values() {
+            Some(values) => values
+                .iter()
+                .fold(Self::EMPTY, |set, &value| set.with(f(value))),
+            None => Self::UNKNOWN,
+        }
+    }
+
+    /// Returns the result of `f` applied to each pair of values of this set and `other`.
+    pub fn map2(self, other: Self, f: impl Fn(usize, usize) -> usize) -> Self {
+        let Some(other) = other.values() else {
+            return Self::UNKNOWN;
+        };
+
+        other.iter().fold(Self::EMPTY, |set, &rhs| {
+            set.union(self.map(|lhs| f(lhs, rhs)))
+        })
+    }
+}
+
+/// The values each general purpose register may hold at a point of a function.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RegValues {
+    regs: [ValueSet; 16],
+}
+
+impl RegValues {
+    /// Unknown values of every register.
+    pub const UNKNOWN: Self = Self {
+        regs: [ValueSet::UNKNOWN; 16],
+    };
+
+    /// Returns the values `reg` may hold.
+    #[inline]
+    pub const fn get(&self, reg: Reg) -> ValueSet {
+        self.regs[reg.bits() as usize]
+    }
+
+    /// Returns the addresses the memory operand of `inst` may reference, such as the slot of a
+    /// `call [rax + 0x18]` through a vtable.
+    pub fn mem_addrs(&self, inst: &WithIp) -> ValueSet {
+        match (inst.target(), inst.mem_access()) {
+            (Target::RipRelativeMem(addr), _) => ValueSet::of(addr),
+            (Target::Memory(mem), _) | (_, Some(MemAccess { mem, .. })) => {
+                self.addrs(mem, inst.next_ip())
+            }
+            _ => ValueSet::UNKNOWN,
+        }
+    }
+
+    /// Returns the addresses `mem`, an operand of the instruction followed by `next_ip`, may
+    /// reference.
+    fn addrs(&self, mem: Mem, next_ip: usize) -> ValueSet {
+        let disp = mem.disp as isize;
+
+        let base = match mem.base {
+            Some(Base::Reg(reg)) => self.get(reg),
+            Some(Base::Rip) => ValueSet::of(next_ip),
+            None => ValueSet::of(0),
+        };
+
+        let index = match mem.index {
+            Some(reg) => self
+                .get(reg)
+                .map(|index| index.wrapping_mul(mem.scale as usize)),
+            None => ValueSet::of(0),
+        };
+
+        base.map2(index, usize::wrapping_add)
+            .map(|addr| addr.wrapping_add_signed(disp))
+    }
+
+    /// Returns the values of either.
+    pub fn union(mut self, other: Self) -> Self {
+        for (reg, other) in self.regs.iter_mut().zip(other.regs) {
+            *reg = reg.union(other);
+        }
+
+        self
+    }
+
+    /// Update the values with the effect of `inst`, a `call` clobbering the volatile registers of
+    /// `abi`.
+    ///
+    /// Tracks `mov` of an immediate or register, `lea`, `xor` of a register with itself, and
+    /// `add` and `sub` of an immediate.
+    pub fn step(&mut self, inst: &WithIp, abi: Abi) {
+        let imm = |arg: Arg| arg.imm().map(|imm| imm as usize);
+
+        let def = match **inst {
+            Inst::Mov(size, Arg::Reg(dst), src) => {
+                let values = match src {
+                    Arg::Reg(src) => self.get(src),
+                    src => imm(src).map_or(ValueSet::UNKNOWN, ValueSet::of),
+                };
+
+                Some((size, dst, values))
+            }
+            Inst::Lea(size, dst, Arg::Mem(mem)) => {
+                Some((size, dst, self.addrs(mem, inst.next_ip())))
+            }
+            Inst::Xor(size, Arg::Reg(dst), Arg::Reg(src)) if dst == src => {
+                Some((size, dst, ValueSet::of(0)))
+            }
+            Inst::Add(size, Arg::Reg(dst), src) => {
+                let values = match imm(src) {
+                    Some(imm) => self.get(dst).map(|value| value.wrapping_add(imm)),
+                    None => ValueSet::UNKNOWN,
+                };
+
+                Some((size, dst, values))
+            }
+            Inst::Sub(size, Arg::Reg(dst), src) => {
+                let values = match imm(src) {
+                    Some(imm) => self.get(dst).map(|value| value.wrapping_sub(imm)),
+                    None => ValueSet::UNKNOWN,
+                };
+
+                Some((size, dst, values))
+            }
+            _ => None,
+        };
+
+        for reg in inst.regs_written() {
+            self.regs[reg.bits() as usize] = ValueSet::UNKNOWN;
+        }
+
+        if inst.is_call() {
+            for reg in Reg::iter().filter(|reg| reg.is_volatile(abi)) {
+                self.regs[reg.bits() as usize] = ValueSet::UNKNOWN;
+            }
+        }
+
+        let Some((size, reg, values)) = def else {
+            return;
+        };
+
+        // a 32-bit result is zero extended, a narrower one merges with the rest of the register
+        let values = match size {
+            Size::Qword => values,
+            Size::Dword => values.map(|value| value as u32 as usize),
+            _ => ValueSet::UNKNOWN,
+        };
+
+        self.regs[reg.bits() as usize] = values;
+    }
+}
+
+/// Returns the values each general purpose register may hold before each instruction of
+/// `insts`, the instructions of a function in order, a `call` clobbering the volatile registers
+/// of `abi`, and each of `tables` reaching its targets, as by [`jump_tables`].
+///
+/// Values are unknown on entry, and joined where control flow merges, see
+/// [`RegValues::step`]. Instructions which are never reached hold no values.
+#[cfg(feature = "alloc")]
+pub fn value_sets(insts: &[WithIp], abi: Abi, tables: &[JumpTable]) -> Vec<RegValues> {
+    let blocks = basic_blocks_with_tables(insts, tables);
+    let unreached = RegValues {
+        regs: [ValueSet::EMPTY; 16],
+    };
+
+    let mut entries = Vec::from_iter((0..blocks.len()).map(|_| unreached));
+    let mut queue = Vec::new();
+
+    if let Some(entry) = entries.first_mut() {
+        *entry = RegValues::UNKNOWN;
+        queue.push(0);
+    }
+
+    // value sets only grow, until each is unknown
+    while let Some(index) = queue.pop() {
+        let block = &blocks[index];
+        let mut values = entries[index];
+
+        for inst in &insts[block.insts.clone()] {
+            values.step(inst, abi);
+        }
+
+        for succ in &block.succs {
+            let Ok(succ) = blocks.binary_search_by_key(succ, |block| block.range.start) else {
+                continue;
+            };
+
+            let joined = entries[succ].union(values);
+
+            if joined != entries[succ] {
+                entries[succ] = joined;
+                queue.push(succ);
+            }
+        }
+    }
+
+    let mut points = Vec::with_capacity(insts.len());
+
+    for (block, mut values) in blocks.iter().zip(entries) {
+        if values == unreached {
+            points.extend(block.insts.clone().map(|_| unreached));
+
+            continue;
+        }
+
+        for inst in &insts[block.insts.clone()] {
+            points.push(values);
+            values.step(inst, abi);
+        }
+    }
+
+    points
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const IP: usize = 0x1000;
+
+    /// Decode `bytes` at [`IP`] in long mode.
+    fn insts(bytes: &[u8]) -> Vec<WithIp> {
+        InstIter::from_bytes(IP, bytes, Mode::Long64).collect()
+    }
+
+    /// A chain of `jmp`s, one looping forever, and a `je` into the chain.
+    const JMP_CHAIN: &[u8] = &[
+        0xEB, 0x02, // jmp 0x1004
+        0xEB, 0xFE, // 0x1002: jmp 0x1002
+        0xEB, 0x01, // 0x1004: jmp 0x1007
+        0x90, // nop
+        0xC3, // 0x1007: ret
+        0x74, 0xF6, // je 0x1000
+    ];
+
+    #[test]
+    fn jmp_chain_ends() {
+        let insts = insts(JMP_CHAIN);
+
+        assert_eq!(jmp_chain_end(&insts, 0x1000), Some(0x1007));
+        assert_eq!(jmp_chain_end(&insts, 0x1002), None);
+        assert_eq!(jmp_chain_end(&insts, 0x1006), Some(0x1006));
+        assert_eq!(jmp_chain_end(&insts, 0x2000), Some(0x2000));
+    }
+
+    #[test]
+    fn collapse_jmp_chains_retargets_branches() {
+        let mut insts = insts(JMP_CHAIN);
+
+        assert_eq!(collapse_jmp_chains(&mut insts), Ok(2));
+        assert_eq!(insts[0].target(), Target::Absolute(0x1007));
+        assert_eq!(insts[1].target(), Target::Absolute(0x1002));
+        assert_eq!(insts[2].target(), Target::Absolute(0x1007));
+        assert_eq!(insts[5].target(), Target::Absolute(0x1007));
+        assert!(insts[5].is_conditional());
+    }
+
+    #[test]
+    fn import_slots() {
+        // jmp [rip + 0x2fe2]
+        let plt = [0xFF, 0x25, 0xE2, 0x2F, 0x00, 0x00];
+        // endbr64; bnd jmp [rip + 0x2fe2]
+        let ibt = [
+            0xF3, 0x0F, 0x1E, 0xFA, 0xF2, 0xFF, 0x25, 0xE2, 0x2F, 0x00, 0x00,
+        ];
+        // jmp [0x402000]
+        let iat = [0xFF, 0x25, 0x00, 0x20, 0x40, 0x00];
+        // jmp 0x1006
+        let direct = [0xE9, 0x01, 0x00, 0x00, 0x00];
+
+        assert_eq!(import_slot(&plt, IP, Mode::Long64), Some(0x3FE8));
+        assert_eq!(import_slot(&ibt, IP, Mode::Long64), Some(0x3FED));
+        assert_eq!(import_slot(&iat, IP, Mode::Compat32), Some(0x402000));
+        assert_eq!(import_slot(&direct, IP, Mode::Long64), None);
+    }
+
+    #[test]
+    fn basic_blocks_of_diamond() {
+        let insts = insts(&[
+            0x85, 0xFF, // test edi, edi
+            0x74, 0x07, // je 0x100B
+            0xB8, 0x01, 0x00, 0x00, 0x00, // mov eax, 1
+            0xEB, 0x02, // jmp 0x100D
+            0x31, 0xC0, // 0x100B: xor eax, eax
+            0xC3, // 0x100D: ret
+        ]);
+
+        let blocks = basic_blocks(&insts);
+
+        assert_eq!(
+            blocks,
+            [
+                Block {
+                    range: 0x1000..0x1004,
+                    insts: 0..2,
+                    succs: vec![0x100B, 0x1004],
+                },
+                Block {
+                    range: 0x1004..0x100B,
+                    insts: 2..4,
+                    succs: vec![0x100D],
+                },
+                Block {
+                    range: 0x100B..0x100D,
+                    insts: 4..5,
+                    succs: vec![0x100D],
+                },
+                Block {
+                    range: 0x100D..0x100E,
+                    insts: 5..6,
+                    succs: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn basic_blocks_split_where_not_contiguous() {
+        let mut insts = insts(&[0x31, 0xC0]); // xor eax, eax
+
+        insts.extend(InstIter::from_bytes(0x2000, &[0xC3], Mode::Long64)); // ret
+
+        let blocks: Vec<_> = basic_blocks(&insts)
+            .into_iter()
+            .map(|block| (block.range, block.succs))
+            .collect();
+
+        assert_eq!(
+            blocks,
+            [(0x1000..0x1002, vec![0x1002]), (0x2000..0x2001, vec![])]
+        );
+    }
+
+    #[test]
+    fn call_graph_of_section() {
+        let mut bytes = vec![
+            0xE8, 0x0B, 0x00, 0x00, 0x00, // call 0x1010
+            0xFF, 0xD0, // 0x1005: call rax
+            0xE9, 0x14, 0x00, 0x00, 0x00, // jmp 0x1020, a tail call
+            0xCC, 0xCC, 0xCC, 0xCC, // padding
+            0xE8, 0xEB, 0x3F, 0x00, 0x00, // 0x1010: call 0x5000
+            0xC3, // ret
+        ];
+
+        bytes.extend([0xCC; 10]);
+        bytes.push(0xC3); // 0x1020: ret
+
+        let insts = insts(&bytes);
+        let graph = call_graph(&insts);
+
+        let calls = BTreeMap::from([
+            (0x1000, BTreeSet::from([0x1010, 0x1020])),
+            (0x1010, BTreeSet::from([0x5000])),
+            (0x1020, BTreeSet::new()),
+        ]);
+
+        assert_eq!(graph.calls, calls);
+        assert_eq!(graph.indirect, BTreeMap::from([(0x1000, vec![0x1005])]));
+    }
+
+    #[test]
+    fn functions_at_calls_padding_and_endbr() {
+        let mut bytes = vec![
+            0xE8, 0x0B, 0x00, 0x00, 0x00, // call 0x1010
+            0xC3, // ret
+        ];
+
+        bytes.extend([0xCC; 10]);
+        bytes.extend([
+            0x31, 0xC0, // 0x1010: xor eax, eax
+            0xC3, // ret
+            0x0F, 0x1F, 0x00, // nop
+            0x55, // 0x1016: push rbp
+            0xC3, // ret
+            0xF3, 0x0F, 0x1E, 0xFA, // 0x1018: endbr64
+            0xC3, // ret
+        ]);
+
+        let insts = insts(&bytes);
+
+        assert_eq!(
+            functions(&insts),
+            [
+                0x1000..0x1006,
+                0x1010..0x1013,
+                0x1016..0x1018,
+                0x1018..0x101D,
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_without_padding_after_ret() {
+        // a nop not following a return or jmp is part of the function
+        let insts = insts(&[
+            0x31, 0xC0, // xor eax, eax
+            0x90, // nop
+            0x85, 0xC0, // test eax, eax
+            0xC3, // ret
+        ]);
+
+        let functions = functions(&insts);
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0], 0x1000..0x1006);
+    }
+
+    /// A `jmp` at `ip` to `target`.
+    fn jmp(ip: usize, target: usize) -> WithIp {
+        let inst = Inst::Jmp(Arg::Imm32(0))
+            .with_target(target, ip, Mode::Long64)
+            .unwrap();
+
+        WithIp::new(ip, inst, Mode::Long64).unwrap()
+    }
+
+    #[test]
+    fn tail_calls() {
+        let functions = [0x1000..0x1010, 0x2000..0x2010];
+
+        // within the function
+        assert!(!is_tail_call(&jmp(0x1000, 0x1008), &functions));
+        // to another function, or outside of any
+        assert!(is_tail_call(&jmp(0x1008, 0x2000), &functions));
+        assert!(is_tail_call(&jmp(0x1008, 0x5000), &functions));
+        // from outside of any function, only to the start of one
+        assert!(is_tail_call(&jmp(0x3000, 0x2000), &functions));
+        assert!(!is_tail_call(&jmp(0x3000, 0x2004), &functions));
+    }
+
+    #[test]
+    fn functions_at_tail_call_targets() {
+        let insts = insts(&[
+            0xEB, 0x08, // jmp 0x100A, a tail call
+            0xCC, 0xCC, // padding
+            0x31, 0xC0, // 0x1004: xor eax, eax
+            0x31, 0xC9, // xor ecx, ecx
+            0x31, 0xD2, // xor edx, edx
+            0x31, 0xDB, // 0x100A: xor ebx, ebx
+            0xC3, // ret
+        ]);
+
+        assert_eq!(
+            functions(&insts),
+            [0x1000..0x1002, 0x1004..0x100A, 0x100A..0x100D]
+        );
+    }
+
+    #[test]
+    fn prologue_with_frame() {
+        let insts = insts(&[
+            0x55, // push rbp
+            0x48, 0x89, 0xE5, // mov rbp, rsp
+            0x53, // push rbx
+            0x48, 0x83, 0xEC, 0x18, // sub rsp, 0x18
+            0x31, 0xC0, // xor eax, eax
+        ]);
+
+        let expected = Prologue {
+            frame: Frame::Rbp,
+            saved: RegSet::of(Reg::Rbx),
+            stack: 0x18,
+            len: 9,
+        };
+
+        assert_eq!(prologue(&insts), expected);
+    }
+
+    #[test]
+    fn epilogue_with_leave() {
+        let insts = insts(&[
+            0x48, 0x83, 0xC4, 0x18, // add rsp, 0x18
+            0x5B, // pop rbx
+            0xC9, // leave
+            0xC3, // ret
+        ]);
+
+        let expected = Epilogue {
+            frame: Frame::Rbp,
+            restored: RegSet::of(Reg::Rbx),
+            stack: 0x18,
+            len: 7,
+        };
+
+        assert_eq!(insts.len(), 4);
+        assert_eq!(epilogue(&insts), Some(expected));
+    }
+
+    #[test]
+    fn epilogue_without_frame() {
+        let insts = insts(&[
+            0x5D, // pop rbp
+            0x41, 0x5C, // pop r12
+            0xC3, // ret
+        ]);
+
+        let expected = Epilogue {
+            frame: Frame::None,
+            restored: RegSet::of(Reg::Rbp).with(Reg::R12),
+            stack: 0,
+            len: 4,
+        };
+
+        assert_eq!(epilogue(&insts), Some(expected));
+        assert_eq!(epilogue(&insts[..2]), None);
+    }
+
+    /// Address of the table of [`SWITCH`].
+    const TABLE: usize = 0x1030;
+
+    /// A `switch` of three cases through a jump table at [`TABLE`], see [`read_table`].
+    const SWITCH: &[u8] = &[
+        0x83, 0xFF, 0x02, // cmp edi, 2
+        0x77, 0x22, // ja default
+        0x48, 0x8D, 0x15, 0x24, 0x00, 0x00, 0x00, // lea rdx, [rip + table]
+        0x48, 0x63, 0x04, 0xBA, // movsxd rax, dword [rdx + rdi * 4]
+        0x48, 0x01, 0xD0, // add rax, rdx
+        0xFF, 0xE0, // jmp rax
+        0xB8, 0x00, 0x00, 0x00, 0x00, // 0x1015: mov eax, 0
+        0xC3, // ret
+        0xB8, 0x01, 0x00, 0x00, 0x00, // 0x101B: mov eax, 1
+        0xC3, // ret
+        0xB8, 0x02, 0x00, 0x00, 0x00, // 0x1021: mov eax, 2
+        0xC3, // ret
+        0x31, 0xC0, // 0x1027: default: xor eax, eax
+        0xC3, // ret
+    ];
+
+    /// Read the table of [`SWITCH`].
+    fn read_table(addr: usize, entry: &mut [u8; 4]) -> bool {
+        let table: [i32; 3] = [0x1015 - 0x1030, 0x101B - 0x1030, 0x1021 - 0x1030];
+
+        let Some(rel) = addr
+            .checked_sub(TABLE)
+            .and_then(|offset| table.get(offset / 4))
+        else {
+            return false;
+        };
+
+        *entry = rel.to_le_bytes();
+
+        true
+    }
+
+    #[test]
+    fn jump_table_of_switch() {
+        let insts = insts(SWITCH);
+        let tables = jump_tables(&insts, read_table);
+
+        assert_eq!(
+            tables,
+            [JumpTable {
+                jmp: 0x1013,
+                table: TABLE,
+                targets: vec![0x1015, 0x101B, 0x1021],
+            }]
+        );
+    }
+
+    #[test]
+    fn basic_blocks_of_switch() {
+        let insts = insts(SWITCH);
+        let tables = jump_tables(&insts, read_table);
+        let blocks = basic_blocks_with_tables(&insts, &tables);
+
+        let ranges: Vec<_> = blocks.iter().map(|block| block.range.clone()).collect();
+        let succs: Vec<_> = blocks.iter().map(|block| block.succs.as_slice()).collect();
+
+        assert_eq!(
+            ranges,
+            [
+                0x1000..0x1005,
+                0x1005..0x1015,
+                0x1015..0x101B,
+                0x101B..0x1021,
+                0x1021..0x1027,
+                0x1027..0x102A,
+            ]
+        );
+
+        assert_eq!(
+            succs,
+            [
+                &[0x1027, 0x1005][..],
+                &[0x1015, 0x101B, 0x1021],
+                &[],
+                &[],
+                &[],
+                &[],
+            ]
+        );
+
+        // without the table, the cases are not successors of the jmp
+        assert_eq!(basic_blocks(&insts)[1].succs, []);
+    }
+
+    #[test]
+    fn value_sets_reach_switch_cases() {
+        let insts = insts(SWITCH);
+        let tables = jump_tables(&insts, read_table);
+        let points = value_sets(&insts, Abi::SysV, &tables);
+
+        // rdx holds the table in each case
+        assert_eq!(points[6].get(Reg::Rdx).values(), Some(&[TABLE][..]));
+        assert_eq!(points[10].get(Reg::Rdx).values(), Some(&[TABLE][..]));
+
+        let unreached = value_sets(&insts, Abi::SysV, &[]);
+
+        assert_eq!(unreached[6].get(Reg::Rdx).values(), Some(&[][..]));
+    }
+
+    /// Branches to and references of data by `rip` relative operands.
+    const REFS: &[u8] = &[
+        0xE8, 0x0B, 0x00, 0x00, 0x00, // call 0x1010
+        0x48, 0x8D, 0x05, 0xF4, 0x0F, 0x00, 0x00, // 0x1005: lea rax, [rip + 0xff4], 0x2000
+        0xEB, 0x02, // 0x100C: jmp 0x1010
+        0x90, 0x90, // nop
+        0x48, 0x8B, 0x05, 0xF1, 0x0F, 0x00, 0x00, // 0x1010: mov rax, [rip + 0xff1], 0x2008
+        0xFF, 0x15, 0x00, 0x10, 0x00, 0x00, // 0x1017: call [rip + 0x1000], 0x201D
+        0xC3, // ret
+    ];
+
+    #[test]
+    fn xrefs_to_addresses() {
+        let insts = insts(REFS);
+
+        fn ips<'a>(insts: impl Iterator<Item = &'a WithIp>) -> Vec<usize> {
+            insts.map(|inst| inst.ip()).collect()
+        }
+
+        assert_eq!(ips(xrefs_to(0x1010..=0x1010, &insts)), [0x1000, 0x100C]);
+        assert_eq!(ips(xrefs_to(0x2000..0x2010, &insts)), [0x1005, 0x1010]);
+        assert_eq!(ips(xrefs_to(0x2000.., &insts)), [0x1005, 0x1010, 0x1017]);
+        assert_eq!(ips(xrefs_to(0x3000.., &insts)), []);
+    }
+
+    #[test]
+    fn data_refs_exclude_branches() {
+        let insts = insts(REFS);
+        let refs: Vec<_> = data_refs(&insts)
+            .map(|(addr, inst)| (addr, inst.ip()))
+            .collect();
+
+        assert_eq!(refs, [(0x2000, 0x1005), (0x2008, 0x1010)]);
+
+        // lea only takes the address, mov reads it
+        let access = |index: usize| insts[index].mem_access().map(|access| access.size);
+
+        assert_eq!(access(1), None);
+        assert_eq!(access(5), Some(Some(8)));
+    }
+
+    #[test]
+    fn string_refs_read_strings() {
+        const DATA: usize = 0x2000;
+
+        let mut data = vec![0; 0x100];
+
+        data[..6].copy_from_slice(b"hello\0");
+        data[0x10..0x13].copy_from_slice(&[0x01, 0x02, 0x00]);
+        // longer than a single read
+        data[0x20..0x84].fill(b'a');
+        // unterminated at the end of the data
+        data[0xF0..].fill(b'b');
+
+        let read = |addr: usize, buf: &mut [u8]| {
+            let Some(rest) = addr.checked_sub(DATA).and_then(|offset| data.get(offset..)) else {
+                return 0;
+            };
+
+            let len = rest.len().min(buf.len());
+
+            buf[..len].copy_from_slice(&rest[..len]);
+
+            len
+        };
+
+        let insts = insts(&[
+            0x48, 0x8D, 0x3D, 0xF9, 0x0F, 0x00, 0x00, // lea rdi, [rip + 0xff9], 0x2000
+            0x48, 0x8D, 0x3D, 0x02, 0x10, 0x00, 0x00, // lea rdi, [rip + 0x1002], 0x2010
+            0x48, 0x8D, 0x3D, 0x0B, 0x10, 0x00, 0x00, // lea rdi, [rip + 0x100b], 0x2020
+            0x48, 0x8D, 0x3D, 0xD4, 0x10, 0x00, 0x00, // lea rdi, [rip + 0x10d4], 0x20F0
+            0xC3, // ret
+        ]);
+
+        let long = String::from_utf8(vec![b'a'; 100]).unwrap();
+
+        assert_eq!(
+            string_refs(&insts, read),
+            [(0x1000, String::from("hello")), (0x100E, long)]
+        );
+    }
+
+    #[test]
+    fn indirect_targets_of_block() {
+        let insts = insts(&[
+            0x48, 0x8D, 0x05, 0xF9, 0x1F, 0x00, 0x00, // lea rax, [rip + 0x1ff9], 0x3000
+            0xFF, 0xD0, // 0x1007: call rax
+            0xB9, 0x00, 0x40, 0x00, 0x00, // mov ecx, 0x4000
+            0xFF, 0xD1, // 0x100E: call rcx
+            0xFF, 0xD0, // 0x1010: call rax, clobbered by the first call
+            0xBB, 0x00, 0x50, 0x00, 0x00, // mov ebx, 0x5000
+            0x48, 0x89, 0xDA, // mov rdx, rbx
+            0xFF, 0xE2, // 0x101A: jmp rdx
+        ]);
+
+        let targets: Vec<_> = indirect_targets(&insts, Abi::SysV)
+            .map(|(inst, target)| (inst.ip(), target))
+            .collect();
+
+        assert_eq!(
+            targets,
+            [(0x1007, 0x3000), (0x100E, 0x4000), (0x101A, 0x5000)]
+        );
+    }
+
+    #[test]
+    fn consts_of_single_values() {
+        let insts = insts(&[
+            0xB8, 0x01, 0x00, 0x00, 0x00, // mov eax, 1
+            0x48, 0x83, 0xC0, 0x02, // add rax, 2
+            0xB0, 0x05, // mov al, 5
+        ]);
+
+        let mut consts = Consts::new();
+
+        consts.step(&insts[0], Abi::SysV);
+        consts.step(&insts[1], Abi::SysV);
+
+        assert_eq!(consts.get(Reg::Rax), Some(3));
+        assert_eq!(consts.get(Reg::Rcx), None);
+
+        // a byte merges with the rest of the register
+        consts.step(&insts[2], Abi::SysV);
+
+        assert_eq!(consts.get(Reg::Rax), None);
+    }
+
+    #[test]
+    fn value_sets_track_arithmetic() {
+        let insts = insts(&[
+            0xBB, 0x00, 0x10, 0x00, 0x00, // mov ebx, 0x1000
+            0x48, 0x8D, 0x43, 0x08, // lea rax, [rbx + 8]
+            0x48, 0x83, 0xC0, 0x10, // add rax, 0x10
+            0x31, 0xC9, // xor ecx, ecx
+            0x48, 0x89, 0xC2, // mov rdx, rax
+            0x48, 0x83, 0xEA, 0x08, // sub rdx, 8
+            0xC3, // ret
+        ]);
+
+        let points = value_sets(&insts, Abi::SysV, &[]);
+        let last = points[6];
+
+        assert_eq!(points.len(), insts.len());
+        assert_eq!(points[2].get(Reg::Rax).values(), Some(&[0x1008][..]));
+        assert_eq!(last.get(Reg::Rbx).values(), Some(&[0x1000][..]));
+        assert_eq!(last.get(Reg::Rax).values(), Some(&[0x1018][..]));
+        assert_eq!(last.get(Reg::Rcx).values(), Some(&[0][..]));
+        assert_eq!(last.get(Reg::Rdx).values(), Some(&[0x1010][..]));
+        assert!(last.get(Reg::Rsi).is_unknown());
+    }
+
+    #[test]
+    fn value_sets_call_clobbers_volatile() {
+        let insts = insts(&[
+            0xBB, 0x01, 0x00, 0x00, 0x00, // mov ebx, 1
+            0xB8, 0x02, 0x00, 0x00, 0x00, // mov eax, 2
+            0xE8, 0x00, 0x00, 0x00, 0x00, // call next
+            0xC3, // ret
+        ]);
+
+        let points = value_sets(&insts, Abi::SysV, &[]);
+
+        assert_eq!(points[2].get(Reg::Rax).values(), Some(&[2][..]));
+        assert_eq!(points[3].get(Reg::Rbx).values(), Some(&[1][..]));
+        assert!(points[3].get(Reg::Rax).is_unknown());
+    }
+
+    #[test]
+    fn value_sets_join_at_merge() {
+        let insts = insts(&[
+            0x31, 0xC0, // xor eax, eax
+            0x85, 0xFF, // test edi, edi
+            0x74, 0x05, // je ret
+            0xB8, 0x01, 0x00, 0x00, 0x00, // mov eax, 1
+            0xC3, // ret
+            0xB9, 0x01, 0x00, 0x00, 0x00, // mov ecx, 1
+        ]);
+
+        let points = value_sets(&insts, Abi::SysV, &[]);
+
+        assert_eq!(points[4].get(Reg::Rax).values(), Some(&[0, 1][..]));
+        // never reached
+        assert_eq!(points[5].get(Reg::Rax).values(), Some(&[][..]));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{Arg, AsmError, Cond, Inst, Mem, Mode, Reg, Size};
+use alloc::vec::Vec;
+
+/// A position in the code being assembled, which may be referenced before it is bound.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Label(usize);
+
+/// An instruction, or a branch whose form is chosen once every label is bound.
+#[derive(Clone, Copy, Debug)]
+enum Item {
+    /// The bytes `start..end` of an instruction, and any displacement to `label` of `width` bytes
+    /// it ends with, relative to its end.
+    Inst {
+        start: usize,
+        end: usize,
+        rel: Option<(usize, Label)>,
+    },
+    /// A `jmp`, or `jcc` if there is a condition, to `label`, which is short if it is in range,
+    /// unless it is `near`.
+    Branch {
+        cond: Option<Cond>,
+        label: Label,
+        near: bool,
+    },
+}
+
+/// Assembler of instructions, branches to labels are patched by [`Assembler::finish`] once every
+/// label is bound.
+///
+/// A `jmp` or `jcc` is short if its label is in range, unless it is near.
+#[derive(Clone, Debug)]
+pub struct Assembler {
+    mode: Mode,
+    bytes: Vec<u8>,
+    items: Vec<Item>,
+    labels: Vec<Option<usize>>,
+}
+
+impl Assembler {
+    /// Assemble for `mode`.
+    #[inline]
+    pub const fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            bytes: Vec::new(),
+            items: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Returns the mode being assembled for.
+    #[inline]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Create a new unbound label.
+    #[inline]
+    pub fn label(&mut self) -> Label {
+        let label = Label(self.labels.len());
+
+        self.labels.push(None);
+
+        label
+    }
+
+    /// Bind `label` to the next instruction.
+    #[inline]
+    pub fn bind(&mut self, label: Label) -> Result<(), AsmError> {
+        match self.labels.get_mut(label.0) {
+            Some(item @ None) => {
+                *item = Some(self.items.len());
+
+                Ok(())
+            }
+            _ => Err(AsmError::LabelBound),
+        }
+    }
+
+    /// Append `inst`.
+    #[inline]
+    pub fn inst(&mut self, inst: Inst) -> Result<(), AsmError> {
+        self.push(inst, None)
+    }
+
+    /// Append a `jmp` to `label`.
+    #[inline]
+    pub fn jmp(&mut self, label: Label) -> Result<(), AsmError> {
+        self.branch(None, label, false)
+    }
+
+    /// Append a near `jmp` to `label`, which may later be retargeted in place.
+    #[inline]
+    pub fn jmp_near(&mut self, label: Label) -> Result<(), AsmError> {
+        self.branch(None, label, true)
+    }
+
+    /// Append a `jcc` to `label`.
+    #[inline]
+    pub fn jcc(&mut self, cond: Cond, label: Label) -> Result<(), AsmError> {
+        self.branch(Some(cond), label, false)
+    }
+
+    /// Append a near `jcc` to `label`, which may later be retargeted in place.
+    #[inline]
+    pub fn jcc_near(&mut self, cond: Cond, label: Label) -> Result<(), AsmError> {
+        self.branch(Some(cond), label, true)
+    }
+
+    /// Append a `call` to `label`.
+    #[inline]
+    pub fn call(&mut self, label: Label) -> Result<(), AsmError> {
+        let size = self.mode.default_size();
+
+        self.push(
+            Inst::Call(crate::rel_arg(self.mode)),
+            Some((size.bytes(), label)),
+        )
+    }
+
+    /// Append a `lea` of the address of `label` into `reg`, which is `rip` relative and only exists
+    /// in long mode.
+    #[inline]
+    pub fn lea(&mut self, size: Size, reg: Reg, label: Label) -> Result<(), AsmError> {
+        let inst = Inst::Lea(size, reg, Arg::Mem(Mem::rip(0)));
+
+        self.push(inst, Some((Size::Dword.bytes(), label)))
+    }
+
+    /// Append `inst`, which ends with a displacement to `rel`, if present.
+    #[inline]
+    fn push(&mut self, inst: Inst, rel: Option<(usize, Label)>) -> Result<(), AsmError> {
+        if let Some((_, label)) = rel {
+            self.check(label)?;
+        }
+
+        let bytes = inst.to_bytes(self.mode)?;
+        let start = self.bytes.len();
+
+        self.bytes.extend_from_slice(&bytes);
+        self.items.push(Item::Inst {
+            start,
+            end: self.bytes.len(),
+            rel,
+        });
+
+        Ok(())
+    }
+
+    /// Append a branch to `label`.
+    #[inline]
+    fn branch(&mut self, cond: Option<Cond>, label: Label, near: bool) -> Result<(), AsmError> {
+        self.check(label)?;
+        self.items.push(Item::Branch { cond, label, near });
+
+        Ok(())
+    }
+
+    /// Returns an error if `label` is not of this assembler.
+    #[inline]
+    fn check(&self, label: Label) -> Result<(), AsmError> {
+        if label.0 < self.labels.len() {
+            Ok(())
+        } else {
+            Err(AsmError::UnboundLabel)
+        }
+    }
+
+    /// Returns the branch `cond` with the relative displacement `rel`, short or near.
+    #[inline]
+    fn branch_inst(&self, cond: Option<Cond>, rel: i32, near: bool) -> Inst {
+        match (cond, near) {
+            (None, false) => Inst::JmpShort(rel as i8),
+            (Some(cond), false) => Inst::JccShort(cond, rel as i8),
+            (None, true) => match crate::rel_arg(self.mode) {
+                Arg::Imm16(_) => Inst::Jmp(Arg::Imm16(rel as i16)),
+                _ => Inst::Jmp(Arg::Imm32(rel)),
+            },
+            (Some(cond), true) => Inst::Jcc(cond, rel),
+        }
+    }
+
+    /// Returns the offset of each item, followed by the end.
+    #[inline]
+    fn offsets(&self, near: &[bool]) -> Result<Vec<usize>, AsmError> {
+        let mut offsets = Vec::with_capacity(self.items.len() + 1);
+        let mut offset = 0;
+
+        for (item, near) in self.items.iter().zip(near) {
+            offsets.push(offset);
+
+            offset += match *item {
+                Item::Inst { start, end, .. } => end - start,
+                Item::Branch { cond, .. } => self.branch_inst(cond, 0, *near).len(self.mode)?,
+            };
+        }
+
+        offsets.push(offset);
+
+        Ok(offsets)
+    }
+
+    /// Returns the offset `label` is bound to.
+    #[inline]
+    fn target(&self, offsets: &[usize], label: Label) -> Result<usize, AsmError> {
+        match self.labels[label.0] {
+            Some(item) => Ok(offsets[item]),
+            None => Err(AsmError::UnboundLabel),
+        }
+    }
+
+    /// Choose the form of each branch, and patch every relative displacement, returning the bytes
+    /// assembled.
+    #[inline]
+    pub fn finish(self) -> Result<Vec<u8>, AsmError> {
+        let mut near: Vec<bool> = self
+            .items
+            .iter()
+            .map(|item| matches!(item, Item::Branch { near: true, .. }))
+            .collect();
+
+        // branches only grow, until every short branch is in range
+        let offsets = loop {
+            let offsets = self.offsets(&near)?;
+            let mut grown = false;
+
+            for (index, item) in self.items.iter().enumerate() {
+                if let Item::Branch { label, .. } = *item {
+                    let rel = crate::rel_to(self.target(&offsets, label)?, offsets[index + 1]);
+
+                    if !near[index] && rel as i8 as isize != rel {
+                        near[index] = true;
+                        grown = true;
+                    }
+                }
+            }
+
+            if !grown {
+                break offsets;
+            }
+        };
+
+        let mut bytes = Vec::with_capacity(offsets[self.items.len()]);
+
+        for (index, item) in self.items.iter().enumerate() {
+            let end = offsets[index + 1];
+
+            match *item {
+                Item::Inst {
+                    start,
+                    end: inst_end,
+                    rel,
+                } => {
+                    bytes.extend_from_slice(&self.bytes[start..inst_end]);
+
+                    if let Some((width, label)) = rel {
+                        let rel = crate::rel_to(self.target(&offsets, label)?, end);
+                        let field = &mut bytes[end - width..end];
+
+                        match width {
+                            2 if rel as i16 as isize == rel => {
+                                field.copy_from_slice(&(rel as i16).to_le_bytes());
+                            }
+                            4 if rel as i32 as isize == rel => {
+                                field.copy_from_slice(&(rel as i32).to_le_bytes());
+                            }
+                            _ => return Err(AsmError::OutOfRange),
+                        }
+                    }
+                }
+                Item::Branch { cond, label, .. } => {
+                    let rel = crate::rel_to(self.target(&offsets, label)?, end);
+
+                    let fits = match self.mode.default_size() {
+                        Size::Word => rel as i16 as isize == rel,
+                        _ => rel as i32 as isize == rel,
+                    };
+
+                    if !fits {
+                        return Err(AsmError::OutOfRange);
+                    }
+
+                    let inst = self.branch_inst(cond, rel as i32, near[index]);
+
+                    bytes.extend_from_slice(&inst.to_bytes(self.mode)?);
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+}
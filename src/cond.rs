@@ -0,0 +1,110 @@
+use crate::Flags;
+
+const COND_MASK: u8 = 0b0000_1111;
+
+/// A condition code, as encoded in the low nibble of `Jcc`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Cond {
+    /// Overflow (`OF = 1`).
+    O,
+    /// Not overflow (`OF = 0`).
+    No,
+    /// Below (`CF = 1`).
+    B,
+    /// Above or equal (`CF = 0`).
+    Ae,
+    /// Equal (`ZF = 1`).
+    E,
+    /// Not equal (`ZF = 0`).
+    Ne,
+    /// Below or equal (`CF = 1 or ZF = 1`).
+    Be,
+    /// Above (`CF = 0 and ZF = 0`).
+    A,
+    /// Sign (`SF = 1`).
+    S,
+    /// Not sign (`SF = 0`).
+    Ns,
+    /// Parity (`PF = 1`).
+    P,
+    /// Not parity (`PF = 0`).
+    Np,
+    /// Less (`SF != OF`).
+    L,
+    /// Greater or equal (`SF = OF`).
+    Ge,
+    /// Less or equal (`ZF = 1 or SF != OF`).
+    Le,
+    /// Greater (`ZF = 0 and SF = OF`).
+    G,
+}
+
+/// Mnemonics of `jcc`, indexed by condition code.
+pub const JCC: [&str; 16] = [
+    "jo", "jno", "jb", "jae", "je", "jne", "jbe", "ja", "js", "jns", "jp", "jnp", "jl", "jge",
+    "jle", "jg",
+];
+
+/// Mnemonics of `cmovcc`, indexed by condition code.
+pub const CMOVCC: [&str; 16] = [
+    "cmovo", "cmovno", "cmovb", "cmovae", "cmove", "cmovne", "cmovbe", "cmova", "cmovs", "cmovns",
+    "cmovp", "cmovnp", "cmovl", "cmovge", "cmovle", "cmovg",
+];
+
+/// Mnemonics of `setcc`, indexed by condition code.
+pub const SETCC: [&str; 16] = [
+    "seto", "setno", "setb", "setae", "sete", "setne", "setbe", "seta", "sets", "setns", "setp",
+    "setnp", "setl", "setge", "setle", "setg",
+];
+
+impl Cond {
+    /// Create a condition code from the low nibble of an opcode.
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & COND_MASK {
+            0x0 => Cond::O,
+            0x1 => Cond::No,
+            0x2 => Cond::B,
+            0x3 => Cond::Ae,
+            0x4 => Cond::E,
+            0x5 => Cond::Ne,
+            0x6 => Cond::Be,
+            0x7 => Cond::A,
+            0x8 => Cond::S,
+            0x9 => Cond::Ns,
+            0xA => Cond::P,
+            0xB => Cond::Np,
+            0xC => Cond::L,
+            0xD => Cond::Ge,
+            0xE => Cond::Le,
+            _ => Cond::G,
+        }
+    }
+
+    /// Returns the bits of this condition code.
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the flags tested by this condition.
+    #[inline]
+    pub const fn flags(self) -> Flags {
+        match self {
+            Cond::O | Cond::No => Flags::OF,
+            Cond::B | Cond::Ae => Flags::CF,
+            Cond::E | Cond::Ne => Flags::ZF,
+            Cond::Be | Cond::A => Flags::CF.union(Flags::ZF),
+            Cond::S | Cond::Ns => Flags::SF,
+            Cond::P | Cond::Np => Flags::PF,
+            Cond::L | Cond::Ge => Flags::SF.union(Flags::OF),
+            Cond::Le | Cond::G => Flags::ZF.union(Flags::SF).union(Flags::OF),
+        }
+    }
+
+    /// Returns the inverse condition.
+    #[inline]
+    pub const fn invert(self) -> Self {
+        Self::from_bits(self.bits() ^ 1)
+    }
+}
@@ -0,0 +1,56 @@
+//! Disassembly of code which may have data embedded in it.
+
+use crate::{InstIter, Mode, Target, WithIp};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Disassemble from `start_ip` in `mode` by recursive descent, returning the instructions
+/// reached, keyed by their address.
+///
+/// `read` fills the buffer with the bytes at an address, returning the number filled, fewer at
+/// the end of the code. The targets of relative branches are disassembled in turn, decoding
+/// stops after an unconditional `jmp`, a return, a trap, an instruction which cannot be decoded,
+/// or on reaching bytes already decoded.
+pub fn recursive<F>(start_ip: usize, mode: Mode, mut read: F) -> BTreeMap<usize, WithIp>
+where
+    F: FnMut(usize, &mut [u8; 15]) -> usize,
+{
+    let mut insts: BTreeMap<usize, WithIp> = BTreeMap::new();
+    let mut queue = Vec::from([start_ip]);
+    let mut buf = [0; 15];
+
+    while let Some(mut ip) = queue.pop() {
+        loop {
+            // within an instruction already decoded
+            if let Some((_, inst)) = insts.range(..=ip).next_back() {
+                if inst.contains(ip) {
+                    break;
+                }
+            }
+
+            let len = read(ip, &mut buf).min(buf.len());
+            let Some(inst) = InstIter::from_bytes(ip, &buf[..len], mode).next() else {
+                break;
+            };
+
+            // overlapping an instruction already decoded
+            if insts.range(ip..inst.next_ip()).next().is_some() {
+                break;
+            }
+
+            insts.insert(ip, inst);
+
+            if let (true, Target::Absolute(target)) = (inst.is_branch(), inst.target()) {
+                queue.push(target);
+            }
+
+            if inst.is_terminator() && !inst.is_conditional() {
+                break;
+            }
+
+            ip = inst.next_ip();
+        }
+    }
+
+    insts
+}
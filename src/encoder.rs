@@ -1,36 +1,253 @@
-use pancake::Vec;
+use crate::nop_pad;
+use crate::{Arg, EncodeError, Inst, Mem, Mode, Size, Thunk};
 
-pub struct Encoder {
-    bytes: Vec<u8, 15>,
+/// Encoder of a sequence of instructions into an inline buffer of `N` bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Encoder<const N: usize = 15> {
+    bytes: [u8; N],
+    len: usize,
+    mode: Mode,
 }
 
-impl Encoder {
+impl<const N: usize> Encoder<N> {
+    /// Capacity of the buffer.
+    pub const CAPACITY: usize = N;
+
+    /// Encode for `mode`.
+    #[inline]
+    pub const fn new(mode: Mode) -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+            mode,
+        }
+    }
+
+    /// Returns the mode being encoded for.
+    #[inline]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the number of bytes written.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing has been written.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes which can still be written.
+    #[inline]
+    pub const fn remaining(&self) -> usize {
+        N - self.len
+    }
+
+    /// Returns the bytes written.
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+
+    /// Discard the bytes written.
     #[inline]
-    pub const fn new() -> Self {
-        let bytes = Vec::new();
+    pub const fn clear(&mut self) {
+        self.bytes = [0; N];
+        self.len = 0;
+    }
 
-        Self { bytes }
+    /// Write `byte`.
+    #[inline]
+    pub const fn write_u8(&mut self, byte: u8) -> Result<(), EncodeError> {
+        self.write_bytes(&[byte])
     }
 
+    /// Write a little endian `i16`.
     #[inline]
-    pub const unsafe fn write_u8(&mut self, value: u8) {
-        self.bytes.push_unchecked(value);
+    pub const fn write_i16(&mut self, value: i16) -> Result<(), EncodeError> {
+        self.write_bytes(&value.to_le_bytes())
     }
 
+    /// Write a little endian `i32`.
     #[inline]
-    pub const unsafe fn write_i32(&mut self, value: i32) {
-        let bytes = value.to_le_bytes();
+    pub const fn write_i32(&mut self, value: i32) -> Result<(), EncodeError> {
+        self.write_bytes(&value.to_le_bytes())
+    }
 
-        self.write_bytes(&bytes);
+    /// Write a little endian `i64`.
+    #[inline]
+    pub const fn write_i64(&mut self, value: i64) -> Result<(), EncodeError> {
+        self.write_bytes(&value.to_le_bytes())
     }
 
+    /// Write `bytes`, nothing is written if they do not fit.
     #[inline]
-    pub const unsafe fn write_bytes(&mut self, bytes: &[u8]) {
-        self.bytes.extend_from_slice_unchecked(bytes);
+    pub const fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        if bytes.len() > self.remaining() {
+            return Err(EncodeError::BufferTooSmall {
+                needed: self.len + bytes.len(),
+            });
+        }
+
+        let mut index = 0;
+
+        while index < bytes.len() {
+            self.bytes[self.len] = bytes[index];
+            self.len += 1;
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Write `inst`, returning its length, nothing is written if it cannot be encoded or does not
+    /// fit.
+    #[inline]
+    pub const fn write_inst(&mut self, inst: &Inst) -> Result<usize, EncodeError> {
+        let bytes = match inst.to_bytes(self.mode) {
+            Ok(bytes) => bytes,
+            Err(error) => return Err(error),
+        };
+
+        match self.write_bytes(bytes.as_slice()) {
+            Ok(()) => Ok(bytes.len()),
+            Err(error) => Err(error),
+        }
     }
 
+    /// Write the recommended multi-byte NOPs filling `len` bytes, nothing is written if they do
+    /// not fit.
+    ///
+    /// See [`Inst::nop_pad`].
     #[inline]
-    pub const fn into_vec(self) -> Vec<u8, 15> {
-        self.bytes
+    pub const fn write_nops(&mut self, len: usize) -> Result<(), EncodeError> {
+        if len > self.remaining() {
+            return Err(EncodeError::BufferTooSmall {
+                needed: self.len + len,
+            });
+        }
+
+        let max_len = nop_pad::max_len(self.mode);
+        let mut remaining = len;
+
+        while remaining > 0 {
+            let len = if remaining < max_len {
+                remaining
+            } else {
+                max_len
+            };
+
+            if let Err(error) = self.write_inst(&Inst::Nop(len as u8)) {
+                return Err(error);
+            }
+
+            remaining -= len;
+        }
+
+        Ok(())
+    }
+
+    /// Write a 64-bit absolute `jmp` to `target` through `thunk`, returning its length, nothing is
+    /// written if it does not fit.
+    #[inline]
+    pub const fn write_jmp_abs(&mut self, target: u64, thunk: Thunk) -> Result<usize, EncodeError> {
+        self.write_thunk(target, thunk, false)
+    }
+
+    /// Write a 64-bit absolute `call` to `target` through `thunk`, returning its length, nothing is
+    /// written if it does not fit.
+    #[inline]
+    pub const fn write_call_abs(
+        &mut self,
+        target: u64,
+        thunk: Thunk,
+    ) -> Result<usize, EncodeError> {
+        self.write_thunk(target, thunk, true)
+    }
+
+    /// Write an absolute `jmp` or `call` to `target` through `thunk`.
+    #[inline]
+    const fn write_thunk(
+        &mut self,
+        target: u64,
+        thunk: Thunk,
+        call: bool,
+    ) -> Result<usize, EncodeError> {
+        let len = if call {
+            thunk.call_len()
+        } else {
+            thunk.jmp_len()
+        };
+
+        if !matches!(self.mode, Mode::Long64) {
+            return Err(EncodeError::InvalidOperands);
+        }
+
+        if len > self.remaining() {
+            return Err(EncodeError::BufferTooSmall {
+                needed: self.len + len,
+            });
+        }
+
+        let (first, second) = match (thunk, call) {
+            (Thunk::Indirect, false) => (Inst::Jmp(Arg::Mem(Mem::rip(0))), None),
+            // the return address is past the target
+            (Thunk::Indirect, true) => (Inst::Call(Arg::Mem(Mem::rip(2))), Some(Inst::JmpShort(8))),
+            (Thunk::Register(reg), _) => {
+                let branch = if call {
+                    Inst::Call(Arg::Reg(reg))
+                } else {
+                    Inst::Jmp(Arg::Reg(reg))
+                };
+
+                (
+                    Inst::Mov(Size::Qword, Arg::Reg(reg), Arg::Imm64(target as i64)),
+                    Some(branch),
+                )
+            }
+        };
+
+        let start = self.len;
+
+        if let Err(error) = self.write_inst(&first) {
+            return Err(error);
+        }
+
+        if let Some(second) = second {
+            if let Err(error) = self.write_inst(&second) {
+                self.len = start;
+                return Err(error);
+            }
+        }
+
+        if let Thunk::Indirect = thunk {
+            if let Err(error) = self.write_bytes(&target.to_le_bytes()) {
+                self.len = start;
+                return Err(error);
+            }
+        }
+
+        Ok(self.len - start)
+    }
+
+    /// Copy the bytes written to the start of `buf`, returning the number of bytes copied.
+    #[inline]
+    pub const fn copy_to(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        if self.len > buf.len() {
+            return Err(EncodeError::BufferTooSmall { needed: self.len });
+        }
+
+        let mut index = 0;
+
+        while index < self.len {
+            buf[index] = self.bytes[index];
+            index += 1;
+        }
+
+        Ok(self.len)
     }
 }
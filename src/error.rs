@@ -0,0 +1,295 @@
+use core::{error, fmt};
+
+/// An error decoding an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The opcode `byte` at `offset` is unknown, or not yet supported.
+    UnknownOpcode { byte: u8, offset: usize },
+    /// The instruction continues past the end of the bytes, it needs `needed` bytes.
+    TruncatedInstruction { needed: usize },
+    /// A prefix is not permitted with the instruction that follows it.
+    InvalidPrefix,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode { byte, offset } => {
+                write!(fmt, "unknown opcode {byte:02x} at offset {offset}")
+            }
+            DecodeError::TruncatedInstruction { needed } => {
+                write!(fmt, "truncated instruction, {needed} bytes are needed")
+            }
+            DecodeError::InvalidPrefix => fmt.write_str("invalid prefix"),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// An error encoding an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EncodeError {
+    /// The operands are not valid for the instruction, or for the mode being encoded for.
+    InvalidOperands,
+    /// The instruction is longer than 15 bytes.
+    TooLong,
+    /// The buffer is too small, it needs `needed` bytes.
+    BufferTooSmall { needed: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InvalidOperands => fmt.write_str("invalid operands"),
+            EncodeError::TooLong => fmt.write_str("instruction is longer than 15 bytes"),
+            EncodeError::BufferTooSmall { needed } => {
+                write!(fmt, "buffer is too small, {needed} bytes are needed")
+            }
+        }
+    }
+}
+
+impl error::Error for EncodeError {}
+
+/// An error retargeting an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TargetError {
+    /// The instruction has no relative target which can be changed.
+    NotRelative,
+    /// The target is out of range of the relative displacement.
+    OutOfRange,
+    /// The instruction cannot be encoded to determine its length.
+    Encode(EncodeError),
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::NotRelative => fmt.write_str("instruction is not relative"),
+            TargetError::OutOfRange => fmt.write_str("target is out of range"),
+            TargetError::Encode(_) => fmt.write_str("failed to encode instruction"),
+        }
+    }
+}
+
+impl error::Error for TargetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TargetError::Encode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EncodeError> for TargetError {
+    fn from(error: EncodeError) -> Self {
+        TargetError::Encode(error)
+    }
+}
+
+/// An error parsing a register name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseRegError;
+
+impl fmt::Display for ParseRegError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("unknown register name")
+    }
+}
+
+impl error::Error for ParseRegError {}
+
+/// An error converting a number to a register, it is not `0` to `15`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RegEncodingError;
+
+impl fmt::Display for RegEncodingError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("invalid register encoding")
+    }
+}
+
+impl error::Error for RegEncodingError {}
+
+/// An error assembling instructions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AsmError {
+    /// The label is already bound.
+    LabelBound,
+    /// The label is referenced, but never bound.
+    UnboundLabel,
+    /// The label is out of range of a relative displacement referencing it.
+    OutOfRange,
+    /// An instruction cannot be encoded.
+    Encode(EncodeError),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::LabelBound => fmt.write_str("label is already bound"),
+            AsmError::UnboundLabel => fmt.write_str("label is never bound"),
+            AsmError::OutOfRange => fmt.write_str("label is out of range"),
+            AsmError::Encode(_) => fmt.write_str("failed to encode instruction"),
+        }
+    }
+}
+
+impl error::Error for AsmError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            AsmError::Encode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EncodeError> for AsmError {
+    fn from(error: EncodeError) -> Self {
+        AsmError::Encode(error)
+    }
+}
+
+/// An error building a trampoline.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrampolineError {
+    /// An instruction to be stolen cannot be decoded.
+    Decode(DecodeError),
+    /// The function returns or jumps away before enough bytes are stolen.
+    FunctionEnds,
+    /// A stolen branch targets the middle of the stolen bytes, which are to be overwritten.
+    BranchIntoStolen,
+    /// A relative target is out of range of the trampoline.
+    OutOfRange,
+    /// The relocated instructions cannot be encoded, or do not fit.
+    Encode(EncodeError),
+}
+
+impl fmt::Display for TrampolineError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrampolineError::Decode(_) => fmt.write_str("failed to decode instruction"),
+            TrampolineError::FunctionEnds => fmt.write_str("function ends within the stolen bytes"),
+            TrampolineError::BranchIntoStolen => {
+                fmt.write_str("branch targets the middle of the stolen bytes")
+            }
+            TrampolineError::OutOfRange => fmt.write_str("target is out of range"),
+            TrampolineError::Encode(_) => fmt.write_str("failed to encode instruction"),
+        }
+    }
+}
+
+impl error::Error for TrampolineError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TrampolineError::Decode(error) => Some(error),
+            TrampolineError::Encode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for TrampolineError {
+    fn from(error: DecodeError) -> Self {
+        TrampolineError::Decode(error)
+    }
+}
+
+impl From<EncodeError> for TrampolineError {
+    fn from(error: EncodeError) -> Self {
+        TrampolineError::Encode(error)
+    }
+}
+
+impl From<TargetError> for TrampolineError {
+    fn from(error: TargetError) -> Self {
+        match error {
+            TargetError::Encode(error) => TrampolineError::Encode(error),
+            _ => TrampolineError::OutOfRange,
+        }
+    }
+}
+
+/// An error applying or reverting a patch.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PatchError {
+    /// The original and replacement bytes differ in length.
+    LenMismatch,
+    /// The buffer is too small, it needs `needed` bytes.
+    BufferTooSmall { needed: usize },
+    /// The region ends before the patch does, it needs `needed` bytes.
+    Truncated { needed: usize },
+    /// The region no longer holds the bytes expected.
+    Mismatch,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::LenMismatch => {
+                fmt.write_str("original and replacement bytes differ in length")
+            }
+            PatchError::BufferTooSmall { needed } => {
+                write!(fmt, "buffer is too small, {needed} bytes are needed")
+            }
+            PatchError::Truncated { needed } => {
+                write!(fmt, "truncated region, {needed} bytes are needed")
+            }
+            PatchError::Mismatch => fmt.write_str("region does not hold the bytes expected"),
+        }
+    }
+}
+
+impl error::Error for PatchError {}
+
+/// An error rebasing a block of instructions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RebaseError {
+    /// A branch targets the middle of an instruction of the block.
+    IntoInstruction,
+    /// A relative branch cannot be retargeted.
+    NotRelative,
+    /// A relative target is out of range of its new address.
+    OutOfRange,
+    /// An instruction cannot be encoded.
+    Encode(EncodeError),
+}
+
+impl fmt::Display for RebaseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebaseError::IntoInstruction => {
+                fmt.write_str("branch targets the middle of an instruction")
+            }
+            RebaseError::NotRelative => fmt.write_str("instruction is not relative"),
+            RebaseError::OutOfRange => fmt.write_str("target is out of range"),
+            RebaseError::Encode(_) => fmt.write_str("failed to encode instruction"),
+        }
+    }
+}
+
+impl error::Error for RebaseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RebaseError::Encode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EncodeError> for RebaseError {
+    fn from(error: EncodeError) -> Self {
+        RebaseError::Encode(error)
+    }
+}
+
+impl From<TargetError> for RebaseError {
+    fn from(error: TargetError) -> Self {
+        match error {
+            TargetError::NotRelative => RebaseError::NotRelative,
+            TargetError::OutOfRange => RebaseError::OutOfRange,
+            TargetError::Encode(error) => RebaseError::Encode(error),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// ty https://en.wikipedia.org/wiki/EVEX_prefix
+
+use crate::mem::Mem;
+use crate::modrm::{ModRm, Rm};
+use crate::prefix::Prefixes;
+use crate::rex::Rex;
+use crate::writer::Writer;
+use crate::EncodeError;
+
+/// First byte of an EVEX prefix.
+pub const EVEX: u8 = 0x62;
+
+const MAP_MASK: u8 = 0b0000_0111;
+const PP_MASK: u8 = 0b0000_0011;
+const AAA_MASK: u8 = 0b0000_0111;
+const VVVV_MASK: u8 = 0b0111_1000;
+const LL_MASK: u8 = 0b0110_0000;
+
+/// Bits of `P0` which must be zero.
+const P0_RESERVED: u8 = 0b0000_1000;
+
+/// Bit of `P1` which must be one.
+const P1_RESERVED: u8 = 0b0000_0100;
+
+/// The `r/m` operand of an EVEX instruction.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum EvexRm {
+    /// A 5-bit vector (or general purpose) register number.
+    Reg(u8),
+    /// A memory operand.
+    ///
+    /// The displacement is as encoded, a `disp8` is not scaled by the operand size (`disp8*N`).
+    Mem(Mem),
+}
+
+/// An EVEX encoded (AVX-512) instruction.
+///
+/// Only decoded as far as is needed to determine its operands and length.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Evex {
+    /// Opcode map, `1` (`0F`), `2` (`0F 38`), `3` (`0F 3A`), `5` or `6`.
+    pub map: u8,
+    /// Opcode within the map.
+    pub opcode: u8,
+    /// Implied mandatory prefix, `0` (none), `1` (`66`), `2` (`F3`) or `3` (`F2`).
+    pub pp: u8,
+    /// `EVEX.W`.
+    pub w: bool,
+    /// Vector length, `0` (128-bit), `1` (256-bit) or `2` (512-bit).
+    pub ll: u8,
+    /// 5-bit `reg` operand.
+    pub reg: u8,
+    /// 5-bit `vvvv` operand.
+    pub vvvv: u8,
+    /// `r/m` operand.
+    pub rm: EvexRm,
+    /// Opmask register (`k0` to `k7`), `k0` meaning no masking.
+    pub mask: u8,
+    /// Zeroing, rather than merging masking.
+    pub zeroing: bool,
+    /// Broadcast, rounding control or suppress all exceptions.
+    pub broadcast: bool,
+    /// Trailing 8-bit immediate.
+    pub imm: Option<u8>,
+}
+
+impl Evex {
+    /// Decode an EVEX instruction, `bytes` starting with `62`.
+    #[inline]
+    pub(crate) const fn from_bytes(bytes: &[u8], prefixes: Prefixes) -> Option<Self> {
+        // legacy prefixes which are implied by EVEX are not permitted
+        if prefixes.lock
+            || prefixes.operand_size
+            || prefixes.rep
+            || prefixes.repne
+            || prefixes.rex.is_present()
+        {
+            return None;
+        }
+
+        let [EVEX, p0, p1, p2, opcode, rest @ ..] = bytes else {
+            return None;
+        };
+
+        let (p0, p1, p2) = (*p0, *p1, *p2);
+
+        if p0 & P0_RESERVED != 0 || p1 & P1_RESERVED == 0 {
+            return None;
+        }
+
+        // R, X, B, R' and V' are stored inverted
+        let r = (!p0 >> 7) & 1;
+        let x = (!p0 >> 6) & 1;
+        let b = (!p0 >> 5) & 1;
+        let r2 = (!p0 >> 4) & 1;
+        let v2 = (!p2 >> 3) & 1;
+
+        let map = p0 & MAP_MASK;
+        let w = p1 & 0x80 != 0;
+        let modrm_prefixes = Prefixes {
+            rex: Rex::new(w, r << 3, x << 3, b << 3),
+            ..prefixes
+        };
+
+        let modrm = match ModRm::from_bytes(rest, modrm_prefixes) {
+            Some(modrm) => modrm,
+            None => return None,
+        };
+
+        let rm = match modrm.rm {
+            // X extends a register r/m to 5 bits
+            Rm::Reg(bits) => EvexRm::Reg(bits | (x << 4)),
+            rm => {
+                let mut mem = match Mem::from_rm(rm) {
+                    Some(mem) => mem,
+                    None => return None,
+                };
+
+                mem.seg = prefixes.seg;
+                mem.addr_size = prefixes.addr_size();
+
+                EvexRm::Mem(mem)
+            }
+        };
+
+        let imm = if has_imm(map, *opcode) {
+            match rest.split_at_checked(modrm.len) {
+                Some((_, [imm, ..])) => Some(*imm),
+                _ => return None,
+            }
+        } else {
+            None
+        };
+
+        Some(Self {
+            map,
+            opcode: *opcode,
+            pp: p1 & PP_MASK,
+            w,
+            ll: (p2 & LL_MASK) >> 5,
+            reg: modrm.reg | (r2 << 4),
+            vvvv: ((!p1 & VVVV_MASK) >> 3) | (v2 << 4),
+            rm,
+            mask: p2 & AAA_MASK,
+            zeroing: p2 & 0x80 != 0,
+            broadcast: p2 & 0x10 != 0,
+            imm,
+        })
+    }
+
+    /// Write this instruction.
+    #[inline]
+    pub(crate) const fn encode(self, writer: &mut Writer) {
+        let (rm, seg, address_size) = match self.rm {
+            EvexRm::Reg(bits) => (Rm::Reg(bits), None, false),
+            EvexRm::Mem(mem) => match mem.to_rm() {
+                Some(rm) => (rm, mem.seg, writer.mode().needs_addr_prefix(mem.addr_size)),
+                None => {
+                    writer.fail(EncodeError::InvalidOperands);
+                    return;
+                }
+            },
+        };
+
+        let (x, b) = match rm {
+            Rm::Reg(bits) => (bits >> 4, bits >> 3),
+            Rm::Mem { base, index, .. } => {
+                let base = match base {
+                    Some(base) => base,
+                    None => 0,
+                };
+
+                let index = match index {
+                    Some(index) => index,
+                    None => 0,
+                };
+
+                (index >> 3, base >> 3)
+            }
+            Rm::Rip(_) => (0, 0),
+        };
+
+        let r = self.reg >> 3;
+        let r2 = self.reg >> 4;
+        let v2 = self.vvvv >> 4;
+
+        let p0 = (!((r & 1) << 7 | (x & 1) << 6 | (b & 1) << 5 | (r2 & 1) << 4) & 0xF0) | self.map;
+        let p1 = (self.w as u8) << 7
+            | ((!self.vvvv << 3) & VVVV_MASK)
+            | P1_RESERVED
+            | (self.pp & PP_MASK);
+        let p2 = (self.zeroing as u8) << 7
+            | (self.ll << 5) & LL_MASK
+            | (self.broadcast as u8) << 4
+            | ((!v2 & 1) << 3)
+            | (self.mask & AAA_MASK);
+
+        writer.write_prefixes(Prefixes {
+            seg,
+            address_size,
+            ..Prefixes::none(writer.mode())
+        });
+
+        writer.write_bytes(&[EVEX, p0, p1, p2, self.opcode]);
+        writer.write_modrm(self.reg, rm);
+
+        if let Some(imm) = self.imm {
+            writer.write_u8(imm);
+        }
+    }
+}
+
+/// Returns `true` if `opcode` in `map` is followed by an 8-bit immediate.
+#[inline]
+pub const fn has_imm(map: u8, opcode: u8) -> bool {
+    match map {
+        1 => matches!(opcode, 0x70..=0x73 | 0xC2 | 0xC4..=0xC6),
+        3 => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,122 @@
+// ty https://www.felixcloutier.com/x86/ and https://en.wikipedia.org/wiki/FLAGS_register
+
+use core::fmt;
+
+/// A set of `rflags` bits.
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct Flags {
+    bits: u32,
+}
+
+impl Flags {
+    /// No flags.
+    pub const NONE: Self = Self::from_bits(0);
+
+    /// Carry flag.
+    pub const CF: Self = Self::from_bits(1 << 0);
+
+    /// Parity flag.
+    pub const PF: Self = Self::from_bits(1 << 2);
+
+    /// Auxiliary carry flag.
+    pub const AF: Self = Self::from_bits(1 << 4);
+
+    /// Zero flag.
+    pub const ZF: Self = Self::from_bits(1 << 6);
+
+    /// Sign flag.
+    pub const SF: Self = Self::from_bits(1 << 7);
+
+    /// Trap flag.
+    pub const TF: Self = Self::from_bits(1 << 8);
+
+    /// Interrupt enable flag.
+    pub const IF: Self = Self::from_bits(1 << 9);
+
+    /// Direction flag.
+    pub const DF: Self = Self::from_bits(1 << 10);
+
+    /// Overflow flag.
+    pub const OF: Self = Self::from_bits(1 << 11);
+
+    /// The status flags, as set by arithmetic.
+    pub const STATUS: Self = Self::from_bits(
+        Self::CF.bits
+            | Self::PF.bits
+            | Self::AF.bits
+            | Self::ZF.bits
+            | Self::SF.bits
+            | Self::OF.bits,
+    );
+
+    /// All flags.
+    pub const ALL: Self = Self::STATUS.union(Self::TF).union(Self::IF).union(Self::DF);
+
+    /// Create a set from `rflags` bits.
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self { bits }
+    }
+
+    /// Returns the `rflags` bits of this set.
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Returns the flags in either set.
+    #[inline]
+    pub const fn union(self, other: Self) -> Self {
+        Self::from_bits(self.bits | other.bits)
+    }
+
+    /// Returns the flags in this set, but not `other`.
+    #[inline]
+    pub const fn difference(self, other: Self) -> Self {
+        Self::from_bits(self.bits & !other.bits)
+    }
+
+    /// Returns `true` if every flag in `other` is in this set.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Returns `true` if any flag in `other` is in this set.
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.bits & other.bits != 0
+    }
+
+    /// Returns `true` if this set is empty.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+}
+
+impl fmt::Debug for Flags {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [(Flags, &str); 9] = [
+            (Flags::CF, "CF"),
+            (Flags::PF, "PF"),
+            (Flags::AF, "AF"),
+            (Flags::ZF, "ZF"),
+            (Flags::SF, "SF"),
+            (Flags::TF, "TF"),
+            (Flags::IF, "IF"),
+            (Flags::DF, "DF"),
+            (Flags::OF, "OF"),
+        ];
+
+        let mut set = fmt.debug_set();
+
+        for (flag, name) in NAMES {
+            if self.contains(flag) {
+                set.entry(&format_args!("{name}"));
+            }
+        }
+
+        set.finish()
+    }
+}
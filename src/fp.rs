@@ -0,0 +1,48 @@
+use crate::prefix::Prefixes;
+
+/// The form of an SSE operation, packed or scalar, single or double precision.
+///
+/// Selected by a mandatory prefix, none (`ps`), `66` (`pd`), `F3` (`ss`) or `F2` (`sd`).
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Fp {
+    /// Packed single precision.
+    Ps,
+    /// Packed double precision.
+    Pd,
+    /// Scalar single precision.
+    Ss,
+    /// Scalar double precision.
+    Sd,
+}
+
+impl Fp {
+    /// Create a form from the mandatory prefix present in `prefixes`.
+    #[inline]
+    pub const fn from_prefixes(prefixes: Prefixes) -> Self {
+        if prefixes.repne {
+            Fp::Sd
+        } else if prefixes.rep {
+            Fp::Ss
+        } else if prefixes.operand_size {
+            Fp::Pd
+        } else {
+            Fp::Ps
+        }
+    }
+
+    /// Returns the size of a memory operand of this form in bytes.
+    #[inline]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Fp::Ps | Fp::Pd => 16,
+            Fp::Ss => 4,
+            Fp::Sd => 8,
+        }
+    }
+
+    /// Returns `true` if this form is packed.
+    #[inline]
+    pub const fn is_packed(self) -> bool {
+        matches!(self, Fp::Ps | Fp::Pd)
+    }
+}
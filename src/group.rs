@@ -0,0 +1,170 @@
+// ty http://ref.x86asm.net/coder64.html#modrm_byte_reg
+
+use crate::{Arg, Inst, Size};
+
+/// An operation selected by ModRM `reg` (`/digit`) within a group, with an `r/m` operand.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ext {
+    Call,
+    Dec,
+    Div,
+    Idiv,
+    Imul,
+    Inc,
+    Jmp,
+    Mul,
+    Neg,
+    Not,
+    Push,
+    /// Followed by an immediate of the operand size.
+    Test,
+}
+
+/// The size of the `r/m` operand of an operation within a group.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Width {
+    /// Always 8-bit.
+    Byte,
+    /// The operand size.
+    Operand,
+    /// The address size of the mode, as for branch targets and the stack.
+    Addr,
+}
+
+/// A group of operations sharing an opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Group {
+    /// The opcode.
+    pub opcode: u8,
+    /// Operations, indexed by `/digit`.
+    pub ops: [Option<(Ext, Width)>; 8],
+}
+
+/// All supported groups.
+const GROUPS: [Group; 4] = [
+    Group {
+        opcode: 0xF6,
+        ops: [
+            Some((Ext::Test, Width::Byte)),
+            None,
+            Some((Ext::Not, Width::Byte)),
+            Some((Ext::Neg, Width::Byte)),
+            Some((Ext::Mul, Width::Byte)),
+            Some((Ext::Imul, Width::Byte)),
+            Some((Ext::Div, Width::Byte)),
+            Some((Ext::Idiv, Width::Byte)),
+        ],
+    },
+    Group {
+        opcode: 0xF7,
+        ops: [
+            Some((Ext::Test, Width::Operand)),
+            None,
+            Some((Ext::Not, Width::Operand)),
+            Some((Ext::Neg, Width::Operand)),
+            Some((Ext::Mul, Width::Operand)),
+            Some((Ext::Imul, Width::Operand)),
+            Some((Ext::Div, Width::Operand)),
+            Some((Ext::Idiv, Width::Operand)),
+        ],
+    },
+    Group {
+        opcode: 0xFE,
+        ops: [
+            Some((Ext::Inc, Width::Byte)),
+            Some((Ext::Dec, Width::Byte)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ],
+    },
+    Group {
+        opcode: 0xFF,
+        ops: [
+            Some((Ext::Inc, Width::Operand)),
+            Some((Ext::Dec, Width::Operand)),
+            Some((Ext::Call, Width::Addr)),
+            // far call
+            None,
+            Some((Ext::Jmp, Width::Addr)),
+            // far jmp
+            None,
+            Some((Ext::Push, Width::Addr)),
+            None,
+        ],
+    },
+];
+
+impl Group {
+    /// Returns the operation selected by `digit` within the group of `opcode`.
+    #[inline]
+    pub const fn lookup(opcode: u8, digit: u8) -> Option<(Ext, Width)> {
+        let mut index = 0;
+
+        while index < GROUPS.len() {
+            let group = GROUPS[index];
+
+            if group.opcode == opcode {
+                return group.ops[(digit & 0b111) as usize];
+            }
+
+            index += 1;
+        }
+
+        None
+    }
+
+    /// Returns the opcode and `/digit` of `ext` at `width`.
+    #[inline]
+    pub const fn find(ext: Ext, width: Width) -> Option<(u8, u8)> {
+        let mut index = 0;
+
+        while index < GROUPS.len() {
+            let group = GROUPS[index];
+            let mut digit = 0;
+
+            while digit < group.ops.len() {
+                if let Some((op, op_width)) = group.ops[digit] {
+                    if op as u8 == ext as u8 && op_width as u8 == width as u8 {
+                        return Some((group.opcode, digit as u8));
+                    }
+                }
+
+                digit += 1;
+            }
+
+            index += 1;
+        }
+
+        None
+    }
+}
+
+impl Ext {
+    /// Construct the instruction from its operand, and the immediate of `test`.
+    #[inline]
+    pub const fn inst(self, size: Size, arg: Arg, imm: Option<Arg>) -> Option<Inst> {
+        let inst = match self {
+            Ext::Call => Inst::Call(arg),
+            Ext::Dec => Inst::Dec(size, arg),
+            Ext::Div => Inst::Div(size, arg),
+            Ext::Idiv => Inst::Idiv(size, arg),
+            Ext::Imul => Inst::Imul(size, arg),
+            Ext::Inc => Inst::Inc(size, arg),
+            Ext::Jmp => Inst::Jmp(arg),
+            Ext::Mul => Inst::Mul(size, arg),
+            Ext::Neg => Inst::Neg(size, arg),
+            Ext::Not => Inst::Not(size, arg),
+            Ext::Push => Inst::Push(arg),
+            Ext::Test => match imm {
+                Some(imm) => Inst::Test(size, arg, imm),
+                None => return None,
+            },
+        };
+
+        Some(inst)
+    }
+}
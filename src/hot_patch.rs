@@ -0,0 +1,119 @@
+use core::iter;
+
+/// What guards the start of a region while the rest of it is written, so no thread executes a
+/// partially written instruction.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Guard {
+    /// `int3`, trapping any thread reaching it.
+    Int3,
+    /// `jmp $`, a short `jmp` to itself, spinning any thread reaching it.
+    ///
+    /// Its 2 bytes must be written atomically.
+    SelfJmp,
+}
+
+impl Guard {
+    /// Returns the bytes of the guard.
+    #[inline]
+    pub const fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Guard::Int3 => &[0xCC],
+            Guard::SelfJmp => &[0xEB, 0xFE],
+        }
+    }
+
+    /// Returns the length of the guard.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub const fn len(self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+/// A write of bytes to an address, one step of a [`HotPatch`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PatchWrite<'a> {
+    /// Address written to.
+    pub addr: usize,
+    /// Bytes written.
+    pub bytes: &'a [u8],
+}
+
+/// Iterator of the writes, in the order they are to be performed, replacing a region of a running
+/// process.
+///
+/// The start of the region is guarded, the rest of it written, then the guard replaced by the
+/// start of the new bytes. Regions no longer than the guard are written at once.
+#[derive(Clone, Debug)]
+pub struct HotPatch<'a> {
+    writes: [PatchWrite<'a>; 3],
+    len: usize,
+    index: usize,
+}
+
+impl<'a> HotPatch<'a> {
+    /// Write `bytes` to `addr`, guarded by `guard`.
+    #[inline]
+    pub const fn new(addr: usize, bytes: &'a [u8], guard: Guard) -> Self {
+        let empty = PatchWrite { addr, bytes: &[] };
+        let mut writes = [empty; 3];
+
+        if bytes.len() <= guard.len() {
+            writes[0] = PatchWrite { addr, bytes };
+
+            return Self {
+                writes,
+                len: 1,
+                index: 0,
+            };
+        }
+
+        let (head, tail) = bytes.split_at(guard.len());
+
+        writes[0] = PatchWrite {
+            addr,
+            bytes: guard.as_bytes(),
+        };
+
+        writes[1] = PatchWrite {
+            addr: addr.wrapping_add(head.len()),
+            bytes: tail,
+        };
+
+        writes[2] = PatchWrite { addr, bytes: head };
+
+        Self {
+            writes,
+            len: 3,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for HotPatch<'a> {
+    type Item = PatchWrite<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<PatchWrite<'a>> {
+        if self.index == self.len {
+            return None;
+        }
+
+        let write = self.writes[self.index];
+
+        self.index += 1;
+
+        Some(write)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.index;
+
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for HotPatch<'_> {}
+
+impl iter::FusedIterator for HotPatch<'_> {}
@@ -0,0 +1,124 @@
+use crate::EncodeError;
+use core::{fmt, ops};
+
+/// Maximum length of an instruction.
+const MAX_LEN: usize = 15;
+
+/// The bytes of an encoded instruction, at most 15.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct InstBytes {
+    bytes: [u8; MAX_LEN],
+    len: usize,
+}
+
+impl InstBytes {
+    /// Maximum length of an instruction.
+    pub const CAPACITY: usize = MAX_LEN;
+
+    /// No bytes.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; MAX_LEN],
+            len: 0,
+        }
+    }
+
+    /// Copy `bytes`, if there are at most 15.
+    #[inline]
+    pub const fn from_slice(bytes: &[u8]) -> Result<Self, EncodeError> {
+        let mut inst_bytes = Self::new();
+
+        match inst_bytes.extend_from_slice(bytes) {
+            Ok(()) => Ok(inst_bytes),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Append `byte`, unless there are already 15 bytes.
+    #[inline]
+    pub const fn push(&mut self, byte: u8) -> Result<(), EncodeError> {
+        if self.len >= MAX_LEN {
+            return Err(EncodeError::TooLong);
+        }
+
+        self.bytes[self.len] = byte;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Append `bytes`, unless there would be more than 15 bytes, in which case nothing is appended.
+    #[inline]
+    pub const fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        if bytes.len() > MAX_LEN - self.len {
+            return Err(EncodeError::TooLong);
+        }
+
+        let mut index = 0;
+
+        while index < bytes.len() {
+            self.bytes[self.len] = bytes[index];
+            self.len += 1;
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the bytes.
+    #[inline]
+    pub const fn as_slice(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+
+    /// Returns the number of bytes.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no bytes.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl ops::Deref for InstBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for InstBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl PartialEq<[u8]> for InstBytes {
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl TryFrom<&[u8]> for InstBytes {
+    type Error = EncodeError;
+
+    #[inline]
+    fn try_from(bytes: &[u8]) -> Result<Self, EncodeError> {
+        Self::from_slice(bytes)
+    }
+}
+
+impl fmt::Debug for InstBytes {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), fmt)
+    }
+}
@@ -0,0 +1,16 @@
+/// The control flow of an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+    /// `call`, which returns to the next instruction.
+    Call,
+    /// `ret`, `iret` or `sysret`.
+    Ret,
+    /// An unconditional `jmp`.
+    Jmp,
+    /// A conditional `jcc`, which otherwise continues to the next instruction.
+    Jcc,
+    /// `int3`, `ud2` or `hlt`, which do not continue to the next instruction.
+    Trap,
+    /// Any other instruction, which continues to the next instruction.
+    Other,
+}
@@ -0,0 +1,242 @@
+// ty http://ref.x86asm.net/coder64.html
+
+use crate::evex;
+use crate::modrm::ModRm;
+use crate::prefix::Prefixes;
+use crate::vex::{self, Vex};
+use crate::{Mode, Size};
+
+/// Maximum length of an instruction.
+const MAX_LEN: usize = 15;
+
+/// The operands following an opcode, which determine its length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operands {
+    /// Nothing follows.
+    None,
+    /// A ModRM operand.
+    ModRm,
+    /// A ModRM operand, then an 8-bit immediate.
+    ModRmImm8,
+    /// A ModRM operand, then an immediate of the operand size (at most 32-bit).
+    ModRmImmZ,
+    /// An 8-bit immediate.
+    Imm8,
+    /// A 16-bit immediate.
+    Imm16,
+    /// A 16-bit immediate, then an 8-bit immediate (`enter`).
+    Imm16Imm8,
+    /// An immediate of the operand size (at most 32-bit).
+    ImmZ,
+    /// An immediate of the operand size, 64-bit with REX.W (`mov reg, imm`).
+    ImmV,
+    /// A relative branch target, 32-bit in long mode, otherwise of the operand size.
+    Rel,
+    /// A far pointer, an offset of the operand size then a 16-bit selector.
+    Far,
+    /// An absolute address of the address size (`mov acc, moffs`).
+    Moffs,
+    /// `F6 /0` and `F7 /0`, `test r/m, imm`, otherwise a ModRM operand.
+    Group3,
+}
+
+/// Returns the operands following a one-byte `opcode`.
+#[inline]
+const fn one_byte(opcode: u8) -> Operands {
+    match opcode {
+        // alu
+        0x00..=0x3F => match opcode & 0b111 {
+            0..=3 => Operands::ModRm,
+            4 => Operands::Imm8,
+            5 => Operands::ImmZ,
+            _ => Operands::None,
+        },
+        0x40..=0x61 => Operands::None,
+        0x62 | 0x63 => Operands::ModRm,
+        0x68 => Operands::ImmZ,
+        0x69 => Operands::ModRmImmZ,
+        0x6A => Operands::Imm8,
+        0x6B => Operands::ModRmImm8,
+        0x70..=0x7F => Operands::Imm8,
+        0x80 | 0x82 | 0x83 => Operands::ModRmImm8,
+        0x81 => Operands::ModRmImmZ,
+        0x84..=0x8F => Operands::ModRm,
+        0x9A => Operands::Far,
+        0xA0..=0xA3 => Operands::Moffs,
+        0xA8 => Operands::Imm8,
+        0xA9 => Operands::ImmZ,
+        0xB0..=0xB7 => Operands::Imm8,
+        0xB8..=0xBF => Operands::ImmV,
+        0xC0 | 0xC1 | 0xC6 => Operands::ModRmImm8,
+        0xC2 | 0xCA => Operands::Imm16,
+        0xC4 | 0xC5 => Operands::ModRm,
+        0xC7 => Operands::ModRmImmZ,
+        0xC8 => Operands::Imm16Imm8,
+        0xCD => Operands::Imm8,
+        0xD0..=0xD3 => Operands::ModRm,
+        0xD4 | 0xD5 => Operands::Imm8,
+        0xD8..=0xDF => Operands::ModRm,
+        0xE0..=0xE7 => Operands::Imm8,
+        0xE8 | 0xE9 => Operands::Rel,
+        0xEA => Operands::Far,
+        0xEB => Operands::Imm8,
+        0xF6 | 0xF7 => Operands::Group3,
+        0xFE | 0xFF => Operands::ModRm,
+        _ => Operands::None,
+    }
+}
+
+/// Returns the operands following a two-byte `0F opcode`.
+#[inline]
+const fn two_byte(opcode: u8) -> Operands {
+    match opcode {
+        0x05..=0x0C | 0x0E => Operands::None,
+        // 3dnow!
+        0x0F => Operands::ModRmImm8,
+        0x30..=0x37 => Operands::None,
+        0x70..=0x73 => Operands::ModRmImm8,
+        0x77 => Operands::None,
+        0x80..=0x8F => Operands::Rel,
+        0xA0..=0xA2 | 0xA8..=0xAA => Operands::None,
+        0xA4 | 0xAC | 0xBA | 0xC2 | 0xC4..=0xC6 => Operands::ModRmImm8,
+        0xC8..=0xCF => Operands::None,
+        _ => Operands::ModRm,
+    }
+}
+
+/// Returns the length of the instruction at the start of `bytes` in `mode`, from its prefixes,
+/// opcode, ModRM, SIB, displacement and immediate, regardless of whether it can be decoded.
+///
+/// Returns `None` if `bytes` is truncated, or the length exceeds 15 bytes.
+#[inline]
+pub const fn inst_len(bytes: &[u8], mode: Mode) -> Option<usize> {
+    match lens(bytes, mode) {
+        Some((len, _)) => Some(len),
+        None => None,
+    }
+}
+
+/// Returns the length of the immediate ending the instruction at the start of `bytes` in `mode`,
+/// which any displacement directly precedes.
+///
+/// Returns `None` if `bytes` is truncated, or the length exceeds 15 bytes.
+#[inline]
+pub const fn imm_len(bytes: &[u8], mode: Mode) -> Option<usize> {
+    match lens(bytes, mode) {
+        Some((_, imm_len)) => Some(imm_len),
+        None => None,
+    }
+}
+
+/// Returns the length of the instruction at the start of `bytes` in `mode`, and of its immediate.
+#[inline]
+const fn lens(bytes: &[u8], mode: Mode) -> Option<(usize, usize)> {
+    let (prefixes, rest) = Prefixes::from_bytes(bytes, mode);
+    let offset = bytes.len() - rest.len();
+
+    let (opcode_len, operands, rest) = match rest {
+        [evex::EVEX, p0, _, _, opcode, rest @ ..]
+            if mode.has_rex() || (matches!(mode, Mode::Compat32) && *p0 >= 0xC0) =>
+        {
+            let operands = if evex::has_imm(*p0 & 0b111, *opcode) {
+                Operands::ModRmImm8
+            } else {
+                Operands::ModRm
+            };
+
+            (5, operands, rest)
+        }
+        [first @ (vex::VEX3 | vex::VEX2), next, ..]
+            if mode.has_rex() || (matches!(mode, Mode::Compat32) && *next >= 0xC0) =>
+        {
+            let prefix_len = if *first == vex::VEX2 { 2 } else { 3 };
+
+            let (vex, rest) = match Vex::from_bytes(rest, Prefixes::none(mode)) {
+                Some(vex) => vex,
+                None => return None,
+            };
+
+            let [opcode, rest @ ..] = rest else {
+                return None;
+            };
+
+            let operands = match (vex.map, *opcode) {
+                // vzeroupper / vzeroall
+                (vex::MAP_0F, 0x77) => Operands::None,
+                (vex::MAP_0F, 0x70..=0x73 | 0xC2 | 0xC4..=0xC6) | (vex::MAP_0F3A, _) => {
+                    Operands::ModRmImm8
+                }
+                _ => Operands::ModRm,
+            };
+
+            (prefix_len + 1, operands, rest)
+        }
+        [0x0F, 0x38, _, rest @ ..] => (3, Operands::ModRm, rest),
+        [0x0F, 0x3A, _, rest @ ..] => (3, Operands::ModRmImm8, rest),
+        [0x0F, opcode, rest @ ..] => (2, two_byte(*opcode), rest),
+        [opcode, rest @ ..] => (1, one_byte(*opcode), rest),
+        [] => return None,
+    };
+
+    let operand_size = if prefixes.rex.w() {
+        Size::Qword
+    } else {
+        prefixes.operand_size()
+    };
+
+    // an immediate of the operand size is at most 32-bit
+    let imm_z = if matches!(operand_size, Size::Word) {
+        2
+    } else {
+        4
+    };
+
+    let modrm_len = match operands {
+        Operands::ModRm | Operands::ModRmImm8 | Operands::ModRmImmZ | Operands::Group3 => {
+            match ModRm::from_bytes(rest, prefixes) {
+                Some(modrm) => modrm.len,
+                None => return None,
+            }
+        }
+        _ => 0,
+    };
+
+    let imm_len = match operands {
+        Operands::None | Operands::ModRm => 0,
+        Operands::ModRmImm8 | Operands::Imm8 => 1,
+        Operands::Imm16 => 2,
+        Operands::Imm16Imm8 => 3,
+        Operands::ModRmImmZ | Operands::ImmZ => imm_z,
+        Operands::ImmV => match operand_size {
+            Size::Qword => 8,
+            _ => imm_z,
+        },
+        Operands::Rel if mode.has_rex() => 4,
+        Operands::Rel => imm_z,
+        Operands::Far => imm_z + 2,
+        Operands::Moffs => match prefixes.addr_size() {
+            Size::Qword => 8,
+            Size::Dword => 4,
+            _ => 2,
+        },
+        // only test has an immediate
+        Operands::Group3 => match rest {
+            [modrm, ..] if (*modrm >> 3) & 0b110 == 0 => {
+                if matches!(bytes[offset], 0xF6) {
+                    1
+                } else {
+                    imm_z
+                }
+            }
+            _ => 0,
+        },
+    };
+
+    let len = offset + opcode_len + modrm_len + imm_len;
+
+    if len > MAX_LEN || len > bytes.len() {
+        return None;
+    }
+
+    Some((len, imm_len))
+}
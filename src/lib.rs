@@ -1,219 +1,3767 @@
-#![feature(const_convert)]
-#![feature(const_mut_refs)]
-#![feature(const_option_ext)]
-#![feature(const_trait_impl)]
-#![feature(const_try)]
+#![no_std]
 
-use core::ops;
-use encoder::Encoder;
-use pancake::Vec;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+use core::{fmt, iter, ops};
+use group::{Ext, Group, Width};
+use map::{Entry, Form, Op};
+use modrm::{ModRm, Rm};
+use prefix::Prefixes;
+use rex::Rex;
+use vex::Vex;
+use writer::Writer;
+
+pub use abi::Abi;
+#[cfg(feature = "alloc")]
+pub use assembler::{Assembler, Label};
+pub use cond::Cond;
+pub use encoder::Encoder;
+pub use error::{
+    AsmError, DecodeError, EncodeError, ParseRegError, PatchError, RebaseError, RegEncodingError,
+    TargetError, TrampolineError,
+};
+pub use evex::{Evex, EvexRm};
+pub use flags::Flags;
+pub use fp::Fp;
+pub use hot_patch::{Guard, HotPatch, PatchWrite};
+pub use inst_bytes::InstBytes;
+pub use kind::Kind;
+pub use mem::{Base, Mem};
+pub use mem_access::MemAccess;
+pub use mode::Mode;
+pub use nop_pad::NopPad;
+pub use patch::Patch;
+#[cfg(feature = "std")]
+pub use read::ReadDecoder;
+#[cfg(feature = "alloc")]
+pub use rebase::rebase;
 pub use reg::Reg;
+pub use reg_set::RegSet;
+pub use seg::Seg;
+pub use size::Size;
+pub use sub_reg::SubReg;
+pub use target::Target;
+pub use thunk::Thunk;
+pub use trampoline::{min_patch_len, Trampoline};
+pub use x87::{X87Rm, X87};
+pub use xmm::Xmm;
+
+/// `?` for [`Option`], which is not yet stable in const fns.
+macro_rules! tri {
+    ($expr:expr) => {
+        match $expr {
+            Some(value) => value,
+            None => return None,
+        }
+    };
+}
 
+pub mod analysis;
+#[cfg(feature = "alloc")]
+pub mod disasm;
+
+mod abi;
+#[cfg(feature = "alloc")]
+mod assembler;
+mod cond;
 mod encoder;
+mod error;
+mod evex;
+mod flags;
+mod fp;
+mod group;
+mod hot_patch;
+mod inst_bytes;
+mod kind;
+mod len;
+mod map;
+mod mem;
+mod mem_access;
+mod mode;
+mod modrm;
+mod nop_pad;
+mod patch;
+mod prefix;
+#[cfg(feature = "std")]
+mod read;
+#[cfg(feature = "alloc")]
+mod rebase;
 mod reg;
+mod reg_set;
+mod rex;
+mod seg;
+mod size;
+mod sub_reg;
+mod target;
+mod thunk;
+mod trampoline;
+mod vex;
+mod writer;
+mod x87;
+mod xmm;
 
 /// An instruction.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Inst {
-    Call(i32),
-    Call2(i32),
-    Lea(Reg, Arg),
-    Mov(Reg, Arg),
+    Adc(Size, Arg, Arg),
+    Add(Size, Arg, Arg),
+    And(Size, Arg, Arg),
+    /// `andn reg, reg, r/m`, the second operand inverted.
+    Andn(Size, Reg, Reg, Arg),
+    /// `bextr reg, r/m, reg`, extract a bit field given its start and length.
+    Bextr(Size, Reg, Arg, Reg),
+    /// `blsi reg, r/m`, isolate the lowest set bit.
+    Blsi(Size, Reg, Arg),
+    /// `blsmsk reg, r/m`, mask up to the lowest set bit.
+    Blsmsk(Size, Reg, Arg),
+    /// `blsr reg, r/m`, reset the lowest set bit.
+    Blsr(Size, Reg, Arg),
+    /// `bsf reg, r/m`, scan forward for the lowest set bit.
+    Bsf(Size, Reg, Arg),
+    /// `bsr reg, r/m`, scan in reverse for the highest set bit.
+    Bsr(Size, Reg, Arg),
+    /// `bt r/m, reg` or `bt r/m, imm8`, test a bit.
+    Bt(Size, Arg, Arg),
+    /// `btc r/m, reg` or `btc r/m, imm8`, test and complement a bit.
+    Btc(Size, Arg, Arg),
+    /// `btr r/m, reg` or `btr r/m, imm8`, test and reset a bit.
+    Btr(Size, Arg, Arg),
+    /// `bts r/m, reg` or `bts r/m, imm8`, test and set a bit.
+    Bts(Size, Arg, Arg),
+    /// `bzhi reg, r/m, reg`, zero the bits from an index upwards.
+    Bzhi(Size, Reg, Arg, Reg),
+    /// `call rel32` (`E8`) given [`Arg::Imm32`], relative to the next instruction, otherwise an
+    /// indirect call through a register or memory (`FF /2`).
+    Call(Arg),
+    /// `cbw`, `cwde` or `cdqe`, sign-extend the accumulator, given the size of the result.
+    Cbw(Size),
+    /// `clflush m8`, flush a cache line.
+    Clflush(Arg),
+    /// `clflushopt m8`, flush a cache line, weakly ordered.
+    Clflushopt(Arg),
+    /// `clwb m8`, write back a cache line, without necessarily evicting it.
+    Clwb(Arg),
+    /// `cmovcc reg, r/m`.
+    Cmovcc(Cond, Size, Reg, Arg),
+    /// `cli`, clear the interrupt flag.
+    Cli,
+    Cmp(Size, Arg, Arg),
+    Cmpxchg(Size, Arg, Reg),
+    /// `cpuid`.
+    Cpuid,
+    /// `cwd`, `cdq` or `cqo`, sign-extend the accumulator into `dx`, given the operand size.
+    Cwd(Size),
+    /// `clrssbsy m64`, clear the busy flag of a shadow stack token.
+    Clrssbsy(Arg),
+    Dec(Size, Arg),
+    Div(Size, Arg),
+    /// `endbr32`, a 32-bit indirect branch target.
+    Endbr32,
+    /// `endbr64`, a 64-bit indirect branch target.
+    Endbr64,
+    /// `fxrstor m512` or `fxrstor64 m512`, restore the x87, MMX and SSE state.
+    Fxrstor(Size, Arg),
+    /// `fxsave m512` or `fxsave64 m512`, save the x87, MMX and SSE state.
+    Fxsave(Size, Arg),
+    /// `hlt`.
+    Hlt,
+    /// An EVEX encoded (AVX-512) instruction.
+    Evex(Evex),
+    Idiv(Size, Arg),
+    /// `in al/ax/eax, imm8` or `in al/ax/eax, dx`, given the port.
+    In(Size, Arg),
+    /// `imul r/m`, multiplying into `rdx:rax`.
+    Imul(Size, Arg),
+    /// `imul reg, r/m`.
+    Imul2(Size, Reg, Arg),
+    /// `imul reg, r/m, imm`.
+    Imul3(Size, Reg, Arg, Arg),
+    Inc(Size, Arg),
+    /// `incsspd reg` or `incsspq reg`, increment the shadow stack pointer.
+    Incssp(Size, Reg),
+    /// `int imm8`.
+    Int(u8),
+    /// `int3`, a breakpoint.
+    Int3,
+    /// `invlpg m`, invalidate the TLB entry of a page.
+    Invlpg(Arg),
+    /// `iret`, `iretd` or `iretq`.
+    Iret(Size),
+    /// `jcc rel32` (`0F 80+cc`), relative to the next instruction.
+    Jcc(Cond, i32),
+    /// `jcc rel8` (`70+cc`), relative to the next instruction.
+    JccShort(Cond, i8),
+    /// `ldmxcsr m32`, load `mxcsr`.
+    Ldmxcsr(Arg),
+    Lea(Size, Reg, Arg),
+    /// `leave`, tear down a stack frame, as by `mov rsp, rbp; pop rbp`.
+    Leave,
+    /// `lfence`, a load fence.
+    Lfence,
+    /// `lzcnt reg, r/m`, count leading zero bits.
+    Lzcnt(Size, Reg, Arg),
+    /// `mfence`, a memory fence.
+    Mfence,
+    Mov(Size, Arg, Arg),
+    /// `movbe reg, m` or `movbe m, reg`, a byte-swapping move.
+    Movbe(Size, Arg, Arg),
+    /// `movsx reg, r/m` (or `movsxd`), with the size of the source.
+    Movsx(Size, Reg, Size, Arg),
+    /// `movzx reg, r/m`, with the size of the source.
+    Movzx(Size, Reg, Size, Arg),
+    Mul(Size, Arg),
+    /// `mulx reg, reg, r/m`, an unsigned multiply of `rdx` which does not affect flags.
+    Mulx(Size, Reg, Reg, Arg),
+    Neg(Size, Arg),
+    /// A NOP of the specified length (1 to 15).
+    Nop(u8),
+    Not(Size, Arg),
+    Or(Size, Arg, Arg),
+    /// An instruction of `len` bytes which is not otherwise decoded, see [`Inst::decode_len`].
+    ///
+    /// Encoded as `int3` padding of the same length, as its bytes are unknown.
+    Other {
+        len: u8,
+    },
+    /// `out imm8, al/ax/eax` or `out dx, al/ax/eax`, given the port.
+    Out(Size, Arg),
+    /// `pause` (`F3 90`), a spin-wait loop hint.
+    Pause,
+    /// `pdep reg, reg, r/m`, parallel bits deposit.
+    Pdep(Size, Reg, Reg, Arg),
+    /// `pext reg, reg, r/m`, parallel bits extract.
+    Pext(Size, Reg, Reg, Arg),
     Pop(Reg),
+    /// `prefetchnta m8`, prefetch non-temporal data.
+    Prefetchnta(Arg),
+    /// `prefetcht0 m8`, prefetch into all cache levels.
+    Prefetcht0(Arg),
+    /// `prefetcht1 m8`, prefetch into level 2 cache and above.
+    Prefetcht1(Arg),
+    /// `prefetcht2 m8`, prefetch into level 3 cache and above.
+    Prefetcht2(Arg),
+    /// `prefetchw m8`, prefetch in anticipation of a write.
+    Prefetchw(Arg),
+    /// `popf`, `popfd` or `popfq`.
+    Popf(Size),
+    /// `popcnt reg, r/m`, count set bits.
+    Popcnt(Size, Reg, Arg),
     Push(Arg),
-    Jmp(i32),
+    /// `pushf`, `pushfd` or `pushfq`.
+    Pushf(Size),
+    Rcl(Size, Arg, Arg),
+    Rcr(Size, Arg, Arg),
+    /// `rdmsr`, read the model specific register `ecx` into `edx:eax`.
+    Rdmsr,
+    /// `rdrand reg`, read a random number.
+    Rdrand(Size, Reg),
+    /// `rdseed reg`, read a random seed.
+    Rdseed(Size, Reg),
+    /// `rdsspd reg` or `rdsspq reg`, read the shadow stack pointer.
+    Rdssp(Size, Reg),
+    /// `rdtsc`, read the time-stamp counter into `edx:eax`.
+    Rdtsc,
+    /// `rdtscp`, read the time-stamp counter into `edx:eax`, and the processor id into `ecx`.
+    Rdtscp,
+    /// `jmp rel32` (`E9`) given [`Arg::Imm32`], relative to the next instruction, otherwise an
+    /// indirect jump through a register or memory (`FF /4`).
+    Jmp(Arg),
+    /// `jmp rel8` (`EB`), relative to the next instruction.
+    JmpShort(i8),
     Ret,
+    /// `rstorssp m64`, restore a saved shadow stack pointer.
+    Rstorssp(Arg),
+    /// `saveprevssp`, save the previous shadow stack pointer.
+    Saveprevssp,
+    Rol(Size, Arg, Arg),
+    Ror(Size, Arg, Arg),
+    /// `rorx reg, r/m, imm8`, rotate right without affecting flags.
+    Rorx(Size, Reg, Arg, u8),
+    Sar(Size, Arg, Arg),
+    /// `sarx reg, r/m, reg`, shift arithmetic right without affecting flags.
+    Sarx(Size, Reg, Arg, Reg),
+    Sbb(Size, Arg, Arg),
+    /// `setcc r/m8`.
+    Setcc(Cond, Arg),
+    /// `sfence`, a store fence.
+    Sfence,
+    /// `setssbsy`, mark the shadow stack busy.
+    Setssbsy,
+    /// `sti`, set the interrupt flag.
+    Sti,
+    /// `stmxcsr m32`, store `mxcsr`.
+    Stmxcsr(Arg),
+    Shl(Size, Arg, Arg),
+    /// `shlx reg, r/m, reg`, shift left without affecting flags.
+    Shlx(Size, Reg, Arg, Reg),
+    Shr(Size, Arg, Arg),
+    /// `shrx reg, r/m, reg`, shift logical right without affecting flags.
+    Shrx(Size, Reg, Arg, Reg),
+    /// `addps`, `addpd`, `addss` or `addsd`.
+    SseAdd(Fp, Xmm, Arg),
+    /// `andps` or `andpd`.
+    SseAnd(Fp, Xmm, Arg),
+    /// `andnps` or `andnpd`.
+    SseAndn(Fp, Xmm, Arg),
+    /// `divps`, `divpd`, `divss` or `divsd`.
+    SseDiv(Fp, Xmm, Arg),
+    /// `maxps`, `maxpd`, `maxss` or `maxsd`.
+    SseMax(Fp, Xmm, Arg),
+    /// `minps`, `minpd`, `minss` or `minsd`.
+    SseMin(Fp, Xmm, Arg),
+    /// `movaps` or `movapd`, an aligned move.
+    SseMova(Fp, Arg, Arg),
+    /// `movups`, `movupd`, `movss` or `movsd`.
+    SseMovu(Fp, Arg, Arg),
+    /// `mulps`, `mulpd`, `mulss` or `mulsd`.
+    SseMul(Fp, Xmm, Arg),
+    /// `orps` or `orpd`.
+    SseOr(Fp, Xmm, Arg),
+    /// `sqrtps`, `sqrtpd`, `sqrtss` or `sqrtsd`.
+    SseSqrt(Fp, Xmm, Arg),
+    /// `subps`, `subpd`, `subss` or `subsd`.
+    SseSub(Fp, Xmm, Arg),
+    /// `xorps` or `xorpd`.
+    SseXor(Fp, Xmm, Arg),
+    Sub(Size, Arg, Arg),
+    /// `swapgs`, exchange the `gs` base with `IA32_KERNEL_GS_BASE`.
+    Swapgs,
     Syscall,
-    Xor(Reg, Reg),
+    /// `sysret` or `sysretq`.
+    Sysret(Size),
+    Test(Size, Arg, Arg),
+    /// `tzcnt reg, r/m`, count trailing zero bits.
+    Tzcnt(Size, Reg, Arg),
+    /// `ud2`, an undefined instruction.
+    Ud2,
+    /// A byte which could not be decoded, see [`InstIter::resync`].
+    ///
+    /// Encoded as the byte itself.
+    Unknown(u8),
+    /// `wait` (or `fwait`), wait for pending x87 exceptions.
+    Wait,
+    /// `wrmsr`, write `edx:eax` into the model specific register `ecx`.
+    Wrmsr,
+    /// `wrssd m, reg` or `wrssq m, reg`, write to the shadow stack.
+    Wrss(Size, Arg, Reg),
+    /// `wrussd m, reg` or `wrussq m, reg`, write to a user shadow stack.
+    Wruss(Size, Arg, Reg),
+    /// An x87 floating-point instruction.
+    X87(X87),
+    Xadd(Size, Arg, Reg),
+    Xchg(Size, Arg, Arg),
+    Xor(Size, Arg, Arg),
+    /// `xrstor mem` or `xrstor64 mem`, restore the extended state.
+    Xrstor(Size, Arg),
+    /// `xrstors mem` or `xrstors64 mem`, restore the extended state, including supervisor state.
+    Xrstors(Size, Arg),
+    /// `xsave mem` or `xsave64 mem`, save the extended state.
+    Xsave(Size, Arg),
+    /// `xsavec mem` or `xsavec64 mem`, save the extended state, compacted.
+    Xsavec(Size, Arg),
+    /// `xsaveopt mem` or `xsaveopt64 mem`, save the modified extended state.
+    Xsaveopt(Size, Arg),
+    /// `xsaves mem` or `xsaves64 mem`, save the extended state, compacted, including supervisor
+    /// state.
+    Xsaves(Size, Arg),
+}
+
+impl Inst {
+    /// Decode an instruction in `mode`.
+    ///
+    /// See [`Inst::try_from_bytes`] for why an instruction could not be decoded.
+    #[inline]
+    pub const fn from_bytes(bytes: &[u8], mode: Mode) -> Option<Inst> {
+        if let Some(len) = nop_len(bytes, mode) {
+            return Some(Inst::Nop(len));
+        }
+
+        let (prefixes, rest) = Prefixes::from_bytes(bytes, mode);
+        let prefixes_len = bytes.len() - rest.len();
+        let bytes = rest;
+        let rex = prefixes.rex;
+
+        let size = if rex.w() {
+            Size::Qword
+        } else {
+            prefixes.operand_size()
+        };
+
+        let inst = match bytes {
+            // pause (rep nop), with no other prefix
+            [0x90, ..] if prefixes.rep && prefixes_len == 1 => Inst::Pause,
+            [0x90, ..] if prefixes.rep => return None,
+
+            // nop (xchg rax, rax), with any other prefixes
+            [0x90, ..] if rex.b() == 0 => match prefixes_len {
+                0..=14 => Inst::Nop(prefixes_len as u8 + 1),
+                _ => return None,
+            },
+
+            // xchg reg, rax
+            [reg @ 0x90..=0x97, ..] => Inst::Xchg(
+                size,
+                Arg::Reg(tri!(Reg::from_bits(opcode_reg(*reg, rex)))),
+                Arg::Reg(Reg::Rax),
+            ),
+
+            // xchg r/m, reg
+            [op @ (0x86 | 0x87), rest @ ..] => {
+                let size = if *op == 0x86 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Xchg(
+                    size,
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                    tri!(gpr_arg(size, modrm.reg, prefixes)),
+                )
+            }
+
+            // cmpxchg/xadd r/m, reg
+            [0x0F, op @ (0xB0 | 0xB1 | 0xC0 | 0xC1), rest @ ..] => {
+                let size = if *op & 1 == 0 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+                let src = tri!(gpr(size, modrm.reg, prefixes));
+
+                if *op < 0xC0 {
+                    Inst::Cmpxchg(size, dst, src)
+                } else {
+                    Inst::Xadd(size, dst, src)
+                }
+            }
+
+            // mov r/m, reg
+            [op @ (0x88 | 0x89), rest @ ..] => {
+                let size = if *op == 0x88 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Mov(
+                    size,
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                    tri!(gpr_arg(size, modrm.reg, prefixes)),
+                )
+            }
+
+            // mov reg, r/m
+            [op @ (0x8A | 0x8B), rest @ ..] => {
+                let size = if *op == 0x8A { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Mov(
+                    size,
+                    tri!(gpr_arg(size, modrm.reg, prefixes)),
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                )
+            }
+
+            // movabs reg, i64
+            [reg @ 0xB8..=0xBF, a, b, c, d, e, f, g, h, ..] if rex.w() => Inst::Mov(
+                size,
+                Arg::Reg(tri!(Reg::from_bits(opcode_reg(*reg, rex)))),
+                Arg::Imm64(i64::from_le_bytes([*a, *b, *c, *d, *e, *f, *g, *h])),
+            ),
+
+            // mov reg, i16 / i32
+            [reg @ 0xB8..=0xBF, rest @ ..] if !rex.w() => Inst::Mov(
+                size,
+                Arg::Reg(tri!(Reg::from_bits(opcode_reg(*reg, rex)))),
+                tri!(read_imm_arg(size, rest, 0)),
+            ),
+
+            // mov reg8, i8
+            [reg @ 0xB0..=0xB7, imm, ..] => Inst::Mov(
+                Size::Byte,
+                tri!(gpr_arg(Size::Byte, opcode_reg(*reg, rex), prefixes)),
+                Arg::Imm8(*imm as i8),
+            ),
+
+            // mov r/m, i8 / i16 / i32 (sign-extended to 64-bit)
+            [op @ (0xC6 | 0xC7), rest @ ..] if modrm_digit(rest) == 0 => {
+                let size = if *op == 0xC6 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                let src = match size {
+                    Size::Byte => Arg::Imm8(tri!(read_i8(rest, modrm.len))),
+                    Size::Qword => Arg::Imm32(tri!(read_i32(rest, modrm.len))),
+                    _ => tri!(read_imm_arg(size, rest, modrm.len)),
+                };
+
+                Inst::Mov(size, dst, src)
+            }
+
+            // lea reg, m
+            [0x8D, rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Lea(
+                    size,
+                    tri!(Reg::from_bits(modrm.reg)),
+                    // the source must be memory
+                    match tri!(Arg::from_rm(size, modrm.rm, prefixes)) {
+                        mem @ Arg::Mem(_) => mem,
+                        _ => return None,
+                    },
+                )
+            }
+
+            // jmp
+            [0xE9, rest @ ..] if !prefixes.operand_size => {
+                Inst::Jmp(tri!(read_imm_arg(size, rest, 0)))
+            }
+
+            // jmp short
+            [0xEB, rel, ..] => Inst::JmpShort(*rel as i8),
+
+            // jcc
+            [0x0F, cc @ 0x80..=0x8F, rest @ ..] if !prefixes.operand_size => {
+                Inst::Jcc(Cond::from_bits(*cc), tri!(read_imm(size, rest, 0)))
+            }
+
+            // jcc short
+            [cc @ 0x70..=0x7F, rel, ..] => Inst::JccShort(Cond::from_bits(*cc), *rel as i8),
+
+            // call
+            [0xE8, rest @ ..] if !prefixes.operand_size => {
+                Inst::Call(tri!(read_imm_arg(size, rest, 0)))
+            }
+
+            // push imm8
+            [0x6A, imm, ..] if !prefixes.operand_size => Inst::Push(Arg::Imm8(*imm as i8)),
+
+            // push imm16 / imm32
+            [0x68, rest @ ..] if !prefixes.operand_size => {
+                Inst::Push(tri!(read_imm_arg(size, rest, 0)))
+            }
+
+            // evex, otherwise bound outside of long mode
+            [evex::EVEX, p0, ..]
+                if mode.has_rex() || (matches!(mode, Mode::Compat32) && *p0 >= 0xC0) =>
+            {
+                Inst::Evex(tri!(Evex::from_bytes(bytes, prefixes)))
+            }
+
+            // vex, otherwise les/lds outside of long mode
+            [vex::VEX3 | vex::VEX2, next, ..]
+                if mode.has_rex() || (matches!(mode, Mode::Compat32) && *next >= 0xC0) =>
+            {
+                let (vex, rest) = tri!(Vex::from_bytes(bytes, prefixes));
+
+                tri!(bmi(vex, rest, prefixes))
+            }
+
+            // x87
+            [0xD8..=0xDF, ..] => Inst::X87(tri!(X87::from_bytes(bytes, prefixes))),
+
+            // wait
+            [0x9B, ..] => Inst::Wait,
+
+            // syscall
+            [0x0F, 0x05, ..] => Inst::Syscall,
+
+            // cpuid
+            [0x0F, 0xA2, ..] => Inst::Cpuid,
+
+            // rdtsc
+            [0x0F, 0x31, ..] => Inst::Rdtsc,
+
+            // rdtscp
+            [0x0F, 0x01, 0xF9, ..] => Inst::Rdtscp,
+
+            // swapgs
+            [0x0F, 0x01, 0xF8, ..] if mode.has_rex() => Inst::Swapgs,
+
+            // invlpg m
+            [0x0F, 0x01, rest @ ..] if modrm_digit(rest) == 7 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Invlpg(tri!(Arg::from_mem(modrm.rm, prefixes)))
+            }
+
+            // sysret
+            [0x0F, 0x07, ..] => Inst::Sysret(rexw_size(rex)),
+
+            // rdmsr
+            [0x0F, 0x32, ..] => Inst::Rdmsr,
+
+            // wrmsr
+            [0x0F, 0x30, ..] => Inst::Wrmsr,
+
+            // iret
+            [0xCF, ..] => Inst::Iret(size),
+
+            // cbw/cwde/cdqe
+            [0x98, ..] => Inst::Cbw(size),
+
+            // cwd/cdq/cqo
+            [0x99, ..] => Inst::Cwd(size),
+
+            // pushf/popf
+            [op @ (0x9C | 0x9D), ..] => {
+                // 64-bit by default, a 32-bit form is not encodable
+                let size = match prefixes.operand_size() {
+                    Size::Dword if mode.has_rex() => Size::Qword,
+                    size => size,
+                };
+
+                if *op == 0x9C {
+                    Inst::Pushf(size)
+                } else {
+                    Inst::Popf(size)
+                }
+            }
+
+            // hlt
+            [0xF4, ..] => Inst::Hlt,
+
+            // in/out al/ax/eax, imm8 / dx
+            [op @ (0xE4..=0xE7 | 0xEC..=0xEF), rest @ ..] => {
+                // REX.W is ignored
+                let size = if *op & 1 == 0 {
+                    Size::Byte
+                } else {
+                    prefixes.operand_size()
+                };
+
+                let port = if *op & 0b1000 == 0 {
+                    Arg::Imm8(tri!(read_i8(rest, 0)))
+                } else {
+                    Arg::Reg(Reg::Rdx)
+                };
+
+                if *op & 0b10 == 0 {
+                    Inst::In(size, port)
+                } else {
+                    Inst::Out(size, port)
+                }
+            }
+
+            // cli
+            [0xFA, ..] => Inst::Cli,
+
+            // sti
+            [0xFB, ..] => Inst::Sti,
+
+            // ret
+            [0xC3, ..] => Inst::Ret,
+
+            // leave
+            [0xC9, ..] if !prefixes.operand_size => Inst::Leave,
+
+            // int3
+            [0xCC, ..] => Inst::Int3,
+
+            // int
+            [0xCD, imm, ..] => Inst::Int(*imm),
+
+            // ud2
+            [0x0F, 0x0B, ..] => Inst::Ud2,
+
+            // endbr64
+            [0x0F, 0x1E, 0xFA, ..] if prefixes.rep => Inst::Endbr64,
+
+            // endbr32
+            [0x0F, 0x1E, 0xFB, ..] if prefixes.rep => Inst::Endbr32,
+
+            // rdssp reg
+            [0x0F, 0x1E, modrm @ 0xC8..=0xCF, ..] if prefixes.rep => Inst::Rdssp(
+                rexw_size(rex),
+                tri!(Reg::from_bits(opcode_reg(*modrm, rex))),
+            ),
+
+            // saveprevssp
+            [0x0F, 0x01, 0xEA, ..] if prefixes.rep => Inst::Saveprevssp,
+
+            // setssbsy
+            [0x0F, 0x01, 0xE8, ..] if prefixes.rep => Inst::Setssbsy,
+
+            // rstorssp m64
+            [0x0F, 0x01, rest @ ..] if prefixes.rep && modrm_digit(rest) == 5 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Rstorssp(tri!(Arg::from_mem(modrm.rm, prefixes)))
+            }
+
+            // incssp reg / clrssbsy m64
+            [0x0F, 0xAE, rest @ ..] if prefixes.rep => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                match (modrm.reg & 0b111, modrm.rm) {
+                    (5, Rm::Reg(reg)) => Inst::Incssp(rexw_size(rex), tri!(Reg::from_bits(reg))),
+                    (6, rm) => Inst::Clrssbsy(tri!(Arg::from_mem(rm, prefixes))),
+                    _ => return None,
+                }
+            }
+
+            // three-byte opcode maps
+            [0x0F, map @ (map::MAP_0F38 | map::MAP_0F3A), opcode, rest @ ..] => {
+                let entry = tri!(Entry::lookup(*map, *opcode, prefixes));
+                let size = if entry.rexw { rexw_size(rex) } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let reg = Arg::Reg(tri!(Reg::from_bits(modrm.reg)));
+                let mem = tri!(Arg::from_mem(modrm.rm, prefixes));
+
+                match entry.form {
+                    Form::RegMem => tri!(entry.inst(size, reg, mem)),
+                    Form::MemReg => tri!(entry.inst(size, mem, reg)),
+                }
+            }
+
+            // prefetchnta/prefetcht0/prefetcht1/prefetcht2 m8
+            [0x0F, 0x18, rest @ ..] if modrm_digit(rest) <= 3 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let src = tri!(Arg::from_mem(modrm.rm, prefixes));
+
+                match modrm.reg & 0b111 {
+                    0 => Inst::Prefetchnta(src),
+                    1 => Inst::Prefetcht0(src),
+                    2 => Inst::Prefetcht1(src),
+                    _ => Inst::Prefetcht2(src),
+                }
+            }
+
+            // prefetchw m8
+            [0x0F, 0x0D, rest @ ..] if modrm_digit(rest) == 1 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Prefetchw(tri!(Arg::from_mem(modrm.rm, prefixes)))
+            }
+
+            // fxsave/fxrstor/ldmxcsr/stmxcsr/xsave/xrstor/xsaveopt/clflush m / fences
+            [0x0F, 0xAE, rest @ ..] if !prefixes.rep && !prefixes.operand_size => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let size = rexw_size(rex);
+
+                match (modrm.reg & 0b111, modrm.rm) {
+                    (5, Rm::Reg(_)) => Inst::Lfence,
+                    (6, Rm::Reg(_)) => Inst::Mfence,
+                    (7, Rm::Reg(_)) => Inst::Sfence,
+                    (digit, rm) => {
+                        let mem = tri!(Arg::from_mem(rm, prefixes));
+
+                        match digit {
+                            0 => Inst::Fxsave(size, mem),
+                            1 => Inst::Fxrstor(size, mem),
+                            2 => Inst::Ldmxcsr(mem),
+                            3 => Inst::Stmxcsr(mem),
+                            4 => Inst::Xsave(size, mem),
+                            5 => Inst::Xrstor(size, mem),
+                            6 => Inst::Xsaveopt(size, mem),
+                            _ => Inst::Clflush(mem),
+                        }
+                    }
+                }
+            }
+
+            // clflushopt/clwb m8
+            [0x0F, 0xAE, rest @ ..] if !prefixes.rep && modrm_digit(rest) >= 6 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let src = tri!(Arg::from_mem(modrm.rm, prefixes));
+
+                if modrm.reg & 0b111 == 7 {
+                    Inst::Clflushopt(src)
+                } else {
+                    Inst::Clwb(src)
+                }
+            }
+
+            // xrstors/xsavec/xsaves m
+            [0x0F, 0xC7, rest @ ..] if matches!(modrm_digit(rest), 3..=5) => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let size = rexw_size(rex);
+                let mem = tri!(Arg::from_mem(modrm.rm, prefixes));
+
+                match modrm.reg & 0b111 {
+                    3 => Inst::Xrstors(size, mem),
+                    4 => Inst::Xsavec(size, mem),
+                    _ => Inst::Xsaves(size, mem),
+                }
+            }
+
+            // alu r/m, reg / reg, r/m / acc, imm
+            [op @ 0x00..=0x3F, rest @ ..] if *op & 0b111 <= 5 => {
+                let size = if *op & 1 == 0 { Size::Byte } else { size };
+
+                let (dst, src) = match *op & 0b111 {
+                    0b100 => (Arg::Reg(Reg::Rax), Arg::Imm8(tri!(read_i8(rest, 0)))),
+                    0b101 => (Arg::Reg(Reg::Rax), tri!(read_imm_arg(size, rest, 0))),
+                    form => {
+                        let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                        let reg = tri!(gpr_arg(size, modrm.reg, prefixes));
+                        let rm = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                        if form & 0b010 == 0 {
+                            (rm, reg)
+                        } else {
+                            (reg, rm)
+                        }
+                    }
+                };
+
+                alu(*op >> 3, size, dst, src)
+            }
+
+            // test r/m, imm / not / neg / mul / imul / div / idiv / inc / dec / call / jmp / push
+            [op @ (0xF6 | 0xF7 | 0xFE | 0xFF), rest @ ..] => {
+                let (ext, width) = tri!(Group::lookup(*op, modrm_digit(rest)));
+
+                let size = match width {
+                    Width::Byte => Size::Byte,
+                    Width::Operand => size,
+                    // 64-bit by default, REX.W is not required
+                    Width::Addr if prefixes.operand_size => return None,
+                    Width::Addr => mode.addr_size(),
+                };
+
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let arg = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                let imm = match (ext, size) {
+                    (Ext::Test, Size::Byte) => Some(Arg::Imm8(tri!(read_i8(rest, modrm.len)))),
+                    (Ext::Test, size) => Some(tri!(read_imm_arg(size, rest, modrm.len))),
+                    _ => None,
+                };
+
+                tri!(ext.inst(size, arg, imm))
+            }
+
+            // test r/m, reg
+            [op @ (0x84 | 0x85), rest @ ..] => {
+                let size = if *op == 0x84 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Test(
+                    size,
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                    tri!(gpr_arg(size, modrm.reg, prefixes)),
+                )
+            }
+
+            // test al, imm8
+            [0xA8, rest @ ..] => Inst::Test(
+                Size::Byte,
+                Arg::Reg(Reg::Rax),
+                Arg::Imm8(tri!(read_i8(rest, 0))),
+            ),
+
+            // test eax, imm32
+            [0xA9, rest @ ..] => {
+                Inst::Test(size, Arg::Reg(Reg::Rax), tri!(read_imm_arg(size, rest, 0)))
+            }
+
+            // shift r/m, imm8 / 1 / cl
+            [op @ (0xC0 | 0xC1 | 0xD0..=0xD3), rest @ ..] => {
+                let size = if *op & 1 == 0 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                let count = match *op {
+                    0xC0 | 0xC1 => Arg::Imm8(tri!(read_i8(rest, modrm.len))),
+                    0xD0 | 0xD1 => Arg::Imm8(1),
+                    _ => Arg::Reg(Reg::Rcx),
+                };
+
+                shift(modrm.reg & 0b111, size, dst, count)
+            }
+
+            // movzx/movsx reg, r/m8 / r/m16
+            [0x0F, op @ (0xB6 | 0xB7 | 0xBE | 0xBF), rest @ ..] => {
+                let src_size = if *op & 1 == 0 { Size::Byte } else { Size::Word };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let reg = tri!(Reg::from_bits(modrm.reg));
+                let src = tri!(Arg::from_rm(src_size, modrm.rm, prefixes));
+
+                if *op < 0xBE {
+                    Inst::Movzx(size, reg, src_size, src)
+                } else {
+                    Inst::Movsx(size, reg, src_size, src)
+                }
+            }
+
+            // popcnt/tzcnt/lzcnt reg, r/m
+            [0x0F, op @ (0xB8 | 0xBC | 0xBD), rest @ ..] if prefixes.rep => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let reg = tri!(Reg::from_bits(modrm.reg));
+                let src = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                match *op {
+                    0xB8 => Inst::Popcnt(size, reg, src),
+                    0xBC => Inst::Tzcnt(size, reg, src),
+                    _ => Inst::Lzcnt(size, reg, src),
+                }
+            }
+
+            // bsf/bsr reg, r/m
+            [0x0F, op @ (0xBC | 0xBD), rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let reg = tri!(Reg::from_bits(modrm.reg));
+                let src = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                if *op == 0xBC {
+                    Inst::Bsf(size, reg, src)
+                } else {
+                    Inst::Bsr(size, reg, src)
+                }
+            }
+
+            // bt/bts/btr/btc r/m, reg
+            [0x0F, op @ (0xA3 | 0xAB | 0xB3 | 0xBB), rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+                let src = Arg::Reg(tri!(Reg::from_bits(modrm.reg)));
+
+                bit((*op >> 3) & 0b11, size, dst, src)
+            }
+
+            // bt/bts/btr/btc r/m, imm8
+            [0x0F, 0xBA, rest @ ..] if modrm_digit(rest) >= 4 => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+                let src = Arg::Imm8(tri!(read_i8(rest, modrm.len)));
+
+                bit(modrm.reg & 0b11, size, dst, src)
+            }
+
+            // rdrand/rdseed reg
+            [0x0F, 0xC7, rest @ ..] if matches!(modrm_digit(rest), 6 | 7) => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                let Rm::Reg(reg) = modrm.rm else {
+                    return None;
+                };
+
+                let reg = tri!(Reg::from_bits(reg));
+
+                if modrm.reg & 0b111 == 6 {
+                    Inst::Rdrand(size, reg)
+                } else {
+                    Inst::Rdseed(size, reg)
+                }
+            }
+
+            // movsxd reg, r/m32
+            [0x63, rest @ ..] if mode.has_rex() => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Movsx(
+                    size,
+                    tri!(Reg::from_bits(modrm.reg)),
+                    Size::Dword,
+                    tri!(Arg::from_rm(Size::Dword, modrm.rm, prefixes)),
+                )
+            }
+
+            // cmovcc reg, r/m
+            [0x0F, cc @ 0x40..=0x4F, rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Cmovcc(
+                    Cond::from_bits(*cc),
+                    size,
+                    tri!(Reg::from_bits(modrm.reg)),
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                )
+            }
+
+            // setcc r/m8
+            [0x0F, cc @ 0x90..=0x9F, rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Setcc(
+                    Cond::from_bits(*cc),
+                    tri!(Arg::from_rm(Size::Byte, modrm.rm, prefixes)),
+                )
+            }
+
+            // imul reg, r/m
+            [0x0F, 0xAF, rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                Inst::Imul2(
+                    size,
+                    tri!(Reg::from_bits(modrm.reg)),
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                )
+            }
+
+            // imul reg, r/m, imm
+            [op @ (0x69 | 0x6B), rest @ ..] => {
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+
+                let imm = if *op == 0x69 {
+                    tri!(read_imm_arg(size, rest, modrm.len))
+                } else {
+                    Arg::Imm8(tri!(read_i8(rest, modrm.len)))
+                };
+
+                Inst::Imul3(
+                    size,
+                    tri!(Reg::from_bits(modrm.reg)),
+                    tri!(Arg::from_rm(size, modrm.rm, prefixes)),
+                    imm,
+                )
+            }
+
+            // alu r/m, imm
+            [op @ (0x80 | 0x81 | 0x83), rest @ ..] => {
+                let size = if *op == 0x80 { Size::Byte } else { size };
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let dst = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+
+                let src = if *op == 0x81 {
+                    tri!(read_imm_arg(size, rest, modrm.len))
+                } else {
+                    Arg::Imm8(tri!(read_i8(rest, modrm.len)))
+                };
+
+                alu(modrm.reg & 0b111, size, dst, src)
+            }
+
+            // sse mov / arithmetic
+            [0x0F, op @ (0x10 | 0x11 | 0x28 | 0x29 | 0x51 | 0x54..=0x59 | 0x5C..=0x5F), rest @ ..] =>
+            {
+                let fp = Fp::from_prefixes(prefixes);
+                let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+                let reg = Xmm::from_bits(modrm.reg);
+                let rm = tri!(Arg::from_xmm_rm(modrm.rm, prefixes));
+
+                tri!(sse(*op, fp, reg, rm))
+            }
+
+            // inc/dec reg, outside of long mode
+            [op @ 0x40..=0x4F, ..] if !mode.has_rex() => {
+                let reg = Arg::Reg(tri!(Reg::from_bits(*op & 0b111)));
+
+                if *op < 0x48 {
+                    Inst::Inc(size, reg)
+                } else {
+                    Inst::Dec(size, reg)
+                }
+            }
+
+            // push reg
+            [reg @ 0x50..=0x57, ..] if !prefixes.operand_size => {
+                Inst::Push(Arg::Reg(tri!(Reg::from_bits(opcode_reg(*reg, rex)))))
+            }
+
+            // pop reg
+            [reg @ 0x58..=0x5F, ..] if !prefixes.operand_size => {
+                Inst::Pop(tri!(Reg::from_bits(opcode_reg(*reg, rex))))
+            }
+
+            _ => return None,
+        };
+
+        Some(inst)
+    }
+
+    /// Decode an instruction in `mode`, describing why it could not be decoded.
+    #[inline]
+    pub const fn try_from_bytes(bytes: &[u8], mode: Mode) -> Result<Inst, DecodeError> {
+        if let Some(inst) = Inst::from_bytes(bytes, mode) {
+            return Ok(inst);
+        }
+
+        if bytes.is_empty() {
+            return Err(DecodeError::TruncatedInstruction { needed: 1 });
+        }
+
+        // an instruction which decodes given more bytes is truncated
+        let mut padded = [0; 15];
+
+        if bytes.len() < padded.len() {
+            let mut index = 0;
+
+            while index < bytes.len() {
+                padded[index] = bytes[index];
+                index += 1;
+            }
+
+            if let Some(inst) = Inst::from_bytes(&padded, mode) {
+                match inst.len(mode) {
+                    Ok(needed) if needed > bytes.len() => {
+                        return Err(DecodeError::TruncatedInstruction { needed });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let (_, rest) = Prefixes::from_bytes(bytes, mode);
+        let offset = bytes.len() - rest.len();
+
+        // an instruction which decodes without its prefixes does not permit them
+        if offset > 0 && Inst::from_bytes(rest, mode).is_some() {
+            return Err(DecodeError::InvalidPrefix);
+        }
+
+        // the opcode follows any escape bytes
+        let (byte, offset) = match rest {
+            [0x0F, 0x38 | 0x3A, byte, ..] => (*byte, offset + 2),
+            [0x0F, 0x38 | 0x3A] => {
+                return Err(DecodeError::TruncatedInstruction { needed: offset + 3 });
+            }
+            [0x0F, byte, ..] => (*byte, offset + 1),
+            [0x0F] => return Err(DecodeError::TruncatedInstruction { needed: offset + 2 }),
+            [byte, ..] => (*byte, offset),
+            [] => return Err(DecodeError::TruncatedInstruction { needed: offset + 1 }),
+        };
+
+        Err(DecodeError::UnknownOpcode { byte, offset })
+    }
+
+    /// Decode an instruction in `mode`, returning it along with the number of bytes it occupies.
+    ///
+    /// The length is that of the bytes decoded, rather than of the canonical encoding.
+    #[inline]
+    pub const fn decode(bytes: &[u8], mode: Mode) -> Result<(Inst, usize), DecodeError> {
+        let inst = match Inst::try_from_bytes(bytes, mode) {
+            Ok(inst) => inst,
+            Err(error) => return Err(error),
+        };
+
+        // every decodable instruction has a length, the canonical length is merely a fallback
+        let len = match (len::inst_len(bytes, mode), inst.len(mode)) {
+            (Some(len), _) | (None, Ok(len)) => len,
+            (None, Err(_)) => return Err(DecodeError::InvalidPrefix),
+        };
+
+        Ok((inst, len))
+    }
+
+    /// Decode an instruction in `mode` as [`Inst::decode`] does, falling back to only its length.
+    ///
+    /// An instruction which cannot be decoded, but whose length can be determined from its
+    /// prefixes, opcode, ModRM, SIB, displacement and immediate, is returned as [`Inst::Other`].
+    #[inline]
+    pub const fn decode_len(bytes: &[u8], mode: Mode) -> Result<(Inst, usize), DecodeError> {
+        match Inst::decode(bytes, mode) {
+            Ok(decoded) => Ok(decoded),
+            Err(error) => match len::inst_len(bytes, mode) {
+                Some(len) => Ok((Inst::Other { len: len as u8 }, len)),
+                None => Err(error),
+            },
+        }
+    }
+
+    /// Encode this instruction for `mode`.
+    #[inline]
+    pub const fn to_bytes(&self, mode: Mode) -> Result<InstBytes, EncodeError> {
+        match self.encode(mode) {
+            Ok(writer) => Ok(writer.into_bytes()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Encode this instruction for `mode` to the start of `buf`, returning its length.
+    ///
+    /// Nothing is written if it cannot be encoded, or does not fit.
+    #[inline]
+    pub const fn encode_into(&self, mode: Mode, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        match self.encode(mode) {
+            Ok(writer) => writer.copy_to(buf),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Encode this instruction for `mode`, appending it to `vec`.
+    ///
+    /// Nothing is appended if it cannot be encoded.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn encode_to_vec(
+        &self,
+        mode: Mode,
+        vec: &mut alloc::vec::Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        let bytes = self.to_bytes(mode)?;
+
+        vec.extend_from_slice(&bytes);
+
+        Ok(())
+    }
+
+    /// Encode a sequence of instructions for `mode`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn encode_all<I>(insts: I, mode: Mode) -> Result<alloc::vec::Vec<u8>, EncodeError>
+    where
+        I: IntoIterator<Item = Inst>,
+    {
+        let mut vec = alloc::vec::Vec::new();
+
+        for inst in insts {
+            inst.encode_to_vec(mode, &mut vec)?;
+        }
+
+        Ok(vec)
+    }
+
+    #[inline]
+    const fn encode(&self, mode: Mode) -> Result<Writer, EncodeError> {
+        let mut writer = Writer::new(mode);
+
+        let inst = match self.high_as_low() {
+            Ok(Some(inst)) => {
+                writer.write_high();
+                inst
+            }
+            Ok(None) => *self,
+            Err(error) => return Err(error),
+        };
+
+        match inst {
+            Inst::Call(rel @ (Arg::Imm16(_) | Arg::Imm32(_))) => {
+                writer.write_u8(0xE8);
+                writer.write_imm(mode.default_size(), imm_z(rel));
+            }
+            Inst::Call(target) => {
+                encode_group(&mut writer, Ext::Call, Width::Addr, target);
+            }
+            Inst::Jcc(cond, rel) => {
+                writer.write_bytes(&[0x0F, 0x80 | cond.bits()]);
+                writer.write_imm(mode.default_size(), rel);
+            }
+            Inst::JccShort(cond, rel) => {
+                writer.write_bytes(&[0x70 | cond.bits(), rel as u8]);
+            }
+            Inst::Lea(size, reg, mem @ Arg::Mem(_)) => {
+                encode_rm(&mut writer, size, &[0x8D], reg, mem);
+            }
+            Inst::Jmp(rel @ (Arg::Imm16(_) | Arg::Imm32(_))) => {
+                writer.write_u8(0xE9);
+                writer.write_imm(mode.default_size(), imm_z(rel));
+            }
+            Inst::Jmp(target) => {
+                encode_group(&mut writer, Ext::Jmp, Width::Addr, target);
+            }
+            Inst::JmpShort(rel) => {
+                writer.write_bytes(&[0xEB, rel as u8]);
+            }
+            Inst::Mov(
+                size @ (Size::Word | Size::Dword),
+                Arg::Reg(reg),
+                imm @ (Arg::Imm16(_) | Arg::Imm32(_)),
+            ) => {
+                writer.write_size(size);
+                writer.write_rex(Rex::new(false, 0, 0, reg.bits()));
+                writer.write_u8(0xB8 | reg.base_bits());
+                writer.write_imm(size, imm_z(imm));
+            }
+            Inst::Mov(Size::Qword, reg @ Arg::Reg(_), Arg::Imm32(imm)) => {
+                encode_ext(&mut writer, Size::Qword, &[0xC7], 0, reg);
+                writer.write_i32(imm);
+            }
+            Inst::Mov(Size::Byte, Arg::Reg(reg), Arg::Imm8(imm)) => {
+                let rex = Rex::new(false, 0, 0, reg.bits());
+
+                // spl, bpl, sil and dil require a REX prefix
+                if matches!(reg.bits(), 4..=7) {
+                    writer.write_rex(rex.present());
+                } else {
+                    writer.write_rex(rex);
+                }
+
+                writer.write_bytes(&[0xB0 | reg.base_bits(), imm as u8]);
+            }
+            Inst::Mov(Size::Byte, dst @ Arg::Mem(_), Arg::Imm8(imm)) => {
+                encode_ext(&mut writer, Size::Byte, &[0xC6], 0, dst);
+                writer.write_u8(imm as u8);
+            }
+            Inst::Mov(size, dst @ Arg::Mem(_), imm @ (Arg::Imm16(_) | Arg::Imm32(_)))
+                if !matches!(size, Size::Byte) =>
+            {
+                encode_ext(&mut writer, size, &[0xC7], 0, dst);
+                writer.write_imm(size, imm_z(imm));
+            }
+            Inst::Mov(Size::Qword, Arg::Reg(reg), Arg::Imm64(imm)) => {
+                writer.write_rex(Rex::new(true, 0, 0, reg.bits()));
+                writer.write_u8(0xB8 | reg.base_bits());
+                writer.write_i64(imm);
+            }
+            Inst::Mov(size, dst, Arg::Reg(reg)) => {
+                let op = if matches!(size, Size::Byte) {
+                    0x88
+                } else {
+                    0x89
+                };
+
+                encode_rm(&mut writer, size, &[op], reg, dst);
+            }
+            Inst::Mov(size, Arg::Reg(reg), src @ Arg::Mem(_)) => {
+                let op = if matches!(size, Size::Byte) {
+                    0x8A
+                } else {
+                    0x8B
+                };
+
+                encode_rm(&mut writer, size, &[op], reg, src);
+            }
+            Inst::Pop(reg) => {
+                writer.write_rex(Rex::new(false, 0, 0, reg.bits()));
+                writer.write_u8(0x58 | reg.base_bits());
+            }
+            Inst::Push(Arg::Reg(reg)) => {
+                writer.write_rex(Rex::new(false, 0, 0, reg.bits()));
+                writer.write_u8(0x50 | reg.base_bits());
+            }
+            Inst::Push(Arg::Imm8(imm)) => {
+                writer.write_bytes(&[0x6A, imm as u8]);
+            }
+            Inst::Push(imm @ (Arg::Imm16(_) | Arg::Imm32(_))) => {
+                writer.write_u8(0x68);
+                writer.write_imm(mode.default_size(), imm_z(imm));
+            }
+            Inst::Push(src) => {
+                encode_group(&mut writer, Ext::Push, Width::Addr, src);
+            }
+            Inst::Nop(len) => {
+                writer.write_nop(len as usize);
+            }
+            Inst::Other { len } => {
+                let mut len = len;
+
+                while len > 0 {
+                    writer.write_u8(0xCC);
+                    len -= 1;
+                }
+            }
+            Inst::Unknown(byte) => {
+                writer.write_u8(byte);
+            }
+            Inst::Ret => {
+                writer.write_u8(0xC3);
+            }
+            Inst::Leave => {
+                writer.write_u8(0xC9);
+            }
+            Inst::Pause => {
+                writer.write_bytes(&[0xF3, 0x90]);
+            }
+            Inst::Int3 => {
+                writer.write_u8(0xCC);
+            }
+            Inst::Int(imm) => {
+                writer.write_bytes(&[0xCD, imm]);
+            }
+            Inst::Ud2 => {
+                writer.write_bytes(&[0x0F, 0x0B]);
+            }
+            Inst::Syscall => {
+                writer.write_bytes(&[0x0F, 0x05]);
+            }
+            Inst::Cpuid => {
+                writer.write_bytes(&[0x0F, 0xA2]);
+            }
+            Inst::Rdtsc => {
+                writer.write_bytes(&[0x0F, 0x31]);
+            }
+            Inst::Rdtscp => {
+                writer.write_bytes(&[0x0F, 0x01, 0xF9]);
+            }
+            Inst::Swapgs => {
+                writer.write_bytes(&[0x0F, 0x01, 0xF8]);
+            }
+            Inst::Invlpg(src) => {
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0x01], 7, src);
+            }
+            Inst::Sysret(size) => {
+                writer.write_rex(Rex::new(rex_w(size), 0, 0, 0));
+                writer.write_bytes(&[0x0F, 0x07]);
+            }
+            Inst::Rdmsr => {
+                writer.write_bytes(&[0x0F, 0x32]);
+            }
+            Inst::Wrmsr => {
+                writer.write_bytes(&[0x0F, 0x30]);
+            }
+            Inst::Iret(size) => {
+                writer.write_size(size);
+                writer.write_rex(Rex::new(rex_w(size), 0, 0, 0));
+                writer.write_u8(0xCF);
+            }
+            Inst::Cbw(size) | Inst::Cwd(size) => {
+                let opcode = if matches!(self, Inst::Cbw(..)) {
+                    0x98
+                } else {
+                    0x99
+                };
+
+                writer.write_size(size);
+                writer.write_rex(Rex::new(rex_w(size), 0, 0, 0));
+                writer.write_u8(opcode);
+            }
+            Inst::Pushf(size) | Inst::Popf(size) => {
+                let opcode = if matches!(self, Inst::Pushf(..)) {
+                    0x9C
+                } else {
+                    0x9D
+                };
+
+                writer.write_size(size);
+                writer.write_u8(opcode);
+            }
+            Inst::Hlt => {
+                writer.write_u8(0xF4);
+            }
+            Inst::In(size, port) | Inst::Out(size, port) => {
+                let mut opcode = if matches!(self, Inst::In(..)) {
+                    0xE4
+                } else {
+                    0xE6
+                };
+
+                if !matches!(size, Size::Byte) {
+                    opcode |= 1;
+                }
+
+                writer.write_size(size);
+
+                match port {
+                    Arg::Imm8(port) => writer.write_bytes(&[opcode, port as u8]),
+                    Arg::Reg(Reg::Rdx) => writer.write_u8(opcode | 0b1000),
+                    _ => return Err(EncodeError::InvalidOperands),
+                }
+            }
+            Inst::Cli => {
+                writer.write_u8(0xFA);
+            }
+            Inst::Sti => {
+                writer.write_u8(0xFB);
+            }
+            Inst::Evex(evex) => {
+                evex.encode(&mut writer);
+            }
+            Inst::X87(x87) => {
+                x87.encode(&mut writer);
+            }
+            Inst::Wait => {
+                writer.write_u8(0x9B);
+            }
+            Inst::Endbr64 => {
+                writer.write_bytes(&[0xF3, 0x0F, 0x1E, 0xFA]);
+            }
+            Inst::Endbr32 => {
+                writer.write_bytes(&[0xF3, 0x0F, 0x1E, 0xFB]);
+            }
+            Inst::Saveprevssp => {
+                writer.write_bytes(&[0xF3, 0x0F, 0x01, 0xEA]);
+            }
+            Inst::Setssbsy => {
+                writer.write_bytes(&[0xF3, 0x0F, 0x01, 0xE8]);
+            }
+            Inst::Rdssp(size, reg) => {
+                encode_rep_ext(&mut writer, size, &[0x0F, 0x1E], 1, Arg::Reg(reg));
+            }
+            Inst::Incssp(size, reg) => {
+                encode_rep_ext(&mut writer, size, &[0x0F, 0xAE], 5, Arg::Reg(reg));
+            }
+            Inst::Rstorssp(dst) => {
+                let size = mode.default_size();
+
+                encode_rep_ext(&mut writer, size, &[0x0F, 0x01], 5, dst);
+            }
+            Inst::Clrssbsy(dst) => {
+                let size = mode.default_size();
+
+                encode_rep_ext(&mut writer, size, &[0x0F, 0xAE], 6, dst);
+            }
+            Inst::Movbe(..) | Inst::Wrss(..) | Inst::Wruss(..) => {
+                let Some((entry, size, reg, rm)) = self.map_entry() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                encode_map(&mut writer, entry, size, reg, rm);
+            }
+            Inst::Popcnt(size, reg, src)
+            | Inst::Tzcnt(size, reg, src)
+            | Inst::Lzcnt(size, reg, src) => {
+                let opcode = match self {
+                    Inst::Popcnt(..) => 0xB8,
+                    Inst::Tzcnt(..) => 0xBC,
+                    _ => 0xBD,
+                };
+
+                encode_rep_rm(&mut writer, size, &[0x0F, opcode], reg, src);
+            }
+            Inst::Rdrand(size, reg) => {
+                encode_ext(&mut writer, size, &[0x0F, 0xC7], 6, Arg::Reg(reg));
+            }
+            Inst::Rdseed(size, reg) => {
+                encode_ext(&mut writer, size, &[0x0F, 0xC7], 7, Arg::Reg(reg));
+            }
+            Inst::Prefetchnta(src)
+            | Inst::Prefetcht0(src)
+            | Inst::Prefetcht1(src)
+            | Inst::Prefetcht2(src) => {
+                let digit = match self {
+                    Inst::Prefetchnta(..) => 0,
+                    Inst::Prefetcht0(..) => 1,
+                    Inst::Prefetcht1(..) => 2,
+                    _ => 3,
+                };
+
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0x18], digit, src);
+            }
+            Inst::Prefetchw(src) => {
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0x0D], 1, src);
+            }
+            Inst::Clflush(src) => {
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0xAE], 7, src);
+            }
+            Inst::Ldmxcsr(src) => {
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0xAE], 2, src);
+            }
+            Inst::Stmxcsr(dst) => {
+                encode_ext(&mut writer, mode.default_size(), &[0x0F, 0xAE], 3, dst);
+            }
+            Inst::Fxsave(size, mem)
+            | Inst::Fxrstor(size, mem)
+            | Inst::Xsave(size, mem)
+            | Inst::Xrstor(size, mem)
+            | Inst::Xsaveopt(size, mem) => {
+                let digit = match self {
+                    Inst::Fxsave(..) => 0,
+                    Inst::Fxrstor(..) => 1,
+                    Inst::Xsave(..) => 4,
+                    Inst::Xrstor(..) => 5,
+                    _ => 6,
+                };
+
+                encode_rexw_ext(&mut writer, size, &[0x0F, 0xAE], digit, mem);
+            }
+            Inst::Xrstors(size, mem) | Inst::Xsavec(size, mem) | Inst::Xsaves(size, mem) => {
+                let digit = match self {
+                    Inst::Xrstors(..) => 3,
+                    Inst::Xsavec(..) => 4,
+                    _ => 5,
+                };
+
+                encode_rexw_ext(&mut writer, size, &[0x0F, 0xC7], digit, mem);
+            }
+            Inst::Lfence => {
+                writer.write_bytes(&[0x0F, 0xAE, 0xE8]);
+            }
+            Inst::Mfence => {
+                writer.write_bytes(&[0x0F, 0xAE, 0xF0]);
+            }
+            Inst::Sfence => {
+                writer.write_bytes(&[0x0F, 0xAE, 0xF8]);
+            }
+            Inst::Clflushopt(src) | Inst::Clwb(src) => {
+                let Some(rm) = src.to_rm() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                let digit = if matches!(self, Inst::Clflushopt(..)) {
+                    7
+                } else {
+                    6
+                };
+
+                // 66 is a mandatory prefix, rather than an operand-size override
+                let mut prefixes = src.prefixes(mode.default_size(), rm.rex(false, 0), mode);
+
+                prefixes.operand_size = true;
+
+                writer.write_op(prefixes, &[0x0F, 0xAE], digit, rm);
+            }
+            Inst::Bsf(size, reg, src) | Inst::Bsr(size, reg, src) => {
+                let opcode = if matches!(self, Inst::Bsf(..)) {
+                    0xBC
+                } else {
+                    0xBD
+                };
+
+                encode_rm(&mut writer, size, &[0x0F, opcode], reg, src);
+            }
+            Inst::Bt(size, dst, src)
+            | Inst::Bts(size, dst, src)
+            | Inst::Btr(size, dst, src)
+            | Inst::Btc(size, dst, src) => {
+                let Some(op) = self.bit_op() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                match src {
+                    Arg::Reg(reg) => {
+                        encode_rm(&mut writer, size, &[0x0F, 0xA3 | op << 3], reg, dst);
+                    }
+                    Arg::Imm8(imm) => {
+                        encode_ext(&mut writer, size, &[0x0F, 0xBA], 4 | op, dst);
+                        writer.write_u8(imm as u8);
+                    }
+                    _ => return Err(EncodeError::InvalidOperands),
+                }
+            }
+            Inst::Andn(size, reg, src1, src2)
+            | Inst::Mulx(size, reg, src1, src2)
+            | Inst::Pdep(size, reg, src1, src2)
+            | Inst::Pext(size, reg, src1, src2) => {
+                let (pp, opcode) = match self {
+                    Inst::Andn(..) => (vex::PP_NONE, 0xF2),
+                    Inst::Mulx(..) => (vex::PP_F2, 0xF6),
+                    Inst::Pdep(..) => (vex::PP_F2, 0xF5),
+                    _ => (vex::PP_F3, 0xF5),
+                };
+
+                let vex = Vex::new(vex::MAP_0F38, pp, rex_w(size), src1.bits());
+
+                encode_vex(&mut writer, vex, opcode, reg.bits(), src2);
+            }
+            Inst::Bextr(size, reg, src, ctl)
+            | Inst::Bzhi(size, reg, src, ctl)
+            | Inst::Sarx(size, reg, src, ctl)
+            | Inst::Shlx(size, reg, src, ctl)
+            | Inst::Shrx(size, reg, src, ctl) => {
+                let (pp, opcode) = match self {
+                    Inst::Bextr(..) => (vex::PP_NONE, 0xF7),
+                    Inst::Bzhi(..) => (vex::PP_NONE, 0xF5),
+                    Inst::Sarx(..) => (vex::PP_F3, 0xF7),
+                    Inst::Shlx(..) => (vex::PP_66, 0xF7),
+                    _ => (vex::PP_F2, 0xF7),
+                };
+
+                let vex = Vex::new(vex::MAP_0F38, pp, rex_w(size), ctl.bits());
+
+                encode_vex(&mut writer, vex, opcode, reg.bits(), src);
+            }
+            Inst::Blsr(size, reg, src)
+            | Inst::Blsmsk(size, reg, src)
+            | Inst::Blsi(size, reg, src) => {
+                let digit = match self {
+                    Inst::Blsr(..) => 1,
+                    Inst::Blsmsk(..) => 2,
+                    _ => 3,
+                };
+
+                let vex = Vex::new(vex::MAP_0F38, vex::PP_NONE, rex_w(size), reg.bits());
+
+                encode_vex(&mut writer, vex, 0xF3, digit, src);
+            }
+            Inst::Rorx(size, reg, src, imm) => {
+                let vex = Vex::new(vex::MAP_0F3A, vex::PP_F2, rex_w(size), 0);
+
+                encode_vex(&mut writer, vex, 0xF0, reg.bits(), src);
+                writer.write_u8(imm);
+            }
+            Inst::Inc(size, Arg::Reg(reg)) | Inst::Dec(size, Arg::Reg(reg))
+                if !mode.has_rex() && !matches!(size, Size::Byte) =>
+            {
+                let opcode = if matches!(self, Inst::Inc(..)) {
+                    0x40
+                } else {
+                    0x48
+                };
+
+                writer.write_size(size);
+                writer.write_u8(opcode | reg.base_bits());
+            }
+            Inst::Inc(size, arg)
+            | Inst::Dec(size, arg)
+            | Inst::Not(size, arg)
+            | Inst::Neg(size, arg)
+            | Inst::Mul(size, arg)
+            | Inst::Imul(size, arg)
+            | Inst::Div(size, arg)
+            | Inst::Idiv(size, arg) => {
+                let ext = match self {
+                    Inst::Inc(..) => Ext::Inc,
+                    Inst::Dec(..) => Ext::Dec,
+                    Inst::Not(..) => Ext::Not,
+                    Inst::Neg(..) => Ext::Neg,
+                    Inst::Mul(..) => Ext::Mul,
+                    Inst::Imul(..) => Ext::Imul,
+                    Inst::Div(..) => Ext::Div,
+                    _ => Ext::Idiv,
+                };
+
+                let width = if matches!(size, Size::Byte) {
+                    Width::Byte
+                } else {
+                    Width::Operand
+                };
+
+                encode_group_sized(&mut writer, ext, width, size, arg);
+            }
+            Inst::Rol(size, dst, count)
+            | Inst::Ror(size, dst, count)
+            | Inst::Rcl(size, dst, count)
+            | Inst::Rcr(size, dst, count)
+            | Inst::Shl(size, dst, count)
+            | Inst::Shr(size, dst, count)
+            | Inst::Sar(size, dst, count) => {
+                let Some(op) = self.shift_op() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                let byte = matches!(size, Size::Byte);
+
+                match count {
+                    Arg::Imm8(1) => {
+                        let opcode = if byte { 0xD0 } else { 0xD1 };
+
+                        encode_ext(&mut writer, size, &[opcode], op, dst);
+                    }
+                    Arg::Imm8(imm) => {
+                        let opcode = if byte { 0xC0 } else { 0xC1 };
+
+                        encode_ext(&mut writer, size, &[opcode], op, dst);
+                        writer.write_u8(imm as u8);
+                    }
+                    Arg::Reg(Reg::Rcx) => {
+                        let opcode = if byte { 0xD2 } else { 0xD3 };
+
+                        encode_ext(&mut writer, size, &[opcode], op, dst);
+                    }
+                    _ => return Err(EncodeError::InvalidOperands),
+                }
+            }
+            Inst::Movzx(size, reg, src_size, src) | Inst::Movsx(size, reg, src_size, src) => {
+                let opcode: &[u8] = match (self, src_size) {
+                    (Inst::Movzx(..), Size::Byte) => &[0x0F, 0xB6],
+                    (Inst::Movzx(..), Size::Word) => &[0x0F, 0xB7],
+                    (Inst::Movsx(..), Size::Byte) => &[0x0F, 0xBE],
+                    (Inst::Movsx(..), Size::Word) => &[0x0F, 0xBF],
+                    (Inst::Movsx(..), Size::Dword) => &[0x63],
+                    _ => return Err(EncodeError::InvalidOperands),
+                };
+
+                let Some(rm) = src.to_rm() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                let rex = op_rex(size, reg, rm);
+
+                // byte sources of spl, bpl, sil and dil require a REX prefix
+                let rex = if matches!(src_size, Size::Byte) && matches!(rm, Rm::Reg(4..=7)) {
+                    rex.present()
+                } else {
+                    rex
+                };
+
+                writer.write_op(src.prefixes(size, rex, mode), opcode, reg.bits(), rm);
+            }
+            Inst::Xchg(size, Arg::Reg(Reg::Rax), Arg::Reg(reg))
+            | Inst::Xchg(size, Arg::Reg(reg), Arg::Reg(Reg::Rax))
+                if !matches!(size, Size::Byte) && !matches!(reg, Reg::Rax) =>
+            {
+                writer.write_size(size);
+                writer.write_rex(Rex::new(rex_w(size), 0, 0, reg.bits()));
+                writer.write_u8(0x90 | reg.base_bits());
+            }
+            Inst::Xchg(size, dst, Arg::Reg(reg)) => {
+                let opcode = if matches!(size, Size::Byte) {
+                    0x86
+                } else {
+                    0x87
+                };
+
+                encode_rm(&mut writer, size, &[opcode], reg, dst);
+            }
+            Inst::Cmpxchg(size, dst, reg) => {
+                let opcode = if matches!(size, Size::Byte) {
+                    0xB0
+                } else {
+                    0xB1
+                };
+
+                encode_rm(&mut writer, size, &[0x0F, opcode], reg, dst);
+            }
+            Inst::Xadd(size, dst, reg) => {
+                let opcode = if matches!(size, Size::Byte) {
+                    0xC0
+                } else {
+                    0xC1
+                };
+
+                encode_rm(&mut writer, size, &[0x0F, opcode], reg, dst);
+            }
+            Inst::Cmovcc(cond, size, reg, src) => {
+                encode_rm(&mut writer, size, &[0x0F, 0x40 | cond.bits()], reg, src);
+            }
+            Inst::Setcc(cond, dst) => {
+                encode_ext(&mut writer, Size::Byte, &[0x0F, 0x90 | cond.bits()], 0, dst);
+            }
+            Inst::Imul2(size, reg, src) => {
+                encode_rm(&mut writer, size, &[0x0F, 0xAF], reg, src);
+            }
+            Inst::Imul3(size, reg, src, Arg::Imm8(imm)) => {
+                encode_rm(&mut writer, size, &[0x6B], reg, src);
+                writer.write_u8(imm as u8);
+            }
+            Inst::Imul3(size, reg, src, imm @ (Arg::Imm16(_) | Arg::Imm32(_))) => {
+                encode_rm(&mut writer, size, &[0x69], reg, src);
+                writer.write_imm(size, imm_z(imm));
+            }
+            Inst::Test(size, dst, src) => {
+                let byte = matches!(size, Size::Byte);
+
+                match (dst, src) {
+                    (Arg::Reg(Reg::Rax), Arg::Imm8(imm)) if byte => {
+                        writer.write_bytes(&[0xA8, imm as u8]);
+                    }
+                    (Arg::Reg(Reg::Rax), imm @ (Arg::Imm16(_) | Arg::Imm32(_))) if !byte => {
+                        writer.write_size(size);
+                        writer.write_rex(Rex::new(rex_w(size), 0, 0, 0));
+                        writer.write_u8(0xA9);
+                        writer.write_imm(size, imm_z(imm));
+                    }
+                    (dst, Arg::Imm8(imm)) if byte => {
+                        encode_ext(&mut writer, size, &[0xF6], 0, dst);
+                        writer.write_u8(imm as u8);
+                    }
+                    (dst, imm @ (Arg::Imm16(_) | Arg::Imm32(_))) if !byte => {
+                        encode_ext(&mut writer, size, &[0xF7], 0, dst);
+                        writer.write_imm(size, imm_z(imm));
+                    }
+                    (dst, Arg::Reg(reg)) => {
+                        let opcode = if byte { 0x84 } else { 0x85 };
+
+                        encode_rm(&mut writer, size, &[opcode], reg, dst);
+                    }
+                    _ => return Err(EncodeError::InvalidOperands),
+                }
+            }
+            Inst::Adc(size, dst, src)
+            | Inst::Add(size, dst, src)
+            | Inst::And(size, dst, src)
+            | Inst::Cmp(size, dst, src)
+            | Inst::Or(size, dst, src)
+            | Inst::Sbb(size, dst, src)
+            | Inst::Sub(size, dst, src)
+            | Inst::Xor(size, dst, src) => {
+                let Some(op) = self.alu_op() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                let byte = matches!(size, Size::Byte);
+                let base = op << 3;
+
+                match (dst, src) {
+                    (Arg::Reg(Reg::Rax), Arg::Imm8(imm)) if byte => {
+                        writer.write_bytes(&[base | 0x04, imm as u8]);
+                    }
+                    (Arg::Reg(Reg::Rax), imm @ (Arg::Imm16(_) | Arg::Imm32(_))) if !byte => {
+                        writer.write_size(size);
+                        writer.write_rex(Rex::new(rex_w(size), 0, 0, 0));
+                        writer.write_u8(base | 0x05);
+                        writer.write_imm(size, imm_z(imm));
+                    }
+                    (dst, Arg::Imm8(imm)) => {
+                        let opcode = if byte { 0x80 } else { 0x83 };
+
+                        encode_ext(&mut writer, size, &[opcode], op, dst);
+                        writer.write_u8(imm as u8);
+                    }
+                    (dst, imm @ (Arg::Imm16(_) | Arg::Imm32(_))) if !byte => {
+                        encode_ext(&mut writer, size, &[0x81], op, dst);
+                        writer.write_imm(size, imm_z(imm));
+                    }
+                    (dst, Arg::Reg(reg)) => {
+                        let opcode = if byte { base } else { base | 0x01 };
+
+                        encode_rm(&mut writer, size, &[opcode], reg, dst);
+                    }
+                    (Arg::Reg(reg), src @ Arg::Mem(_)) => {
+                        let opcode = if byte { base | 0x02 } else { base | 0x03 };
+
+                        encode_rm(&mut writer, size, &[opcode], reg, src);
+                    }
+                    _ => return Err(EncodeError::InvalidOperands),
+                }
+            }
+            Inst::SseMova(fp, Arg::Xmm(reg), src) => {
+                encode_sse(&mut writer, fp, 0x28, reg, src);
+            }
+            Inst::SseMova(fp, dst, Arg::Xmm(reg)) => {
+                encode_sse(&mut writer, fp, 0x29, reg, dst);
+            }
+            Inst::SseMovu(fp, Arg::Xmm(reg), src) => {
+                encode_sse(&mut writer, fp, 0x10, reg, src);
+            }
+            Inst::SseMovu(fp, dst, Arg::Xmm(reg)) => {
+                encode_sse(&mut writer, fp, 0x11, reg, dst);
+            }
+            Inst::SseAdd(fp, reg, src)
+            | Inst::SseAnd(fp, reg, src)
+            | Inst::SseAndn(fp, reg, src)
+            | Inst::SseDiv(fp, reg, src)
+            | Inst::SseMax(fp, reg, src)
+            | Inst::SseMin(fp, reg, src)
+            | Inst::SseMul(fp, reg, src)
+            | Inst::SseOr(fp, reg, src)
+            | Inst::SseSqrt(fp, reg, src)
+            | Inst::SseSub(fp, reg, src)
+            | Inst::SseXor(fp, reg, src) => {
+                let Some(op) = self.sse_op() else {
+                    return Err(EncodeError::InvalidOperands);
+                };
+
+                encode_sse(&mut writer, fp, op, reg, src);
+            }
+            _ => return Err(EncodeError::InvalidOperands),
+        }
+
+        writer.finish()
+    }
+
+    /// Returns the SSE arithmetic opcode (following `0F`) of this instruction.
+    #[inline]
+    const fn sse_op(&self) -> Option<u8> {
+        let op = match self {
+            Inst::SseSqrt(..) => 0x51,
+            Inst::SseAnd(..) => 0x54,
+            Inst::SseAndn(..) => 0x55,
+            Inst::SseOr(..) => 0x56,
+            Inst::SseXor(..) => 0x57,
+            Inst::SseAdd(..) => 0x58,
+            Inst::SseMul(..) => 0x59,
+            Inst::SseSub(..) => 0x5C,
+            Inst::SseMin(..) => 0x5D,
+            Inst::SseDiv(..) => 0x5E,
+            Inst::SseMax(..) => 0x5F,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// Returns the three-byte opcode map entry of this instruction, along with its operand size,
+    /// `reg` operand and `r/m` operand.
+    #[inline]
+    const fn map_entry(&self) -> Option<(Entry, Size, Reg, Arg)> {
+        let (op, form, size, reg, rm) = match *self {
+            Inst::Movbe(size, Arg::Reg(reg), rm @ Arg::Mem(_)) => {
+                (Op::Movbe, Form::RegMem, size, reg, rm)
+            }
+            Inst::Movbe(size, rm @ Arg::Mem(_), Arg::Reg(reg)) => {
+                (Op::Movbe, Form::MemReg, size, reg, rm)
+            }
+            Inst::Wrss(size, rm, reg) => (Op::Wrss, Form::MemReg, size, reg, rm),
+            Inst::Wruss(size, rm, reg) => (Op::Wruss, Form::MemReg, size, reg, rm),
+            _ => return None,
+        };
+
+        match Entry::of(op, form) {
+            Some(entry) => Some((entry, size, reg, rm)),
+            None => None,
+        }
+    }
+
+    /// Returns this instruction with `ah`, `ch`, `dh` and `bh` as `spl`, `bpl`, `sil` and `dil`,
+    /// which share their encoding without a REX prefix, if it has any.
+    ///
+    /// Fails if it also has `spl`, `bpl`, `sil` or `dil`, which require a REX prefix.
+    #[inline]
+    const fn high_as_low(&self) -> Result<Option<Inst>, EncodeError> {
+        let mut high = false;
+        let mut low = false;
+
+        macro_rules! low {
+            ($size:expr, $arg:expr) => {
+                match high_as_low($size, $arg, &mut high, &mut low) {
+                    Some(arg) => arg,
+                    None => return Err(EncodeError::InvalidOperands),
+                }
+            };
+        }
+
+        let inst = match *self {
+            Inst::Adc(size, dst, src) => Inst::Adc(size, low!(size, dst), low!(size, src)),
+            Inst::Add(size, dst, src) => Inst::Add(size, low!(size, dst), low!(size, src)),
+            Inst::And(size, dst, src) => Inst::And(size, low!(size, dst), low!(size, src)),
+            Inst::Cmp(size, dst, src) => Inst::Cmp(size, low!(size, dst), low!(size, src)),
+            Inst::Mov(size, dst, src) => Inst::Mov(size, low!(size, dst), low!(size, src)),
+            Inst::Or(size, dst, src) => Inst::Or(size, low!(size, dst), low!(size, src)),
+            Inst::Sbb(size, dst, src) => Inst::Sbb(size, low!(size, dst), low!(size, src)),
+            Inst::Sub(size, dst, src) => Inst::Sub(size, low!(size, dst), low!(size, src)),
+            Inst::Test(size, dst, src) => Inst::Test(size, low!(size, dst), low!(size, src)),
+            Inst::Xchg(size, dst, src) => Inst::Xchg(size, low!(size, dst), low!(size, src)),
+            Inst::Xor(size, dst, src) => Inst::Xor(size, low!(size, dst), low!(size, src)),
+            Inst::Dec(size, arg) => Inst::Dec(size, low!(size, arg)),
+            Inst::Div(size, arg) => Inst::Div(size, low!(size, arg)),
+            Inst::Idiv(size, arg) => Inst::Idiv(size, low!(size, arg)),
+            Inst::Imul(size, arg) => Inst::Imul(size, low!(size, arg)),
+            Inst::Inc(size, arg) => Inst::Inc(size, low!(size, arg)),
+            Inst::Mul(size, arg) => Inst::Mul(size, low!(size, arg)),
+            Inst::Neg(size, arg) => Inst::Neg(size, low!(size, arg)),
+            Inst::Not(size, arg) => Inst::Not(size, low!(size, arg)),
+            Inst::Rcl(size, dst, count) => Inst::Rcl(size, low!(size, dst), count),
+            Inst::Rcr(size, dst, count) => Inst::Rcr(size, low!(size, dst), count),
+            Inst::Rol(size, dst, count) => Inst::Rol(size, low!(size, dst), count),
+            Inst::Ror(size, dst, count) => Inst::Ror(size, low!(size, dst), count),
+            Inst::Sar(size, dst, count) => Inst::Sar(size, low!(size, dst), count),
+            Inst::Shl(size, dst, count) => Inst::Shl(size, low!(size, dst), count),
+            Inst::Shr(size, dst, count) => Inst::Shr(size, low!(size, dst), count),
+            Inst::Setcc(cond, dst) => Inst::Setcc(cond, low!(Size::Byte, dst)),
+            Inst::Movsx(size, reg, src_size, src) => {
+                Inst::Movsx(size, reg, src_size, low!(src_size, src))
+            }
+            Inst::Movzx(size, reg, src_size, src) => {
+                Inst::Movzx(size, reg, src_size, low!(src_size, src))
+            }
+            Inst::Cmpxchg(size, dst, reg) => {
+                low!(size, Arg::Reg(reg));
+                Inst::Cmpxchg(size, low!(size, dst), reg)
+            }
+            Inst::Xadd(size, dst, reg) => {
+                low!(size, Arg::Reg(reg));
+                Inst::Xadd(size, low!(size, dst), reg)
+            }
+            _ => return Ok(None),
+        };
+
+        match (high, low) {
+            (false, _) => Ok(None),
+            (true, false) => Ok(Some(inst)),
+            (true, true) => Err(EncodeError::InvalidOperands),
+        }
+    }
+
+    /// Returns the ALU operation (`/digit`) of this instruction.
+    #[inline]
+    const fn alu_op(&self) -> Option<u8> {
+        let op = match self {
+            Inst::Add(..) => 0,
+            Inst::Or(..) => 1,
+            Inst::Adc(..) => 2,
+            Inst::Sbb(..) => 3,
+            Inst::And(..) => 4,
+            Inst::Sub(..) => 5,
+            Inst::Xor(..) => 6,
+            Inst::Cmp(..) => 7,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// Returns the shift operation (`/digit`) of this instruction.
+    #[inline]
+    const fn shift_op(&self) -> Option<u8> {
+        let op = match self {
+            Inst::Rol(..) => 0,
+            Inst::Ror(..) => 1,
+            Inst::Rcl(..) => 2,
+            Inst::Rcr(..) => 3,
+            Inst::Shl(..) => 4,
+            Inst::Shr(..) => 5,
+            Inst::Sar(..) => 7,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// Returns the bit test operation (`/digit` less 4) of this instruction.
+    #[inline]
+    const fn bit_op(&self) -> Option<u8> {
+        let op = match self {
+            Inst::Bt(..) => 0,
+            Inst::Bts(..) => 1,
+            Inst::Btr(..) => 2,
+            Inst::Btc(..) => 3,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// Returns the Intel mnemonic of this instruction.
+    ///
+    /// An undecoded byte or undefined x87 instruction is `(bad)`, and an instruction which is only
+    /// partially decoded ([`Inst::Evex`] and [`Inst::Other`]) is `(unknown)`.
+    #[inline]
+    pub const fn mnemonic(&self) -> &'static str {
+        match *self {
+            Inst::Adc(..) => "adc",
+            Inst::Add(..) => "add",
+            Inst::And(..) => "and",
+            Inst::Andn(..) => "andn",
+            Inst::Bextr(..) => "bextr",
+            Inst::Blsi(..) => "blsi",
+            Inst::Blsmsk(..) => "blsmsk",
+            Inst::Blsr(..) => "blsr",
+            Inst::Bsf(..) => "bsf",
+            Inst::Bsr(..) => "bsr",
+            Inst::Bt(..) => "bt",
+            Inst::Btc(..) => "btc",
+            Inst::Btr(..) => "btr",
+            Inst::Bts(..) => "bts",
+            Inst::Bzhi(..) => "bzhi",
+            Inst::Call(..) => "call",
+            Inst::Cbw(size) => sized(size, "cbw", "cwde", "cdqe"),
+            Inst::Clflush(..) => "clflush",
+            Inst::Clflushopt(..) => "clflushopt",
+            Inst::Clwb(..) => "clwb",
+            Inst::Cmovcc(cond, ..) => cond::CMOVCC[cond.bits() as usize],
+            Inst::Cli => "cli",
+            Inst::Cmp(..) => "cmp",
+            Inst::Cmpxchg(..) => "cmpxchg",
+            Inst::Cpuid => "cpuid",
+            Inst::Cwd(size) => sized(size, "cwd", "cdq", "cqo"),
+            Inst::Clrssbsy(..) => "clrssbsy",
+            Inst::Dec(..) => "dec",
+            Inst::Div(..) => "div",
+            Inst::Endbr32 => "endbr32",
+            Inst::Endbr64 => "endbr64",
+            Inst::Fxrstor(size, _) => sized(size, "fxrstor", "fxrstor", "fxrstor64"),
+            Inst::Fxsave(size, _) => sized(size, "fxsave", "fxsave", "fxsave64"),
+            Inst::Hlt => "hlt",
+            Inst::Evex(..) => "(unknown)",
+            Inst::Idiv(..) => "idiv",
+            Inst::In(..) => "in",
+            Inst::Imul(..) | Inst::Imul2(..) | Inst::Imul3(..) => "imul",
+            Inst::Inc(..) => "inc",
+            Inst::Incssp(size, _) => sized(size, "incsspd", "incsspd", "incsspq"),
+            Inst::Int(..) => "int",
+            Inst::Int3 => "int3",
+            Inst::Invlpg(..) => "invlpg",
+            Inst::Iret(size) => sized(size, "iret", "iretd", "iretq"),
+            Inst::Jcc(cond, _) | Inst::JccShort(cond, _) => cond::JCC[cond.bits() as usize],
+            Inst::Ldmxcsr(..) => "ldmxcsr",
+            Inst::Lea(..) => "lea",
+            Inst::Leave => "leave",
+            Inst::Lfence => "lfence",
+            Inst::Lzcnt(..) => "lzcnt",
+            Inst::Mfence => "mfence",
+            Inst::Mov(..) => "mov",
+            Inst::Movbe(..) => "movbe",
+            Inst::Movsx(_, _, Size::Dword, _) => "movsxd",
+            Inst::Movsx(..) => "movsx",
+            Inst::Movzx(..) => "movzx",
+            Inst::Mul(..) => "mul",
+            Inst::Mulx(..) => "mulx",
+            Inst::Neg(..) => "neg",
+            Inst::Nop(..) => "nop",
+            Inst::Not(..) => "not",
+            Inst::Or(..) => "or",
+            Inst::Other { .. } => "(unknown)",
+            Inst::Out(..) => "out",
+            Inst::Pause => "pause",
+            Inst::Pdep(..) => "pdep",
+            Inst::Pext(..) => "pext",
+            Inst::Pop(..) => "pop",
+            Inst::Prefetchnta(..) => "prefetchnta",
+            Inst::Prefetcht0(..) => "prefetcht0",
+            Inst::Prefetcht1(..) => "prefetcht1",
+            Inst::Prefetcht2(..) => "prefetcht2",
+            Inst::Prefetchw(..) => "prefetchw",
+            Inst::Popf(size) => sized(size, "popf", "popfd", "popfq"),
+            Inst::Popcnt(..) => "popcnt",
+            Inst::Push(..) => "push",
+            Inst::Pushf(size) => sized(size, "pushf", "pushfd", "pushfq"),
+            Inst::Rcl(..) => "rcl",
+            Inst::Rcr(..) => "rcr",
+            Inst::Rdmsr => "rdmsr",
+            Inst::Rdrand(..) => "rdrand",
+            Inst::Rdseed(..) => "rdseed",
+            Inst::Rdssp(size, _) => sized(size, "rdsspd", "rdsspd", "rdsspq"),
+            Inst::Rdtsc => "rdtsc",
+            Inst::Rdtscp => "rdtscp",
+            Inst::Jmp(..) | Inst::JmpShort(..) => "jmp",
+            Inst::Ret => "ret",
+            Inst::Rstorssp(..) => "rstorssp",
+            Inst::Saveprevssp => "saveprevssp",
+            Inst::Rol(..) => "rol",
+            Inst::Ror(..) => "ror",
+            Inst::Rorx(..) => "rorx",
+            Inst::Sar(..) => "sar",
+            Inst::Sarx(..) => "sarx",
+            Inst::Sbb(..) => "sbb",
+            Inst::Setcc(cond, _) => cond::SETCC[cond.bits() as usize],
+            Inst::Sfence => "sfence",
+            Inst::Setssbsy => "setssbsy",
+            Inst::Sti => "sti",
+            Inst::Stmxcsr(..) => "stmxcsr",
+            Inst::Shl(..) => "shl",
+            Inst::Shlx(..) => "shlx",
+            Inst::Shr(..) => "shr",
+            Inst::Shrx(..) => "shrx",
+            Inst::SseAdd(fp, ..) => fp_form(fp, ["addps", "addpd", "addss", "addsd"]),
+            Inst::SseAnd(fp, ..) => fp_form(fp, ["andps", "andpd", "(bad)", "(bad)"]),
+            Inst::SseAndn(fp, ..) => fp_form(fp, ["andnps", "andnpd", "(bad)", "(bad)"]),
+            Inst::SseDiv(fp, ..) => fp_form(fp, ["divps", "divpd", "divss", "divsd"]),
+            Inst::SseMax(fp, ..) => fp_form(fp, ["maxps", "maxpd", "maxss", "maxsd"]),
+            Inst::SseMin(fp, ..) => fp_form(fp, ["minps", "minpd", "minss", "minsd"]),
+            Inst::SseMova(fp, ..) => fp_form(fp, ["movaps", "movapd", "(bad)", "(bad)"]),
+            Inst::SseMovu(fp, ..) => fp_form(fp, ["movups", "movupd", "movss", "movsd"]),
+            Inst::SseMul(fp, ..) => fp_form(fp, ["mulps", "mulpd", "mulss", "mulsd"]),
+            Inst::SseOr(fp, ..) => fp_form(fp, ["orps", "orpd", "(bad)", "(bad)"]),
+            Inst::SseSqrt(fp, ..) => fp_form(fp, ["sqrtps", "sqrtpd", "sqrtss", "sqrtsd"]),
+            Inst::SseSub(fp, ..) => fp_form(fp, ["subps", "subpd", "subss", "subsd"]),
+            Inst::SseXor(fp, ..) => fp_form(fp, ["xorps", "xorpd", "(bad)", "(bad)"]),
+            Inst::Sub(..) => "sub",
+            Inst::Swapgs => "swapgs",
+            Inst::Syscall => "syscall",
+            Inst::Sysret(..) => "sysret",
+            Inst::Test(..) => "test",
+            Inst::Tzcnt(..) => "tzcnt",
+            Inst::Ud2 => "ud2",
+            Inst::Unknown(..) => "(bad)",
+            Inst::Wait => "wait",
+            Inst::Wrmsr => "wrmsr",
+            Inst::Wrss(size, ..) => sized(size, "wrssd", "wrssd", "wrssq"),
+            Inst::Wruss(size, ..) => sized(size, "wrussd", "wrussd", "wrussq"),
+            Inst::X87(x87) => match x87.mnemonic() {
+                Some(mnemonic) => mnemonic,
+                None => "(bad)",
+            },
+            Inst::Xadd(..) => "xadd",
+            Inst::Xchg(..) => "xchg",
+            Inst::Xor(..) => "xor",
+            Inst::Xrstor(size, _) => sized(size, "xrstor", "xrstor", "xrstor64"),
+            Inst::Xrstors(size, _) => sized(size, "xrstors", "xrstors", "xrstors64"),
+            Inst::Xsave(size, _) => sized(size, "xsave", "xsave", "xsave64"),
+            Inst::Xsavec(size, _) => sized(size, "xsavec", "xsavec", "xsavec64"),
+            Inst::Xsaveopt(size, _) => sized(size, "xsaveopt", "xsaveopt", "xsaveopt64"),
+            Inst::Xsaves(size, _) => sized(size, "xsaves", "xsaves", "xsaves64"),
+        }
+    }
+
+    /// Returns the control flow of this instruction.
+    #[inline]
+    pub const fn kind(&self) -> Kind {
+        match self {
+            Inst::Call(_) => Kind::Call,
+            Inst::Ret | Inst::Iret(_) | Inst::Sysret(_) => Kind::Ret,
+            Inst::Jmp(_) | Inst::JmpShort(_) => Kind::Jmp,
+            Inst::Jcc(..) | Inst::JccShort(..) => Kind::Jcc,
+            Inst::Int3 | Inst::Ud2 | Inst::Hlt => Kind::Trap,
+            _ => Kind::Other,
+        }
+    }
+
+    /// Returns `true` if this is a `call`.
+    #[inline]
+    pub const fn is_call(&self) -> bool {
+        matches!(self.kind(), Kind::Call)
+    }
+
+    /// Returns `true` if this is a return, `ret`, `iret` or `sysret`.
+    #[inline]
+    pub const fn is_ret(&self) -> bool {
+        matches!(self.kind(), Kind::Ret)
+    }
+
+    /// Returns `true` if this is a `call`, `jmp` or `jcc`.
+    #[inline]
+    pub const fn is_branch(&self) -> bool {
+        matches!(self.kind(), Kind::Call | Kind::Jmp | Kind::Jcc)
+    }
+
+    /// Returns `true` if this is a conditional branch.
+    #[inline]
+    pub const fn is_conditional(&self) -> bool {
+        matches!(self.kind(), Kind::Jcc)
+    }
+
+    /// Returns `true` if this instruction ends a basic block.
+    #[inline]
+    pub const fn is_terminator(&self) -> bool {
+        matches!(self.kind(), Kind::Ret | Kind::Jmp | Kind::Jcc | Kind::Trap)
+    }
+
+    /// Returns the access to the explicit memory operand of this instruction, if present.
+    ///
+    /// Implicit accesses to the stack, as by `push` and `call`, are not included.
+    #[inline]
+    pub const fn mem_access(&self) -> Option<MemAccess> {
+        match *self {
+            Inst::Adc(size, dst, src)
+            | Inst::Add(size, dst, src)
+            | Inst::And(size, dst, src)
+            | Inst::Btc(size, dst, src)
+            | Inst::Btr(size, dst, src)
+            | Inst::Bts(size, dst, src)
+            | Inst::Or(size, dst, src)
+            | Inst::Rcl(size, dst, src)
+            | Inst::Rcr(size, dst, src)
+            | Inst::Rol(size, dst, src)
+            | Inst::Ror(size, dst, src)
+            | Inst::Sar(size, dst, src)
+            | Inst::Sbb(size, dst, src)
+            | Inst::Shl(size, dst, src)
+            | Inst::Shr(size, dst, src)
+            | Inst::Sub(size, dst, src)
+            | Inst::Xor(size, dst, src) => operand_access(dst, src, true, size.bytes()),
+            Inst::Bt(size, dst, src) | Inst::Cmp(size, dst, src) | Inst::Test(size, dst, src) => {
+                match (dst, src) {
+                    (Arg::Mem(mem), _) | (_, Arg::Mem(mem)) => {
+                        Some(MemAccess::read(mem, size.bytes()))
+                    }
+                    _ => None,
+                }
+            }
+            Inst::Mov(size, dst, src) | Inst::Movbe(size, dst, src) => {
+                operand_access(dst, src, false, size.bytes())
+            }
+            Inst::Xchg(size, dst, src) => match (dst, src) {
+                (Arg::Mem(mem), _) | (_, Arg::Mem(mem)) => {
+                    Some(MemAccess::read_write(mem, size.bytes()))
+                }
+                _ => None,
+            },
+            Inst::Xadd(size, dst, _)
+            | Inst::Cmpxchg(size, dst, _)
+            | Inst::Dec(size, dst)
+            | Inst::Inc(size, dst)
+            | Inst::Neg(size, dst)
+            | Inst::Not(size, dst) => access(dst, true, true, Some(size.bytes())),
+            Inst::Movsx(_, _, size, src) | Inst::Movzx(_, _, size, src) => {
+                access(src, true, false, Some(size.bytes()))
+            }
+            Inst::Andn(size, _, _, src)
+            | Inst::Bextr(size, _, src, _)
+            | Inst::Blsi(size, _, src)
+            | Inst::Blsmsk(size, _, src)
+            | Inst::Blsr(size, _, src)
+            | Inst::Bsf(size, _, src)
+            | Inst::Bsr(size, _, src)
+            | Inst::Bzhi(size, _, src, _)
+            | Inst::Cmovcc(_, size, _, src)
+            | Inst::Div(size, src)
+            | Inst::Idiv(size, src)
+            | Inst::Imul(size, src)
+            | Inst::Imul2(size, _, src)
+            | Inst::Imul3(size, _, src, _)
+            | Inst::Lzcnt(size, _, src)
+            | Inst::Mul(size, src)
+            | Inst::Mulx(size, _, _, src)
+            | Inst::Pdep(size, _, _, src)
+            | Inst::Pext(size, _, _, src)
+            | Inst::Popcnt(size, _, src)
+            | Inst::Rorx(size, _, src, _)
+            | Inst::Sarx(size, _, src, _)
+            | Inst::Shlx(size, _, src, _)
+            | Inst::Shrx(size, _, src, _)
+            | Inst::Tzcnt(size, _, src) => access(src, true, false, Some(size.bytes())),
+            // the operand size of an indirect branch or push is that of the mode, as is the
+            // address size without a 67 prefix
+            Inst::Call(Arg::Mem(mem)) | Inst::Jmp(Arg::Mem(mem)) | Inst::Push(Arg::Mem(mem)) => {
+                Some(MemAccess::read(mem, mem.addr_size.bytes()))
+            }
+            Inst::Setcc(_, dst) => access(dst, false, true, Some(1)),
+            Inst::Wrss(size, dst, _) | Inst::Wruss(size, dst, _) => {
+                access(dst, false, true, Some(size.bytes()))
+            }
+            Inst::Clrssbsy(mem) => access(mem, true, true, Some(8)),
+            Inst::Rstorssp(mem) => access(mem, true, false, Some(8)),
+            Inst::Ldmxcsr(mem) => access(mem, true, false, Some(4)),
+            Inst::Stmxcsr(mem) => access(mem, false, true, Some(4)),
+            Inst::Fxrstor(_, mem) => access(mem, true, false, Some(512)),
+            Inst::Fxsave(_, mem) => access(mem, false, true, Some(512)),
+            // the size depends on the state components
+            Inst::Xrstor(_, mem) | Inst::Xrstors(_, mem) => access(mem, true, false, None),
+            Inst::Xsave(_, mem) | Inst::Xsavec(_, mem) | Inst::Xsaves(_, mem) => {
+                access(mem, false, true, None)
+            }
+            // only modified state components are written, which are read to determine that
+            Inst::Xsaveopt(_, mem) => access(mem, true, true, None),
+            Inst::Clflush(mem)
+            | Inst::Clflushopt(mem)
+            | Inst::Clwb(mem)
+            | Inst::Invlpg(mem)
+            | Inst::Prefetchnta(mem)
+            | Inst::Prefetcht0(mem)
+            | Inst::Prefetcht1(mem)
+            | Inst::Prefetcht2(mem)
+            | Inst::Prefetchw(mem) => access(mem, false, false, None),
+            Inst::SseMova(fp, dst, src) | Inst::SseMovu(fp, dst, src) => {
+                operand_access(dst, src, false, fp.bytes())
+            }
+            Inst::SseAdd(fp, _, src)
+            | Inst::SseAnd(fp, _, src)
+            | Inst::SseAndn(fp, _, src)
+            | Inst::SseDiv(fp, _, src)
+            | Inst::SseMax(fp, _, src)
+            | Inst::SseMin(fp, _, src)
+            | Inst::SseMul(fp, _, src)
+            | Inst::SseOr(fp, _, src)
+            | Inst::SseSqrt(fp, _, src)
+            | Inst::SseSub(fp, _, src)
+            | Inst::SseXor(fp, _, src) => access(src, true, false, Some(fp.bytes())),
+            Inst::X87(x87) => x87.mem_access(),
+            // not decoded far enough to know, assume both
+            Inst::Evex(Evex {
+                rm: EvexRm::Mem(mem),
+                ..
+            }) => Some(MemAccess::new(mem, true, true, None)),
+            _ => None,
+        }
+    }
+
+    /// Returns the flags read by this instruction.
+    #[inline]
+    pub const fn flags_read(&self) -> Flags {
+        self.flags().0
+    }
+
+    /// Returns the flags written by this instruction, including those left undefined.
+    ///
+    /// A shift or rotate by a count of zero leaves the flags unchanged, it is considered to write
+    /// them regardless.
+    #[inline]
+    pub const fn flags_written(&self) -> Flags {
+        self.flags().1
+    }
+
+    /// Returns the flags read and written by this instruction.
+    #[inline]
+    const fn flags(&self) -> (Flags, Flags) {
+        const NONE: Flags = Flags::NONE;
+        const STATUS: Flags = Flags::STATUS;
+
+        match *self {
+            Inst::Add(..)
+            | Inst::And(..)
+            | Inst::Andn(..)
+            | Inst::Bextr(..)
+            | Inst::Blsi(..)
+            | Inst::Blsmsk(..)
+            | Inst::Blsr(..)
+            | Inst::Bsf(..)
+            | Inst::Bsr(..)
+            | Inst::Bt(..)
+            | Inst::Btc(..)
+            | Inst::Btr(..)
+            | Inst::Bts(..)
+            | Inst::Bzhi(..)
+            | Inst::Clrssbsy(..)
+            | Inst::Cmp(..)
+            | Inst::Cmpxchg(..)
+            | Inst::Div(..)
+            | Inst::Idiv(..)
+            | Inst::Imul(..)
+            | Inst::Imul2(..)
+            | Inst::Imul3(..)
+            | Inst::Lzcnt(..)
+            | Inst::Mul(..)
+            | Inst::Neg(..)
+            | Inst::Or(..)
+            | Inst::Popcnt(..)
+            | Inst::Rdrand(..)
+            | Inst::Rdseed(..)
+            | Inst::Rstorssp(..)
+            | Inst::Sar(..)
+            | Inst::Shl(..)
+            | Inst::Shr(..)
+            | Inst::Sub(..)
+            | Inst::Test(..)
+            | Inst::Tzcnt(..)
+            | Inst::Xadd(..)
+            | Inst::Xor(..) => (NONE, STATUS),
+            Inst::Adc(..) | Inst::Sbb(..) => (Flags::CF, STATUS),
+            // the carry flag is unaffected
+            Inst::Inc(..) | Inst::Dec(..) => (NONE, STATUS.difference(Flags::CF)),
+            Inst::Rol(..) | Inst::Ror(..) => (NONE, Flags::CF.union(Flags::OF)),
+            Inst::Rcl(..) | Inst::Rcr(..) => (Flags::CF, Flags::CF.union(Flags::OF)),
+            Inst::Cmovcc(cond, ..)
+            | Inst::Jcc(cond, _)
+            | Inst::JccShort(cond, _)
+            | Inst::Setcc(cond, _) => (cond.flags(), NONE),
+            Inst::Pushf(_) => (Flags::ALL, NONE),
+            Inst::Popf(_) | Inst::Iret(_) | Inst::Sysret(_) => (NONE, Flags::ALL),
+            // the flags are saved in r11, then masked
+            Inst::Syscall => (Flags::ALL, Flags::ALL),
+            // the flags are pushed, then traps and interrupts are disabled
+            Inst::Int(_) | Inst::Int3 => (Flags::ALL, Flags::TF.union(Flags::IF)),
+            Inst::Cli | Inst::Sti => (NONE, Flags::IF),
+            Inst::X87(x87) => x87.flags(),
+            _ => (NONE, NONE),
+        }
+    }
+
+    /// Returns the general purpose registers read by this instruction, including implicit operands
+    /// and those used to compute a memory address.
+    #[inline]
+    pub const fn regs_read(&self) -> RegSet {
+        self.regs().0
+    }
+
+    /// Returns the general purpose registers written by this instruction, including implicit
+    /// operands.
+    #[inline]
+    pub const fn regs_written(&self) -> RegSet {
+        self.regs().1
+    }
+
+    /// Returns the general purpose registers read and written by this instruction.
+    #[inline]
+    const fn regs(&self) -> (RegSet, RegSet) {
+        const EMPTY: RegSet = RegSet::EMPTY;
+
+        let rax = RegSet::of(Reg::Rax);
+        let rdx = RegSet::of(Reg::Rdx);
+        let rsp = RegSet::of(Reg::Rsp);
+        let rax_rdx = rax.with(Reg::Rdx);
+
+        match *self {
+            Inst::Adc(_, dst, src)
+            | Inst::Add(_, dst, src)
+            | Inst::And(_, dst, src)
+            | Inst::Btc(_, dst, src)
+            | Inst::Btr(_, dst, src)
+            | Inst::Bts(_, dst, src)
+            | Inst::Or(_, dst, src)
+            | Inst::Rcl(_, dst, src)
+            | Inst::Rcr(_, dst, src)
+            | Inst::Rol(_, dst, src)
+            | Inst::Ror(_, dst, src)
+            | Inst::Sar(_, dst, src)
+            | Inst::Sbb(_, dst, src)
+            | Inst::Shl(_, dst, src)
+            | Inst::Shr(_, dst, src)
+            | Inst::Sub(_, dst, src)
+            | Inst::Xor(_, dst, src) => {
+                (dst.regs_read().union(src.regs_read()), dst.regs_written())
+            }
+            Inst::Bt(_, dst, src) | Inst::Cmp(_, dst, src) | Inst::Test(_, dst, src) => {
+                (dst.regs_read().union(src.regs_read()), EMPTY)
+            }
+            Inst::Mov(_, dst, src) | Inst::Movbe(_, dst, src) => {
+                (dst.addr_regs().union(src.regs_read()), dst.regs_written())
+            }
+            Inst::Movsx(_, reg, _, src)
+            | Inst::Movzx(_, reg, _, src)
+            | Inst::Bsf(_, reg, src)
+            | Inst::Bsr(_, reg, src)
+            | Inst::Blsi(_, reg, src)
+            | Inst::Blsmsk(_, reg, src)
+            | Inst::Blsr(_, reg, src)
+            | Inst::Lzcnt(_, reg, src)
+            | Inst::Popcnt(_, reg, src)
+            | Inst::Tzcnt(_, reg, src)
+            | Inst::Imul3(_, reg, src, _)
+            | Inst::Rorx(_, reg, src, _) => (src.regs_read(), RegSet::of(reg)),
+            Inst::Lea(_, reg, src) => (src.addr_regs(), RegSet::of(reg)),
+            // the destination is kept if the condition is false
+            Inst::Cmovcc(_, _, reg, src) | Inst::Imul2(_, reg, src) => {
+                (src.regs_read().with(reg), RegSet::of(reg))
+            }
+            Inst::Andn(_, reg, other, src)
+            | Inst::Pdep(_, reg, other, src)
+            | Inst::Pext(_, reg, other, src)
+            | Inst::Bextr(_, reg, src, other)
+            | Inst::Bzhi(_, reg, src, other)
+            | Inst::Sarx(_, reg, src, other)
+            | Inst::Shlx(_, reg, src, other)
+            | Inst::Shrx(_, reg, src, other) => (src.regs_read().with(other), RegSet::of(reg)),
+            Inst::Mulx(_, hi, lo, src) => (src.regs_read().with(Reg::Rdx), RegSet::of(hi).with(lo)),
+            Inst::Xchg(_, dst, src) => (
+                dst.regs_read().union(src.regs_read()),
+                dst.regs_written().union(src.regs_written()),
+            ),
+            Inst::Xadd(_, dst, reg) => (dst.regs_read().with(reg), dst.regs_written().with(reg)),
+            Inst::Cmpxchg(_, dst, reg) => (
+                dst.regs_read().with(reg).with(Reg::Rax),
+                dst.regs_written().with(Reg::Rax),
+            ),
+            Inst::Dec(_, dst) | Inst::Inc(_, dst) | Inst::Neg(_, dst) | Inst::Not(_, dst) => {
+                (dst.regs_read(), dst.regs_written())
+            }
+            // ax is the product, dividend, quotient and remainder of 8-bit operations
+            Inst::Mul(Size::Byte, src)
+            | Inst::Imul(Size::Byte, src)
+            | Inst::Div(Size::Byte, src)
+            | Inst::Idiv(Size::Byte, src) => (src.regs_read().with(Reg::Rax), rax),
+            Inst::Mul(_, src) | Inst::Imul(_, src) => (src.regs_read().with(Reg::Rax), rax_rdx),
+            Inst::Div(_, src) | Inst::Idiv(_, src) => (src.regs_read().union(rax_rdx), rax_rdx),
+            Inst::Cbw(_) => (rax, rax),
+            Inst::Cwd(_) => (rax, rdx),
+            Inst::Call(target) => (target.regs_read().with(Reg::Rsp), rsp),
+            Inst::Jmp(target) => (target.regs_read(), EMPTY),
+            Inst::Ret | Inst::Iret(_) | Inst::Pushf(_) | Inst::Popf(_) => (rsp, rsp),
+            Inst::Push(src) => (src.regs_read().with(Reg::Rsp), rsp),
+            Inst::Pop(reg) => (rsp, rsp.with(reg)),
+            Inst::Leave => (RegSet::of(Reg::Rbp), rsp.with(Reg::Rbp)),
+            Inst::Cpuid => (rax.with(Reg::Rcx), rax_rdx.with(Reg::Rbx).with(Reg::Rcx)),
+            Inst::Rdtsc => (EMPTY, rax_rdx),
+            Inst::Rdtscp => (EMPTY, rax_rdx.with(Reg::Rcx)),
+            Inst::Rdmsr => (RegSet::of(Reg::Rcx), rax_rdx),
+            Inst::Wrmsr => (rax_rdx.with(Reg::Rcx), EMPTY),
+            // the return address and flags are saved in rcx and r11
+            Inst::Syscall => (EMPTY, RegSet::of(Reg::Rcx).with(Reg::R11)),
+            Inst::Sysret(_) => (RegSet::of(Reg::Rcx).with(Reg::R11), EMPTY),
+            Inst::In(_, port) => (port.regs_read(), rax),
+            Inst::Out(_, port) => (port.regs_read().with(Reg::Rax), EMPTY),
+            Inst::Rdrand(_, reg) | Inst::Rdseed(_, reg) => (EMPTY, RegSet::of(reg)),
+            // the destination is kept if shadow stacks are disabled
+            Inst::Rdssp(_, reg) => (RegSet::of(reg), RegSet::of(reg)),
+            Inst::Incssp(_, reg) => (RegSet::of(reg), EMPTY),
+            Inst::Setcc(_, dst) => (dst.addr_regs(), dst.regs_written()),
+            Inst::Wrss(_, dst, reg) | Inst::Wruss(_, dst, reg) => {
+                (dst.addr_regs().with(reg), EMPTY)
+            }
+            // edx:eax is the mask of state components
+            Inst::Xrstor(_, mem)
+            | Inst::Xrstors(_, mem)
+            | Inst::Xsave(_, mem)
+            | Inst::Xsavec(_, mem)
+            | Inst::Xsaveopt(_, mem)
+            | Inst::Xsaves(_, mem) => (mem.addr_regs().union(rax_rdx), EMPTY),
+            Inst::Clflush(mem)
+            | Inst::Clflushopt(mem)
+            | Inst::Clwb(mem)
+            | Inst::Clrssbsy(mem)
+            | Inst::Fxrstor(_, mem)
+            | Inst::Fxsave(_, mem)
+            | Inst::Invlpg(mem)
+            | Inst::Ldmxcsr(mem)
+            | Inst::Prefetchnta(mem)
+            | Inst::Prefetcht0(mem)
+            | Inst::Prefetcht1(mem)
+            | Inst::Prefetcht2(mem)
+            | Inst::Prefetchw(mem)
+            | Inst::Rstorssp(mem)
+            | Inst::Stmxcsr(mem) => (mem.addr_regs(), EMPTY),
+            Inst::SseMova(_, dst, src) | Inst::SseMovu(_, dst, src) => {
+                (dst.addr_regs().union(src.regs_read()), EMPTY)
+            }
+            Inst::SseAdd(_, _, src)
+            | Inst::SseAnd(_, _, src)
+            | Inst::SseAndn(_, _, src)
+            | Inst::SseDiv(_, _, src)
+            | Inst::SseMax(_, _, src)
+            | Inst::SseMin(_, _, src)
+            | Inst::SseMul(_, _, src)
+            | Inst::SseOr(_, _, src)
+            | Inst::SseSqrt(_, _, src)
+            | Inst::SseSub(_, _, src)
+            | Inst::SseXor(_, _, src) => (src.regs_read(), EMPTY),
+            Inst::Evex(Evex {
+                rm: EvexRm::Mem(mem),
+                ..
+            })
+            | Inst::X87(X87 {
+                rm: X87Rm::Mem(mem),
+                ..
+            }) => (mem.regs(), EMPTY),
+            _ => (EMPTY, EMPTY),
+        }
+    }
+
+    /// Returns `true` if this is `endbr64` or `endbr32`, which begin most functions in CET enabled
+    /// binaries.
+    #[inline]
+    pub const fn is_endbr(&self) -> bool {
+        matches!(self, Inst::Endbr64 | Inst::Endbr32)
+    }
+
+    /// Returns `true` if this is `rdrand` or `rdseed`, which produce randomness from the hardware
+    /// generator.
+    #[inline]
+    pub const fn is_random(&self) -> bool {
+        matches!(self, Inst::Rdrand(..) | Inst::Rdseed(..))
+    }
+
+    /// Returns `true` if this instruction is atomic, either through a `lock` prefix, or an
+    /// implicitly locked `xchg` with memory.
+    #[inline]
+    pub const fn is_locked(&self) -> bool {
+        match (self, self.mem()) {
+            (Inst::Xchg(..), Some(_)) => true,
+            (_, Some(mem)) => mem.lock,
+            _ => false,
+        }
+    }
+
+    /// Returns the relative address if present in this instruction.
+    #[inline]
+    pub const fn rel_addr(&self) -> Option<isize> {
+        let rel = match self {
+            Inst::Call(rel @ (Arg::Imm16(_) | Arg::Imm32(_)))
+            | Inst::Jmp(rel @ (Arg::Imm16(_) | Arg::Imm32(_))) => imm_z(*rel) as isize,
+            Inst::Jcc(_, rel) => *rel as isize,
+            Inst::JccShort(_, rel) | Inst::JmpShort(rel) => *rel as isize,
+            _ => match self.mem() {
+                Some(mem) if mem.is_rip() => mem.disp as isize,
+                _ => return None,
+            },
+        };
+
+        Some(rel)
+    }
+
+    /// Returns `true` if this instruction has a `rip` relative memory operand.
+    #[inline]
+    pub const fn is_rip_relative(&self) -> bool {
+        matches!(self.mem(), Some(mem) if mem.is_rip())
+    }
+
+    /// Returns the address referenced by the `rip` relative memory operand of this instruction at
+    /// `ip`, whether it is data or a branch target loaded from memory.
+    ///
+    /// The length is that of the canonical encoding, see [`WithIp::target`] for that of the bytes
+    /// decoded.
+    #[inline]
+    pub const fn rip_target(&self, ip: usize) -> Option<usize> {
+        match self.mem() {
+            // rip relative addressing only exists in long mode
+            Some(mem) if mem.is_rip() => {
+                let next_ip = match self.len(Mode::Long64) {
+                    Ok(len) => ip.wrapping_add(len),
+                    Err(_) => return None,
+                };
+
+                Some((next_ip as isize).wrapping_add(mem.disp as isize) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a copy of this instruction with the relative displacement `rel`.
+    ///
+    /// Applies to relative branches, and `call`, `jmp`, `push`, `lea` and `mov` with a `rip`
+    /// relative memory operand. A short branch becomes a near branch if `rel` does not fit.
+    #[inline]
+    pub const fn with_rel(&self, rel: i32) -> Result<Inst, TargetError> {
+        let short = rel as i8 as i32 == rel;
+        let word = rel as i16 as i32 == rel;
+
+        let inst = match *self {
+            // a 16-bit displacement keeps its width
+            Inst::Call(Arg::Imm16(_)) if word => Inst::Call(Arg::Imm16(rel as i16)),
+            Inst::Jmp(Arg::Imm16(_)) if word => Inst::Jmp(Arg::Imm16(rel as i16)),
+            Inst::Call(Arg::Imm16(_)) | Inst::Jmp(Arg::Imm16(_)) => {
+                return Err(TargetError::OutOfRange)
+            }
+            Inst::Call(Arg::Imm32(_)) => Inst::Call(Arg::Imm32(rel)),
+            Inst::Jmp(Arg::Imm32(_)) => Inst::Jmp(Arg::Imm32(rel)),
+            Inst::JmpShort(_) if short => Inst::JmpShort(rel as i8),
+            Inst::JmpShort(_) => Inst::Jmp(Arg::Imm32(rel)),
+            Inst::Jcc(cond, _) => Inst::Jcc(cond, rel),
+            Inst::JccShort(cond, _) if short => Inst::JccShort(cond, rel as i8),
+            Inst::JccShort(cond, _) => Inst::Jcc(cond, rel),
+            Inst::Call(Arg::Mem(mem)) if mem.is_rip() => Inst::Call(Arg::Mem(with_disp(mem, rel))),
+            Inst::Jmp(Arg::Mem(mem)) if mem.is_rip() => Inst::Jmp(Arg::Mem(with_disp(mem, rel))),
+            Inst::Push(Arg::Mem(mem)) if mem.is_rip() => Inst::Push(Arg::Mem(with_disp(mem, rel))),
+            Inst::Lea(size, reg, Arg::Mem(mem)) if mem.is_rip() => {
+                Inst::Lea(size, reg, Arg::Mem(with_disp(mem, rel)))
+            }
+            Inst::Mov(size, Arg::Mem(mem), src) if mem.is_rip() => {
+                Inst::Mov(size, Arg::Mem(with_disp(mem, rel)), src)
+            }
+            Inst::Mov(size, dst, Arg::Mem(mem)) if mem.is_rip() => {
+                Inst::Mov(size, dst, Arg::Mem(with_disp(mem, rel)))
+            }
+            _ => return Err(TargetError::NotRelative),
+        };
+
+        Ok(inst)
+    }
+
+    /// The recommended multi-byte NOPs filling `len` bytes when encoded for `mode`.
+    #[inline]
+    pub const fn nop_pad(len: usize, mode: Mode) -> NopPad {
+        NopPad::new(len, mode)
+    }
+
+    /// A near `call` at `from_ip` to the absolute address `to`, when encoded for `mode`.
+    #[inline]
+    pub const fn call_to(from_ip: usize, to: usize, mode: Mode) -> Result<Inst, TargetError> {
+        Inst::Call(rel_arg(mode)).with_target(to, from_ip, mode)
+    }
+
+    /// A `jmp` at `from_ip` to the absolute address `to`, when encoded for `mode`, which is short
+    /// if `to` is in range.
+    #[inline]
+    pub const fn jmp_to(from_ip: usize, to: usize, mode: Mode) -> Result<Inst, TargetError> {
+        Inst::JmpShort(0).with_target(to, from_ip, mode)
+    }
+
+    /// A near `jmp` at `from_ip` to the absolute address `to`, when encoded for `mode`, which may
+    /// later be retargeted in place.
+    #[inline]
+    pub const fn jmp_near_to(from_ip: usize, to: usize, mode: Mode) -> Result<Inst, TargetError> {
+        Inst::Jmp(rel_arg(mode)).with_target(to, from_ip, mode)
+    }
+
+    /// A `jcc` at `from_ip` to the absolute address `to`, when encoded for `mode`, which is short
+    /// if `to` is in range.
+    #[inline]
+    pub const fn jcc_to(
+        cond: Cond,
+        from_ip: usize,
+        to: usize,
+        mode: Mode,
+    ) -> Result<Inst, TargetError> {
+        Inst::JccShort(cond, 0).with_target(to, from_ip, mode)
+    }
+
+    /// A near `jcc` at `from_ip` to the absolute address `to`, when encoded for `mode`, which may
+    /// later be retargeted in place.
+    #[inline]
+    pub const fn jcc_near_to(
+        cond: Cond,
+        from_ip: usize,
+        to: usize,
+        mode: Mode,
+    ) -> Result<Inst, TargetError> {
+        Inst::Jcc(cond, 0).with_target(to, from_ip, mode)
+    }
+
+    /// Returns a copy of this instruction at `ip` retargeted to the absolute address `target`, when
+    /// encoded for `mode`.
+    ///
+    /// See [`Inst::with_rel`] for the instructions which can be retargeted.
+    #[inline]
+    pub const fn with_target(
+        &self,
+        target: usize,
+        ip: usize,
+        mode: Mode,
+    ) -> Result<Inst, TargetError> {
+        // keep a short branch if the target is in range
+        if let Inst::JmpShort(_) | Inst::JccShort(..) = self {
+            let len = match self.len(mode) {
+                Ok(len) => len,
+                Err(error) => return Err(TargetError::Encode(error)),
+            };
+
+            let rel = rel_to(target, ip.wrapping_add(len));
+
+            if rel as i8 as isize == rel {
+                return self.with_rel(rel as i32);
+            }
+        }
+
+        // the length is independent of the displacement, other than that of a short branch, which
+        // becomes a near branch as it is out of range of an i8
+        let inst = match self.with_rel(i8::MAX as i32 + 1) {
+            Ok(inst) => inst,
+            Err(error) => return Err(error),
+        };
+
+        let len = match inst.len(mode) {
+            Ok(len) => len,
+            Err(error) => return Err(TargetError::Encode(error)),
+        };
+
+        let rel = rel_to(target, ip.wrapping_add(len));
+
+        // addresses wrap outside of long mode
+        let rel = match mode {
+            Mode::Long64 if rel as i32 as isize == rel => rel as i32,
+            Mode::Long64 => return Err(TargetError::OutOfRange),
+            Mode::Compat32 => rel as i32,
+            Mode::Real16 => rel as i16 as i32,
+        };
+
+        inst.with_rel(rel)
+    }
+
+    /// Returns the memory operand of this instruction, if present.
+    #[inline]
+    const fn mem(&self) -> Option<Mem> {
+        let (dst, src) = match *self {
+            Inst::Evex(Evex {
+                rm: EvexRm::Mem(mem),
+                ..
+            })
+            | Inst::X87(X87 {
+                rm: X87Rm::Mem(mem),
+                ..
+            }) => return Some(mem),
+            Inst::Lea(_, _, src)
+            | Inst::Cmovcc(_, _, _, src)
+            | Inst::Imul2(_, _, src)
+            | Inst::Imul3(_, _, src, _)
+            | Inst::Andn(_, _, _, src)
+            | Inst::Bextr(_, _, src, _)
+            | Inst::Blsi(_, _, src)
+            | Inst::Blsmsk(_, _, src)
+            | Inst::Blsr(_, _, src)
+            | Inst::Bsf(_, _, src)
+            | Inst::Bsr(_, _, src)
+            | Inst::Bzhi(_, _, src, _)
+            | Inst::Lzcnt(_, _, src)
+            | Inst::Mulx(_, _, _, src)
+            | Inst::Pdep(_, _, _, src)
+            | Inst::Pext(_, _, _, src)
+            | Inst::Popcnt(_, _, src)
+            | Inst::Rorx(_, _, src, _)
+            | Inst::Sarx(_, _, src, _)
+            | Inst::Shlx(_, _, src, _)
+            | Inst::Shrx(_, _, src, _)
+            | Inst::Tzcnt(_, _, src)
+            | Inst::Movsx(_, _, _, src)
+            | Inst::Movzx(_, _, _, src) => (src, src),
+            Inst::Adc(_, dst, src)
+            | Inst::Add(_, dst, src)
+            | Inst::And(_, dst, src)
+            | Inst::Bt(_, dst, src)
+            | Inst::Btc(_, dst, src)
+            | Inst::Btr(_, dst, src)
+            | Inst::Bts(_, dst, src)
+            | Inst::Cmp(_, dst, src)
+            | Inst::Mov(_, dst, src)
+            | Inst::Movbe(_, dst, src)
+            | Inst::Or(_, dst, src)
+            | Inst::Rcl(_, dst, src)
+            | Inst::Rcr(_, dst, src)
+            | Inst::Rol(_, dst, src)
+            | Inst::Ror(_, dst, src)
+            | Inst::Sar(_, dst, src)
+            | Inst::Sbb(_, dst, src)
+            | Inst::Shl(_, dst, src)
+            | Inst::Shr(_, dst, src)
+            | Inst::Sub(_, dst, src)
+            | Inst::SseMova(_, dst, src)
+            | Inst::SseMovu(_, dst, src)
+            | Inst::Test(_, dst, src)
+            | Inst::Xchg(_, dst, src)
+            | Inst::Xor(_, dst, src) => (dst, src),
+            Inst::Cmpxchg(_, dst, _)
+            | Inst::Wrss(_, dst, _)
+            | Inst::Wruss(_, dst, _)
+            | Inst::Xadd(_, dst, _) => (dst, dst),
+            Inst::Call(arg)
+            | Inst::Clflush(arg)
+            | Inst::Clflushopt(arg)
+            | Inst::Clrssbsy(arg)
+            | Inst::Clwb(arg)
+            | Inst::Dec(_, arg)
+            | Inst::Fxrstor(_, arg)
+            | Inst::Fxsave(_, arg)
+            | Inst::Div(_, arg)
+            | Inst::Idiv(_, arg)
+            | Inst::Imul(_, arg)
+            | Inst::Inc(_, arg)
+            | Inst::Invlpg(arg)
+            | Inst::Jmp(arg)
+            | Inst::Ldmxcsr(arg)
+            | Inst::Mul(_, arg)
+            | Inst::Neg(_, arg)
+            | Inst::Not(_, arg)
+            | Inst::Prefetchnta(arg)
+            | Inst::Prefetcht0(arg)
+            | Inst::Prefetcht1(arg)
+            | Inst::Prefetcht2(arg)
+            | Inst::Prefetchw(arg)
+            | Inst::Push(arg)
+            | Inst::Rstorssp(arg)
+            | Inst::Setcc(_, arg)
+            | Inst::Stmxcsr(arg)
+            | Inst::SseAdd(_, _, arg)
+            | Inst::SseAnd(_, _, arg)
+            | Inst::SseAndn(_, _, arg)
+            | Inst::SseDiv(_, _, arg)
+            | Inst::SseMax(_, _, arg)
+            | Inst::SseMin(_, _, arg)
+            | Inst::SseMul(_, _, arg)
+            | Inst::SseOr(_, _, arg)
+            | Inst::SseSqrt(_, _, arg)
+            | Inst::SseSub(_, _, arg)
+            | Inst::SseXor(_, _, arg)
+            | Inst::Xrstor(_, arg)
+            | Inst::Xrstors(_, arg)
+            | Inst::Xsave(_, arg)
+            | Inst::Xsavec(_, arg)
+            | Inst::Xsaveopt(_, arg)
+            | Inst::Xsaves(_, arg) => (arg, arg),
+            _ => return None,
+        };
+
+        match (dst, src) {
+            (Arg::Mem(mem), _) | (_, Arg::Mem(mem)) => Some(mem),
+            _ => None,
+        }
+    }
+
+    /// obtains the length of the instruction (max 15) when encoded for `mode`
+    #[inline]
+    pub const fn len(&self, mode: Mode) -> Result<usize, EncodeError> {
+        match self.encode(mode) {
+            Ok(writer) => Ok(writer.len()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Returns a zero relative displacement of the width of a near branch in `mode`.
+#[inline]
+const fn rel_arg(mode: Mode) -> Arg {
+    match mode.default_size() {
+        Size::Word => Arg::Imm16(0),
+        _ => Arg::Imm32(0),
+    }
+}
+
+/// Returns `mem` with the displacement `disp`.
+#[inline]
+const fn with_disp(mut mem: Mem, disp: i32) -> Mem {
+    mem.disp = disp;
+    mem
+}
+
+/// Returns the displacement from `next_ip` to `target`.
+#[inline]
+const fn rel_to(target: usize, next_ip: usize) -> isize {
+    (target as isize).wrapping_sub(next_ip as isize)
+}
+
+/// Returns the access to `arg`, if it is a memory operand.
+#[inline]
+const fn access(arg: Arg, read: bool, write: bool, size: Option<usize>) -> Option<MemAccess> {
+    match arg {
+        Arg::Mem(mem) => Some(MemAccess::new(mem, read, write, size)),
+        _ => None,
+    }
+}
+
+/// Returns the access to the memory operand of either `dst`, written and read if `read`, or `src`,
+/// read.
+#[inline]
+const fn operand_access(dst: Arg, src: Arg, read: bool, size: usize) -> Option<MemAccess> {
+    match (dst, src) {
+        (Arg::Mem(mem), _) => Some(MemAccess::new(mem, read, true, Some(size))),
+        (_, Arg::Mem(mem)) => Some(MemAccess::read(mem, size)),
+        _ => None,
+    }
+}
+
+/// Returns the mnemonic of the form of an instruction with an operand size of `size`.
+#[inline]
+const fn sized(
+    size: Size,
+    word: &'static str,
+    dword: &'static str,
+    qword: &'static str,
+) -> &'static str {
+    match size {
+        Size::Word => word,
+        Size::Qword => qword,
+        _ => dword,
+    }
+}
+
+/// Returns the mnemonic of the SSE form `fp`, given the mnemonics indexed as [`Fp`].
+#[inline]
+const fn fp_form(fp: Fp, mnemonics: [&'static str; 4]) -> &'static str {
+    mnemonics[fp as usize]
+}
+
+/// Returns the register encoded in the low bits of an opcode, extended by REX.B.
+#[inline]
+const fn opcode_reg(opcode: u8, rex: Rex) -> u8 {
+    (opcode & 0b111) | rex.b()
+}
+
+/// Returns whether `size` requires REX.W.
+#[inline]
+const fn rex_w(size: Size) -> bool {
+    matches!(size, Size::Qword)
+}
+
+/// Returns the register `bits` of size `size`.
+///
+/// Without a REX prefix, byte registers 4 to 7 are `ah`, `ch`, `dh` and `bh`, which only
+/// operands can represent, see [`gpr_arg`].
+#[inline]
+const fn gpr(size: Size, bits: u8, prefixes: Prefixes) -> Option<Reg> {
+    if matches!(size, Size::Byte) && !prefixes.rex.is_present() && matches!(bits, 4..=7) {
+        return None;
+    }
+
+    Reg::from_bits(bits)
+}
+
+/// Returns the register operand `bits` of size `size`.
+///
+/// Without a REX prefix, byte registers 4 to 7 are `ah`, `ch`, `dh` and `bh`.
+#[inline]
+const fn gpr_arg(size: Size, bits: u8, prefixes: Prefixes) -> Option<Arg> {
+    if matches!(size, Size::Byte) && !prefixes.rex.is_present() && matches!(bits, 4..=7) {
+        return Some(Arg::High(tri!(Reg::from_bits(bits - 4))));
+    }
+
+    Some(Arg::Reg(tri!(Reg::from_bits(bits))))
+}
+
+/// Returns `arg` of size `size` with `ah`, `ch`, `dh` or `bh` as `spl`, `bpl`, `sil` or `dil`,
+/// setting `high` if it was, or `low` if it is one of the latter.
+///
+/// Returns `None` if it is the second byte of a register without one.
+#[inline]
+const fn high_as_low(size: Size, arg: Arg, high: &mut bool, low: &mut bool) -> Option<Arg> {
+    match arg {
+        Arg::High(reg) if reg.bits() < 4 => {
+            *high = true;
+
+            Some(Arg::Reg(tri!(Reg::from_bits(reg.bits() + 4))))
+        }
+        Arg::High(_) => None,
+        Arg::Reg(reg) if matches!(size, Size::Byte) && matches!(reg.bits(), 4..=7) => {
+            *low = true;
+
+            Some(arg)
+        }
+        arg => Some(arg),
+    }
+}
+
+/// Returns the REX prefix required to encode `reg` and `rm` at `size`.
+///
+/// Byte-sized `spl`, `bpl`, `sil` and `dil` require a REX prefix, even if empty.
+#[inline]
+const fn op_rex(size: Size, reg: Reg, rm: Rm) -> Rex {
+    let rex = rm.rex(rex_w(size), reg.bits());
+
+    if matches!(size, Size::Byte) && (matches!(reg.bits(), 4..=7) || matches!(rm, Rm::Reg(4..=7))) {
+        rex.present()
+    } else {
+        rex
+    }
+}
+
+/// Returns the REX prefix required to encode `rm` at `size`, with an opcode extension.
+#[inline]
+const fn ext_rex(size: Size, rm: Rm) -> Rex {
+    let rex = rm.rex(rex_w(size), 0);
+
+    if matches!(size, Size::Byte) && matches!(rm, Rm::Reg(4..=7)) {
+        rex.present()
+    } else {
+        rex
+    }
+}
+
+/// Write `opcode` with a register, and a register or memory operand.
+#[inline]
+const fn encode_rm(writer: &mut Writer, size: Size, opcode: &[u8], reg: Reg, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    writer.write_op(
+        rm.prefixes(size, op_rex(size, reg, modrm), writer.mode()),
+        opcode,
+        reg.bits(),
+        modrm,
+    );
+}
+
+/// Write `opcode` with an opcode extension (`/digit`), and a register or memory operand.
+#[inline]
+const fn encode_ext(writer: &mut Writer, size: Size, opcode: &[u8], digit: u8, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    writer.write_op(
+        rm.prefixes(size, ext_rex(size, modrm), writer.mode()),
+        opcode,
+        digit,
+        modrm,
+    );
+}
+
+/// Write an operation within a group at `width`, with a register or memory operand.
+///
+/// Branch targets and the stack are 64-bit by default, REX.W is not required.
+#[inline]
+const fn encode_group(writer: &mut Writer, ext: Ext, width: Width, rm: Arg) {
+    let size = writer.mode().default_size();
+
+    encode_group_sized(writer, ext, width, size, rm);
+}
+
+/// Write an operation within a group at `width`, with a register or memory operand of `size`.
+#[inline]
+const fn encode_group_sized(writer: &mut Writer, ext: Ext, width: Width, size: Size, rm: Arg) {
+    let Some((opcode, digit)) = Group::find(ext, width) else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    encode_ext(writer, size, &[opcode], digit, rm);
+}
+
+/// Write `opcode` with an opcode extension (`/digit`), and a register or memory operand, `size`
+/// only selecting REX.W.
+#[inline]
+const fn encode_rexw_ext(writer: &mut Writer, size: Size, opcode: &[u8], digit: u8, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    let mode = writer.mode();
+
+    writer.write_op(
+        rm.prefixes(mode.default_size(), ext_rex(size, modrm), mode),
+        opcode,
+        digit,
+        modrm,
+    );
+}
+
+/// Write a three-byte opcode map `entry` with its mandatory prefix, a register, and a register or
+/// memory operand.
+#[inline]
+const fn encode_map(writer: &mut Writer, entry: Entry, size: Size, reg: Reg, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    let mode = writer.mode();
+    let rex = op_rex(size, reg, modrm);
+
+    let mut prefixes = if entry.rexw {
+        rm.prefixes(mode.default_size(), rex, mode)
+    } else {
+        rm.prefixes(size, rex, mode)
+    };
+
+    prefixes.operand_size |= entry.pp == vex::PP_66;
+    prefixes.rep = entry.pp == vex::PP_F3;
+    prefixes.repne = entry.pp == vex::PP_F2;
+
+    writer.write_op(
+        prefixes,
+        &[0x0F, entry.map, entry.opcode],
+        reg.bits(),
+        modrm,
+    );
+}
+
+/// Write `opcode` with a mandatory `F3` prefix and an opcode extension (`/digit`), and a register
+/// or memory operand.
+#[inline]
+const fn encode_rep_ext(writer: &mut Writer, size: Size, opcode: &[u8], digit: u8, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    let mut prefixes = rm.prefixes(size, ext_rex(size, modrm), writer.mode());
+
+    prefixes.rep = true;
+
+    writer.write_op(prefixes, opcode, digit, modrm);
+}
+
+/// Write `opcode` with a mandatory `F3` prefix, a register, and a register or memory operand.
+#[inline]
+const fn encode_rep_rm(writer: &mut Writer, size: Size, opcode: &[u8], reg: Reg, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
+
+    let mut prefixes = rm.prefixes(size, op_rex(size, reg, modrm), writer.mode());
+
+    prefixes.rep = true;
+
+    writer.write_op(prefixes, opcode, reg.bits(), modrm);
 }
 
-const REX_W: u8 = 0x48;
+/// Write a VEX encoded `opcode` with a register (or opcode extension), and a register or memory
+/// operand.
+#[inline]
+const fn encode_vex(writer: &mut Writer, vex: Vex, opcode: u8, reg: u8, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
 
-impl Inst {
-    #[inline]
-    pub const fn from_bytes(bytes: &[u8]) -> Option<Inst> {
-        let inst = match bytes {
-            /*// mov rax rdi
-            [REX_W, 0x89, 0xC7, ..] => {}
-            // xor rdi rdi
-            [REX_W, 0x31, 0xFF, ..] => {}
-            // mov rdi, i32
-            [REX_W, 0xC7, 0xC7, a, b, c, d, ..] => {}
-            // mov rax, i32
-            [REX_W, 0xC7, 0xC0, a, b, c, d, ..] => {}
-            // lea rip rsi
-            [REX_W, 0x8D, 0x35, a, b, c, d, ..] => {}*/
+    let prefixes = rm.prefixes(Size::Dword, Rex::NONE, writer.mode());
 
-            // mov rax, qword ptr [rip+rel]
-            [REX_W, 0x8B, 0x05, a, b, c, d, ..] => {
-                Inst::Mov(Reg::Rax, Arg::Int(i32::from_le_bytes([*a, *b, *c, *d])))
-            }
+    vex.encode(writer, prefixes, opcode, reg, modrm);
+}
 
-            // lea
-            [REX_W, 0x8D, 0x0D, a, b, c, d, ..] => {
-                Inst::Lea(Reg::Rcx, Arg::Int(i32::from_le_bytes([*a, *b, *c, *d])))
-            }
+/// Write an SSE `opcode` (following `0F`) with its mandatory prefix, an `xmm` register, and a
+/// register or memory operand.
+#[inline]
+const fn encode_sse(writer: &mut Writer, fp: Fp, opcode: u8, reg: Xmm, rm: Arg) {
+    let Some(modrm) = rm.to_rm() else {
+        writer.fail(EncodeError::InvalidOperands);
+        return;
+    };
 
-            // jmp
-            [0xFF, 0x25, a, b, c, d, ..] => Inst::Jmp(i32::from_le_bytes([*a, *b, *c, *d])),
+    let mut prefixes = rm.prefixes(
+        writer.mode().default_size(),
+        modrm.rex(false, reg.bits()),
+        writer.mode(),
+    );
 
-            // call
-            [0xFF, 0x15, a, b, c, d, ..] => Inst::Call2(i32::from_le_bytes([*a, *b, *c, *d])),
+    prefixes.operand_size = matches!(fp, Fp::Pd);
+    prefixes.rep = matches!(fp, Fp::Ss);
+    prefixes.repne = matches!(fp, Fp::Sd);
 
-            // call
-            [0xE8, a, b, c, d, ..] => Inst::Call(i32::from_le_bytes([*a, *b, *c, *d])),
+    writer.write_op(prefixes, &[0x0F, opcode], reg.bits(), modrm);
+}
 
-            // push
-            [0x6A, byte] => Inst::Push(Arg::Int(*byte as i32)),
+/// Decode a VEX encoded BMI1/BMI2 instruction, `bytes` following the VEX prefix.
+#[inline]
+const fn bmi(vex: Vex, bytes: &[u8], prefixes: Prefixes) -> Option<Inst> {
+    // scalar only
+    if vex.l {
+        return None;
+    }
 
-            // syscall
-            [0x0F, 0x05, ..] => Inst::Syscall,
+    // W is ignored outside of long mode
+    let size = if vex.rex.w() && prefixes.mode.has_rex() {
+        Size::Qword
+    } else {
+        Size::Dword
+    };
 
-            // push reg <= r7
-            [0x41, reg @ 0x50..=0x57, ..] => {
-                Inst::Push(Arg::Reg(unsafe { Reg::from_hi_unchecked(*reg) }))
-            }
+    let (opcode, rest) = match bytes {
+        [opcode, rest @ ..] => (*opcode, rest),
+        _ => return None,
+    };
 
-            // pop reg <= r7
-            [0x41, reg @ 0x58..=0x5F, ..] => Inst::Pop(unsafe { Reg::from_hi_unchecked(*reg) }),
+    let prefixes = Prefixes {
+        rex: vex.rex,
+        ..prefixes
+    };
 
-            // ret
-            [0xC3, ..] => Inst::Ret,
+    let modrm = tri!(ModRm::from_bytes(rest, prefixes));
+    let reg = tri!(Reg::from_bits(modrm.reg));
+    let src = tri!(Arg::from_rm(size, modrm.rm, prefixes));
+    let vvvv = tri!(Reg::from_bits(vex.vvvv));
 
-            // push reg <= r7
-            [reg @ 0x50..=0x57, ..] => {
-                Inst::Push(Arg::Reg(unsafe { Reg::from_lo_unchecked(*reg) }))
+    let inst = match (vex.map, vex.pp, opcode) {
+        (vex::MAP_0F38, vex::PP_NONE, 0xF2) => Inst::Andn(size, reg, vvvv, src),
+        (vex::MAP_0F38, vex::PP_NONE, 0xF3) => match modrm.reg & 0b111 {
+            1 => Inst::Blsr(size, vvvv, src),
+            2 => Inst::Blsmsk(size, vvvv, src),
+            3 => Inst::Blsi(size, vvvv, src),
+            _ => return None,
+        },
+        (vex::MAP_0F38, vex::PP_NONE, 0xF5) => Inst::Bzhi(size, reg, src, vvvv),
+        (vex::MAP_0F38, vex::PP_F3, 0xF5) => Inst::Pext(size, reg, vvvv, src),
+        (vex::MAP_0F38, vex::PP_F2, 0xF5) => Inst::Pdep(size, reg, vvvv, src),
+        (vex::MAP_0F38, vex::PP_F2, 0xF6) => Inst::Mulx(size, reg, vvvv, src),
+        (vex::MAP_0F38, vex::PP_NONE, 0xF7) => Inst::Bextr(size, reg, src, vvvv),
+        (vex::MAP_0F38, vex::PP_66, 0xF7) => Inst::Shlx(size, reg, src, vvvv),
+        (vex::MAP_0F38, vex::PP_F3, 0xF7) => Inst::Sarx(size, reg, src, vvvv),
+        (vex::MAP_0F38, vex::PP_F2, 0xF7) => Inst::Shrx(size, reg, src, vvvv),
+        // vvvv is reserved
+        (vex::MAP_0F3A, vex::PP_F2, 0xF0) if vex.vvvv == 0 => {
+            match rest.split_at_checked(modrm.len) {
+                Some((_, [imm, ..])) => Inst::Rorx(size, reg, src, *imm),
+                _ => return None,
             }
+        }
+        _ => return None,
+    };
+
+    Some(inst)
+}
+
+/// Construct an SSE instruction from its opcode (following `0F`).
+///
+/// Aligned moves and bitwise operations only exist in packed form.
+#[inline]
+const fn sse(op: u8, fp: Fp, reg: Xmm, rm: Arg) -> Option<Inst> {
+    if matches!(op, 0x28 | 0x29 | 0x54..=0x57) && !fp.is_packed() {
+        return None;
+    }
+
+    let inst = match op {
+        0x10 => Inst::SseMovu(fp, Arg::Xmm(reg), rm),
+        0x11 => Inst::SseMovu(fp, rm, Arg::Xmm(reg)),
+        0x28 => Inst::SseMova(fp, Arg::Xmm(reg), rm),
+        0x29 => Inst::SseMova(fp, rm, Arg::Xmm(reg)),
+        0x51 => Inst::SseSqrt(fp, reg, rm),
+        0x54 => Inst::SseAnd(fp, reg, rm),
+        0x55 => Inst::SseAndn(fp, reg, rm),
+        0x56 => Inst::SseOr(fp, reg, rm),
+        0x57 => Inst::SseXor(fp, reg, rm),
+        0x58 => Inst::SseAdd(fp, reg, rm),
+        0x59 => Inst::SseMul(fp, reg, rm),
+        0x5C => Inst::SseSub(fp, reg, rm),
+        0x5D => Inst::SseMin(fp, reg, rm),
+        0x5E => Inst::SseDiv(fp, reg, rm),
+        0x5F => Inst::SseMax(fp, reg, rm),
+        _ => return None,
+    };
+
+    Some(inst)
+}
+
+/// Construct an ALU instruction from its operation (`/digit`).
+#[inline]
+const fn alu(op: u8, size: Size, dst: Arg, src: Arg) -> Inst {
+    match op {
+        0 => Inst::Add(size, dst, src),
+        1 => Inst::Or(size, dst, src),
+        2 => Inst::Adc(size, dst, src),
+        3 => Inst::Sbb(size, dst, src),
+        4 => Inst::And(size, dst, src),
+        5 => Inst::Sub(size, dst, src),
+        6 => Inst::Xor(size, dst, src),
+        _ => Inst::Cmp(size, dst, src),
+    }
+}
+
+/// Returns the length of a NOP at the start of `bytes`.
+///
+/// This recognizes `90`, and `0F 1F /0` with any number of `66` prefixes and an optional `2E`
+/// prefix, as emitted by compilers for padding.
+#[inline]
+const fn nop_len(bytes: &[u8], mode: Mode) -> Option<u8> {
+    let mut offset = 0;
+
+    while offset < bytes.len() && bytes[offset] == 0x66 {
+        offset += 1;
+    }
+
+    if offset < bytes.len() && bytes[offset] == 0x2E {
+        offset += 1;
+    }
 
-            // pop reg <= r7
-            [reg @ 0x58..=0x5F, ..] => Inst::Pop(unsafe { Reg::from_lo_unchecked(*reg) }),
+    let (_, rest) = bytes.split_at(offset);
 
+    let len = match rest {
+        [0x90, ..] => offset + 1,
+        [0x0F, 0x1F, rest @ ..] => match ModRm::from_bytes(rest, Prefixes::none(mode)) {
+            Some(modrm) if modrm.reg == 0 => offset + 2 + modrm.len,
             _ => return None,
-        };
+        },
+        _ => return None,
+    };
 
-        Some(inst)
+    if len > 15 {
+        return None;
+    }
+
+    Some(len as u8)
+}
+
+/// Returns the operand size of an instruction which ignores the operand-size prefix, only REX.W.
+#[inline]
+const fn rexw_size(rex: Rex) -> Size {
+    if rex.w() {
+        Size::Qword
+    } else {
+        Size::Dword
+    }
+}
+
+/// Returns the `reg` field (`/digit`) of the ModRM byte at the start of `bytes`.
+#[inline]
+const fn modrm_digit(bytes: &[u8]) -> u8 {
+    match bytes {
+        [modrm, ..] => (*modrm >> 3) & 0b111,
+        [] => 0,
+    }
+}
+
+/// Construct a bit test instruction from its operation (`/digit` less 4).
+#[inline]
+const fn bit(op: u8, size: Size, dst: Arg, src: Arg) -> Inst {
+    match op {
+        0 => Inst::Bt(size, dst, src),
+        1 => Inst::Bts(size, dst, src),
+        2 => Inst::Btr(size, dst, src),
+        _ => Inst::Btc(size, dst, src),
+    }
+}
+
+/// Construct a shift instruction from its operation (`/digit`).
+#[inline]
+const fn shift(op: u8, size: Size, dst: Arg, count: Arg) -> Inst {
+    match op {
+        0 => Inst::Rol(size, dst, count),
+        1 => Inst::Ror(size, dst, count),
+        2 => Inst::Rcl(size, dst, count),
+        3 => Inst::Rcr(size, dst, count),
+        // 6 is an alias of shl
+        4 | 6 => Inst::Shl(size, dst, count),
+        5 => Inst::Shr(size, dst, count),
+        _ => Inst::Sar(size, dst, count),
+    }
+}
+
+/// Read an i8 at `offset`.
+#[inline]
+const fn read_i8(bytes: &[u8], offset: usize) -> Option<i8> {
+    match bytes.split_at_checked(offset) {
+        Some((_, [a, ..])) => Some(*a as i8),
+        _ => None,
+    }
+}
+
+/// Read an i32 at `offset`.
+#[inline]
+const fn read_i32(bytes: &[u8], offset: usize) -> Option<i32> {
+    match bytes.split_at_checked(offset) {
+        Some((_, [a, b, c, d, ..])) => Some(i32::from_le_bytes([*a, *b, *c, *d])),
+        _ => None,
+    }
+}
+
+/// Read a 16 or 32-bit immediate at `offset`, depending on `size`.
+#[inline]
+const fn read_imm(size: Size, bytes: &[u8], offset: usize) -> Option<i32> {
+    match (size, bytes.split_at_checked(offset)) {
+        (Size::Word, Some((_, [a, b, ..]))) => Some(i16::from_le_bytes([*a, *b]) as i32),
+        (Size::Word, _) => None,
+        _ => read_i32(bytes, offset),
+    }
+}
+
+/// Read a 16 or 32-bit immediate operand at `offset`, depending on `size`.
+#[inline]
+const fn read_imm_arg(size: Size, bytes: &[u8], offset: usize) -> Option<Arg> {
+    let Some(imm) = read_imm(size, bytes, offset) else {
+        return None;
+    };
+
+    match size {
+        Size::Word => Some(Arg::Imm16(imm as i16)),
+        _ => Some(Arg::Imm32(imm)),
+    }
+}
+
+/// Returns a 16 or 32-bit immediate operand as an i32.
+#[inline]
+const fn imm_z(arg: Arg) -> i32 {
+    match arg {
+        Arg::Imm8(imm) => imm as i32,
+        Arg::Imm16(imm) => imm as i32,
+        Arg::Imm32(imm) => imm,
+        _ => 0,
     }
+}
+
+/// A register, immediate, or memory operand.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Arg {
+    Reg(Reg),
+    /// `ah`, `ch`, `dh` or `bh`, the second byte of `rax`, `rcx`, `rdx` or `rbx`.
+    High(Reg),
+    Imm8(i8),
+    Imm16(i16),
+    Imm32(i32),
+    Imm64(i64),
+    Mem(Mem),
+    Xmm(Xmm),
+}
 
+impl Arg {
+    /// Returns the sign extended value of this operand if it is an immediate.
     #[inline]
-    pub const fn to_bytes(&self) -> Vec<u8, 15> {
-        let mut encoder = Encoder::new();
+    pub const fn imm(self) -> Option<i64> {
+        match self {
+            Arg::Imm8(imm) => Some(imm as i64),
+            Arg::Imm16(imm) => Some(imm as i64),
+            Arg::Imm32(imm) => Some(imm as i64),
+            Arg::Imm64(imm) => Some(imm),
+            _ => None,
+        }
+    }
 
-        unsafe {
-            match *self {
-                Inst::Call(rel) => {
-                    encoder.write_u8(0xE8);
-                    encoder.write_i32(rel);
-                }
-                Inst::Call2(rel) => {
-                    encoder.write_bytes(&[0xFF, 0x15]);
-                    encoder.write_i32(rel);
-                }
-                Inst::Lea(Reg::Rcx, Arg::Int(rel)) => {
-                    encoder.write_bytes(&[REX_W, 0x8D, 0x0D]);
-                    encoder.write_i32(rel);
-                }
-                Inst::Jmp(rel) => {
-                    encoder.write_bytes(&[0xFF, 0x25]);
-                    encoder.write_i32(rel);
-                }
-                Inst::Mov(Reg::Rax, Arg::Int(rel)) => {
-                    encoder.write_bytes(&[REX_W, 0x8B, 0x05]);
-                    encoder.write_i32(rel);
-                }
-                Inst::Pop(reg) => {
-                    if reg.is_hi() {
-                        encoder.write_bytes(&[0x41, 0x58 | reg.base_bits()]);
-                    } else {
-                        encoder.write_u8(0x58 | reg.bits());
-                    }
-                }
-                Inst::Push(Arg::Reg(reg)) => {
-                    if reg.is_hi() {
-                        encoder.write_bytes(&[0x41, 0x50 | reg.base_bits()]);
-                    } else {
-                        encoder.write_u8(0x50 | reg.bits());
-                    }
-                }
-                Inst::Ret => {
-                    encoder.write_u8(0xC3);
-                }
-                Inst::Syscall => {
-                    encoder.write_bytes(&[0x0F, 0x05]);
-                }
-                _ => unreachable!(),
-            }
+    /// Returns the encoded width of this operand in bytes if it is an immediate.
+    #[inline]
+    pub const fn imm_len(self) -> Option<usize> {
+        match self {
+            Arg::Imm8(_) => Some(1),
+            Arg::Imm16(_) => Some(2),
+            Arg::Imm32(_) => Some(4),
+            Arg::Imm64(_) => Some(8),
+            _ => None,
         }
+    }
 
-        encoder.into_vec()
+    /// Returns the register of this operand at `size`, the size of the instruction, if it is a
+    /// register.
+    #[inline]
+    pub const fn sub_reg(self, size: Size) -> Option<SubReg> {
+        match self {
+            Arg::Reg(reg) => Some(reg.with_size(size)),
+            Arg::High(reg) => SubReg::high(reg),
+            _ => None,
+        }
     }
 
-    /// Returns the relative address if present in this instruction.
+    /// Returns the general purpose registers used to compute the address of a memory operand.
     #[inline]
-    pub const fn rel_addr(&self) -> Option<isize> {
-        let rel = match self {
-            Inst::Call(rel)
-            | Inst::Call2(rel)
-            | Inst::Jmp(rel)
-            | Inst::Mov(Reg::Rax, Arg::Int(rel)) => rel,
-            _ => return None,
-        };
+    pub const fn addr_regs(self) -> RegSet {
+        match self {
+            Arg::Mem(mem) => mem.regs(),
+            _ => RegSet::EMPTY,
+        }
+    }
 
-        Some(*rel as isize)
+    /// Returns the general purpose registers read by this operand as a source.
+    #[inline]
+    pub const fn regs_read(self) -> RegSet {
+        match self {
+            Arg::Reg(reg) | Arg::High(reg) => RegSet::of(reg),
+            _ => self.addr_regs(),
+        }
     }
-    /// obtains the length of the instruction (max 15)
+
+    /// Returns the general purpose registers written by this operand as a destination.
     #[inline]
-    pub const fn len(&self) -> usize {
+    pub const fn regs_written(self) -> RegSet {
         match self {
-            Inst::Call(_) => 5,
-            Inst::Call2(_) => 6,
-            Inst::Lea(_, _) => 7,
-            Inst::Jmp(_) => 6,
-            Inst::Mov(_, _) => 7,
-            Inst::Pop(reg) => {
-                if reg.is_hi() {
-                    2
-                } else {
-                    1
-                }
-            }
-            Inst::Push(_) => 1,
-            Inst::Ret => 1,
-            Inst::Syscall => 2,
-            Inst::Xor(_, _) => 3,
+            Arg::Reg(reg) | Arg::High(reg) => RegSet::of(reg),
+            _ => RegSet::EMPTY,
         }
     }
-}
 
-/// A register or i32,
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum Arg {
-    Reg(Reg),
-    Int(i32),
+    /// Convert a ModRM `r/m` operand of size `size`.
+    #[inline]
+    const fn from_rm(size: Size, rm: Rm, prefixes: Prefixes) -> Option<Self> {
+        match rm {
+            Rm::Reg(bits) => gpr_arg(size, bits, prefixes),
+            rm => Self::from_mem(rm, prefixes),
+        }
+    }
+
+    /// Convert a ModRM `r/m` operand, where registers are `xmm` registers.
+    #[inline]
+    const fn from_xmm_rm(rm: Rm, prefixes: Prefixes) -> Option<Self> {
+        match rm {
+            Rm::Reg(bits) => Some(Arg::Xmm(Xmm::from_bits(bits))),
+            rm => Self::from_mem(rm, prefixes),
+        }
+    }
+
+    /// Convert a memory ModRM `r/m` operand, applying `prefixes`.
+    #[inline]
+    const fn from_mem(rm: Rm, prefixes: Prefixes) -> Option<Self> {
+        let mut mem = tri!(Mem::from_rm(rm));
+
+        mem.seg = prefixes.seg;
+        mem.lock = prefixes.lock;
+        mem.addr_size = prefixes.addr_size();
+
+        Some(Arg::Mem(mem))
+    }
+
+    /// Convert to a ModRM `r/m` operand.
+    #[inline]
+    const fn to_rm(self) -> Option<Rm> {
+        match self {
+            Arg::Reg(reg) => Some(Rm::Reg(reg.bits())),
+            Arg::Mem(mem) => mem.to_rm(),
+            Arg::Xmm(xmm) => Some(Rm::Reg(xmm.bits())),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is a locked memory operand.
+    #[inline]
+    const fn is_locked(self) -> bool {
+        match self {
+            Arg::Mem(mem) => mem.lock,
+            _ => false,
+        }
+    }
+
+    /// Returns the segment override of a memory operand.
+    #[inline]
+    const fn seg(self) -> Option<Seg> {
+        match self {
+            Arg::Mem(mem) => mem.seg,
+            _ => None,
+        }
+    }
+
+    /// Returns the prefixes required to encode this operand at `size` for `mode`, along with `rex`.
+    #[inline]
+    const fn prefixes(self, size: Size, rex: Rex, mode: Mode) -> Prefixes {
+        // the address size differs from the default of the mode
+        let address_size = match self {
+            Arg::Mem(mem) => mode.needs_addr_prefix(mem.addr_size),
+            _ => false,
+        };
+
+        Prefixes {
+            lock: self.is_locked(),
+            seg: self.seg(),
+            operand_size: mode.needs_size_prefix(size),
+            address_size,
+            rex,
+            ..Prefixes::none(mode)
+        }
+    }
 }
 
 /// Instruction pointer alongside an instruction.
-#[derive(Clone, Copy, Eq, PartialEq)]
+///
+/// Ordered by instruction pointer first.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct WithIp {
     ip: usize,
     inst: Inst,
+    bytes: [u8; 15],
+    len: usize,
+    mode: Mode,
 }
 
 impl WithIp {
-    /// Construct a new instruction with an associated instruction pointer.
+    /// Construct a new instruction with an associated instruction pointer, decoded in `mode`.
+    #[inline]
+    pub const fn new(ip: usize, inst: Inst, mode: Mode) -> Result<Self, EncodeError> {
+        match inst.encode(mode) {
+            Ok(writer) => Ok(Self::decoded(ip, inst, writer.as_bytes(), mode)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Construct a new instruction decoded from `bytes`.
     #[inline]
-    pub const fn new(ip: usize, inst: Inst) -> Self {
-        Self { ip, inst }
+    const fn decoded(ip: usize, inst: Inst, bytes: &[u8], mode: Mode) -> Self {
+        let mut array = [0; 15];
+        let mut index = 0;
+
+        while index < bytes.len() {
+            array[index] = bytes[index];
+            index += 1;
+        }
+
+        Self {
+            ip,
+            inst,
+            bytes: array,
+            len: bytes.len(),
+            mode,
+        }
     }
 
     /// Resolves the relative address (if present)
     #[inline]
     pub const fn abs_addr(self) -> Option<usize> {
+        match self.target() {
+            Target::Absolute(addr) | Target::RipRelativeMem(addr) => Some(addr),
+            _ => None,
+        }
+    }
+
+    /// Returns the target of a branch, or the address referenced by a `rip` relative memory
+    /// operand.
+    #[inline]
+    pub const fn target(self) -> Target {
         // relative addresses are calculated from the ip after the current instruction.
-        let ip = self.next_ip();
-        let addr = ip as isize + self.inst.rel_addr()?;
+        let ip = self.next_ip() as isize;
 
-        Some(addr as usize)
+        match self.inst {
+            Inst::Call(Arg::Reg(reg)) | Inst::Jmp(Arg::Reg(reg)) => Target::Register(reg),
+            Inst::Call(Arg::Mem(mem)) | Inst::Jmp(Arg::Mem(mem)) if !mem.is_rip() => {
+                Target::Memory(mem)
+            }
+            inst => match (inst.rel_addr(), inst.mem()) {
+                (Some(rel), Some(_)) => Target::RipRelativeMem(ip.wrapping_add(rel) as usize),
+                (Some(rel), None) => Target::Absolute(ip.wrapping_add(rel) as usize),
+                (None, _) => Target::None,
+            },
+        }
     }
 
     /// Returns the current instruction pointer.
@@ -222,12 +3770,43 @@ impl WithIp {
         self.ip
     }
 
+    /// Returns the length of this instruction, as decoded.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub const fn len(self) -> usize {
+        self.len
+    }
+
+    /// Returns the bytes this instruction was decoded from, which may differ from its encoding.
+    #[inline]
+    pub const fn bytes(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+
+    /// Returns the mode this instruction was decoded in.
+    #[inline]
+    pub const fn mode(self) -> Mode {
+        self.mode
+    }
+
     /// Returns the next instruction pointer.
     ///
     /// Equivalent to `withip.ip() + withip.len()`.
     #[inline]
     pub const fn next_ip(self) -> usize {
-        self.ip + self.inst.len()
+        self.ip.wrapping_add(self.len)
+    }
+
+    /// Returns the addresses this instruction occupies, `ip..next_ip`.
+    #[inline]
+    pub const fn range(self) -> ops::Range<usize> {
+        self.ip..self.next_ip()
+    }
+
+    /// Returns `true` if this instruction occupies `addr`.
+    #[inline]
+    pub const fn contains(self, addr: usize) -> bool {
+        self.ip <= addr && addr < self.next_ip()
     }
 
     /// Fancy formatter, not implemented yet.
@@ -237,6 +3816,17 @@ impl WithIp {
     }
 }
 
+impl fmt::Debug for WithIp {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("WithIp")
+            .field("ip", &self.ip)
+            .field("inst", &self.inst)
+            .field("bytes", &self.bytes())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
 impl ops::Deref for WithIp {
     type Target = Inst;
 
@@ -247,18 +3837,102 @@ impl ops::Deref for WithIp {
 }
 
 /// Instruction iterator (decoder).
+///
+/// Cloning copies the position without decoding, a clone may be advanced to look ahead.
+#[derive(Clone)]
 pub struct InstIter<'a> {
     bytes: &'a [u8],
     ip: usize,
     offset: usize,
+    mode: Mode,
+    lengths: bool,
+    resync: bool,
+    remaining: usize,
+    error: Option<DecodeError>,
 }
 
 impl<'a> InstIter<'a> {
+    /// Decode `bytes` in `mode`, starting at `ip`.
     #[inline]
-    pub fn from_bytes(ip: usize, bytes: &'a [u8]) -> Self {
+    pub fn from_bytes(ip: usize, bytes: &'a [u8], mode: Mode) -> Self {
         let offset = 0;
+        let lengths = false;
+        let resync = false;
+        let remaining = usize::MAX;
+        let error = None;
+
+        Self {
+            bytes,
+            ip,
+            offset,
+            mode,
+            lengths,
+            resync,
+            remaining,
+            error,
+        }
+    }
+
+    /// Yield [`Inst::Other`] for instructions which cannot be decoded, but whose length can be
+    /// determined, rather than ending.
+    ///
+    /// See [`Inst::decode_len`].
+    #[inline]
+    pub fn lengths(mut self) -> Self {
+        self.lengths = true;
+        self
+    }
+
+    /// Yield [`Inst::Unknown`] for a byte which cannot be decoded, and continue from the next
+    /// byte, rather than ending.
+    ///
+    /// A truncated instruction at the end of the bytes still ends decoding.
+    ///
+    /// Combine with [`InstIter::lengths`] to skip whole instructions where their length is known.
+    #[inline]
+    pub fn resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// Decode at most the next `len` bytes, an instruction which extends past them is not decoded.
+    #[inline]
+    pub fn take_bytes(mut self, len: usize) -> Self {
+        let end = self.offset.saturating_add(len).min(self.bytes.len());
+
+        self.bytes = &self.bytes[..end];
+        self
+    }
+
+    /// Decode at most the next `count` instructions.
+    #[inline]
+    pub fn take_insts(mut self, count: usize) -> Self {
+        self.remaining = self.remaining.min(count);
+        self
+    }
+
+    /// Decode the next instruction without advancing.
+    #[inline]
+    pub fn peek(&self) -> Option<WithIp> {
+        self.clone().next()
+    }
+
+    /// Decode the instruction `n` after the next without advancing, `peek_n(0)` is `peek()`.
+    #[inline]
+    pub fn peek_n(&self, n: usize) -> Option<WithIp> {
+        self.clone().nth(n)
+    }
 
-        Self { bytes, ip, offset }
+    /// Returns why decoding ended, if it ended before the end of the bytes.
+    #[inline]
+    pub fn error(&self) -> Option<DecodeError> {
+        self.error
+    }
+
+    /// Returns `true` if decoding ended at an instruction which extends past the end of the bytes.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        matches!(self.error, Some(DecodeError::TruncatedInstruction { .. }))
     }
 }
 
@@ -267,17 +3941,144 @@ impl<'a> Iterator for InstIter<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<WithIp> {
-        let rest = &self.bytes[self.offset..];
+        if self.remaining == 0 || self.error.is_some() {
+            return None;
+        }
+
+        let rest = self.bytes.get(self.offset..)?;
+
+        if rest.is_empty() {
+            return None;
+        }
 
-        match Inst::from_bytes(rest) {
-            Some(inst) => {
-                let ip = self.ip + self.offset;
+        let decoded = if self.lengths {
+            Inst::decode_len(rest, self.mode)
+        } else {
+            Inst::decode(rest, self.mode)
+        };
 
-                self.offset += inst.len();
+        let (inst, len) = match (decoded, rest) {
+            (Ok((_, len)), _) if len > rest.len() => {
+                self.error = Some(DecodeError::TruncatedInstruction { needed: len });
 
-                Some(WithIp::new(ip, inst))
+                return None;
             }
-            None => None,
+            (Ok(decoded), _) => decoded,
+            (Err(DecodeError::TruncatedInstruction { needed }), _) => {
+                self.error = Some(DecodeError::TruncatedInstruction { needed });
+
+                return None;
+            }
+            (Err(_), [byte, ..]) if self.resync => (Inst::Unknown(*byte), 1),
+            (Err(error), _) => {
+                self.error = Some(error);
+
+                return None;
+            }
+        };
+
+        let ip = self.ip.wrapping_add(self.offset);
+
+        self.offset += len;
+        self.remaining -= 1;
+
+        Some(WithIp::decoded(ip, inst, &rest[..len], self.mode))
+    }
+
+    /// Every instruction occupies at least one byte, and decoding may end early.
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.error.is_some() {
+            return (0, Some(0));
+        }
+
+        let rest = self.bytes.len().saturating_sub(self.offset);
+
+        (0, Some(rest.min(self.remaining)))
+    }
+}
+
+impl<'a> iter::FusedIterator for InstIter<'a> {}
+
+/// The result of [`Decoder::decode`].
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Decoded {
+    /// A decoded instruction.
+    Inst(WithIp),
+    /// The instruction continues past the end of the chunk, at least this many more bytes are
+    /// needed.
+    NeedMoreBytes(usize),
+}
+
+/// Incremental decoder, for code which arrives in chunks.
+///
+/// An instruction which straddles the end of a chunk is kept, and resumed with the next chunk.
+pub struct Decoder {
+    ip: usize,
+    mode: Mode,
+    bytes: [u8; 15],
+    len: usize,
+}
+
+impl Decoder {
+    /// Decode in `mode`, starting at `ip`.
+    #[inline]
+    pub const fn new(ip: usize, mode: Mode) -> Self {
+        Self {
+            ip,
+            mode,
+            bytes: [0; 15],
+            len: 0,
+        }
+    }
+
+    /// Returns the instruction pointer of the next instruction.
+    #[inline]
+    pub const fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the number of bytes kept of an incomplete instruction.
+    #[inline]
+    pub const fn pending(&self) -> usize {
+        self.len
+    }
+
+    /// Decode the next instruction from `chunk`, following any bytes kept from the previous chunk,
+    /// returning it along with the number of bytes of `chunk` consumed.
+    ///
+    /// If the instruction is incomplete, the rest of `chunk` is consumed and kept.
+    #[inline]
+    pub fn decode(&mut self, chunk: &[u8]) -> Result<(Decoded, usize), DecodeError> {
+        let kept = self.len;
+        let taken = chunk.len().min(self.bytes.len() - kept);
+        let mut bytes = self.bytes;
+
+        bytes[kept..kept + taken].copy_from_slice(&chunk[..taken]);
+
+        let available = kept + taken;
+
+        match Inst::decode(&bytes[..available], self.mode) {
+            Ok((inst, len)) => {
+                let ip = self.ip;
+
+                self.ip = self.ip.wrapping_add(len);
+                self.len = 0;
+
+                let inst = WithIp::decoded(ip, inst, &bytes[..len], self.mode);
+
+                Ok((Decoded::Inst(inst), len.saturating_sub(kept)))
+            }
+            Err(DecodeError::TruncatedInstruction { needed }) => {
+                self.bytes = bytes;
+                self.len = available;
+
+                Ok((
+                    Decoded::NeedMoreBytes(needed.saturating_sub(available)),
+                    taken,
+                ))
+            }
+            Err(error) => Err(error),
         }
     }
 }
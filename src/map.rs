@@ -0,0 +1,142 @@
+// ty http://ref.x86asm.net/coder64.html#x0F38
+
+use crate::prefix::Prefixes;
+use crate::vex::{PP_66, PP_F2, PP_F3, PP_NONE};
+use crate::{Arg, Inst, Size};
+
+/// Second byte of the `0F 38` opcode map.
+pub const MAP_0F38: u8 = 0x38;
+
+/// Second byte of the `0F 3A` opcode map.
+pub const MAP_0F3A: u8 = 0x3A;
+
+/// An operation within a three-byte opcode map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    Movbe,
+    Wrss,
+    Wruss,
+}
+
+/// The operands of an entry, in order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Form {
+    /// `reg, m`.
+    RegMem,
+    /// `m, reg`.
+    MemReg,
+}
+
+/// An instruction within a three-byte opcode map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Entry {
+    /// Second byte of the opcode, [`MAP_0F38`] or [`MAP_0F3A`].
+    pub map: u8,
+    /// Last byte of the opcode.
+    pub opcode: u8,
+    /// Mandatory prefix, as for VEX.
+    pub pp: u8,
+    /// The operand size is selected by REX.W alone.
+    pub rexw: bool,
+    /// Operands.
+    pub form: Form,
+    /// Operation.
+    pub op: Op,
+}
+
+/// Create an entry.
+#[inline]
+const fn entry(map: u8, opcode: u8, pp: u8, rexw: bool, form: Form, op: Op) -> Entry {
+    Entry {
+        map,
+        opcode,
+        pp,
+        rexw,
+        form,
+        op,
+    }
+}
+
+/// All supported instructions within the three-byte opcode maps.
+const ENTRIES: [Entry; 4] = [
+    entry(MAP_0F38, 0xF0, PP_NONE, false, Form::RegMem, Op::Movbe),
+    entry(MAP_0F38, 0xF1, PP_NONE, false, Form::MemReg, Op::Movbe),
+    entry(MAP_0F38, 0xF5, PP_66, true, Form::MemReg, Op::Wruss),
+    entry(MAP_0F38, 0xF6, PP_NONE, true, Form::MemReg, Op::Wrss),
+];
+
+impl Entry {
+    /// Find the entry for `opcode` in `map`, given the legacy prefixes present.
+    ///
+    /// `66` is an operand-size override unless an entry requires it.
+    #[inline]
+    pub const fn lookup(map: u8, opcode: u8, prefixes: Prefixes) -> Option<Self> {
+        let pp = if prefixes.repne {
+            PP_F2
+        } else if prefixes.rep {
+            PP_F3
+        } else if prefixes.operand_size {
+            PP_66
+        } else {
+            PP_NONE
+        };
+
+        match Self::find(map, opcode, pp) {
+            Some(entry) => Some(entry),
+            None if pp == PP_66 => match Self::find(map, opcode, PP_NONE) {
+                Some(entry) if !entry.rexw => Some(entry),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Find the entry for `opcode` in `map` with the mandatory prefix `pp`.
+    #[inline]
+    const fn find(map: u8, opcode: u8, pp: u8) -> Option<Self> {
+        let mut index = 0;
+
+        while index < ENTRIES.len() {
+            let entry = ENTRIES[index];
+
+            if entry.map == map && entry.opcode == opcode && entry.pp == pp {
+                return Some(entry);
+            }
+
+            index += 1;
+        }
+
+        None
+    }
+
+    /// Find the entry encoding `op` in `form`.
+    #[inline]
+    pub const fn of(op: Op, form: Form) -> Option<Self> {
+        let mut index = 0;
+
+        while index < ENTRIES.len() {
+            let entry = ENTRIES[index];
+
+            if entry.op as u8 == op as u8 && entry.form as u8 == form as u8 {
+                return Some(entry);
+            }
+
+            index += 1;
+        }
+
+        None
+    }
+
+    /// Construct the instruction from its operands.
+    #[inline]
+    pub const fn inst(self, size: Size, dst: Arg, src: Arg) -> Option<Inst> {
+        let inst = match (self.op, dst, src) {
+            (Op::Movbe, dst, src) => Inst::Movbe(size, dst, src),
+            (Op::Wrss, dst, Arg::Reg(reg)) => Inst::Wrss(size, dst, reg),
+            (Op::Wruss, dst, Arg::Reg(reg)) => Inst::Wruss(size, dst, reg),
+            _ => return None,
+        };
+
+        Some(inst)
+    }
+}
@@ -0,0 +1,217 @@
+use crate::modrm::Rm;
+use crate::{Reg, RegSet, Seg, Size};
+use core::fmt;
+
+/// Base of a memory operand.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Base {
+    Reg(Reg),
+    Rip,
+}
+
+/// A memory operand, `seg:[base + index * scale + disp]`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Mem {
+    /// Segment override.
+    pub seg: Option<Seg>,
+    /// Base register, or `rip`.
+    pub base: Option<Base>,
+    /// Index register.
+    pub index: Option<Reg>,
+    /// Scale applied to the index (1, 2, 4 or 8).
+    pub scale: u8,
+    /// Displacement.
+    pub disp: i32,
+    /// Locked (`lock` prefix), the read-modify-write of this operand is atomic.
+    pub lock: bool,
+    /// Address size, the address is computed from the low `addr_size` bits of `base` and `index`,
+    /// and truncated to the same size.
+    pub addr_size: Size,
+}
+
+impl Mem {
+    /// Construct a new memory operand.
+    #[inline]
+    pub const fn new(base: Option<Base>, index: Option<Reg>, scale: u8, disp: i32) -> Self {
+        Self {
+            seg: None,
+            base,
+            index,
+            scale,
+            disp,
+            lock: false,
+            addr_size: Size::Qword,
+        }
+    }
+
+    /// Construct `[base + disp]`.
+    #[inline]
+    pub const fn base(base: Reg, disp: i32) -> Self {
+        Self::new(Some(Base::Reg(base)), None, 1, disp)
+    }
+
+    /// Construct `[rip + disp]`.
+    #[inline]
+    pub const fn rip(disp: i32) -> Self {
+        Self::new(Some(Base::Rip), None, 1, disp)
+    }
+
+    /// Returns this operand with the segment override `seg`.
+    #[inline]
+    pub const fn with_seg(mut self, seg: Seg) -> Self {
+        self.seg = Some(seg);
+        self
+    }
+
+    /// Returns this operand, locked.
+    #[inline]
+    pub const fn with_lock(mut self) -> Self {
+        self.lock = true;
+        self
+    }
+
+    /// Returns this operand with the address size `addr_size`.
+    #[inline]
+    pub const fn with_addr_size(mut self, addr_size: Size) -> Self {
+        self.addr_size = addr_size;
+        self
+    }
+
+    /// Returns `true` if this operand is relative to the instruction pointer.
+    #[inline]
+    pub const fn is_rip(self) -> bool {
+        matches!(self.base, Some(Base::Rip))
+    }
+
+    /// Returns the registers used to compute the address.
+    #[inline]
+    pub const fn regs(self) -> RegSet {
+        let set = match self.base {
+            Some(Base::Reg(base)) => RegSet::of(base),
+            _ => RegSet::EMPTY,
+        };
+
+        match self.index {
+            Some(index) => set.with(index),
+            None => set,
+        }
+    }
+
+    /// Convert a memory `r/m` operand.
+    #[inline]
+    pub(crate) const fn from_rm(rm: Rm) -> Option<Self> {
+        let mem = match rm {
+            Rm::Reg(_) => return None,
+            Rm::Rip(disp) => Self::rip(disp),
+            Rm::Mem {
+                base,
+                index,
+                scale,
+                disp,
+            } => {
+                let base = match base {
+                    Some(base) => Some(Base::Reg(tri!(Reg::from_bits(base)))),
+                    None => None,
+                };
+
+                let index = match index {
+                    Some(index) => Some(tri!(Reg::from_bits(index))),
+                    None => None,
+                };
+
+                Self::new(base, index, scale, disp)
+            }
+        };
+
+        Some(mem)
+    }
+
+    /// Convert to a memory `r/m` operand.
+    #[inline]
+    pub(crate) const fn to_rm(self) -> Option<Rm> {
+        let index = match self.index {
+            // rsp cannot be used as an index
+            Some(Reg::Rsp) => return None,
+            Some(index) => Some(index.bits()),
+            None => None,
+        };
+
+        let rm = match self.base {
+            Some(Base::Rip) => match index {
+                Some(_) => return None,
+                None => Rm::Rip(self.disp),
+            },
+            Some(Base::Reg(base)) => Rm::Mem {
+                base: Some(base.bits()),
+                index,
+                scale: self.scale,
+                disp: self.disp,
+            },
+            None => Rm::Mem {
+                base: None,
+                index,
+                scale: self.scale,
+                disp: self.disp,
+            },
+        };
+
+        Some(rm)
+    }
+
+    /// Returns the name of `reg` at the address size of this operand.
+    #[inline]
+    const fn reg_name(self, reg: Reg) -> &'static str {
+        match self.addr_size {
+            Size::Word => reg.name16(),
+            Size::Dword => reg.name32(),
+            _ => reg.name(),
+        }
+    }
+}
+
+impl fmt::Display for Mem {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(seg) = self.seg {
+            write!(fmt, "{}:", seg.name())?;
+        }
+
+        fmt.write_str("[")?;
+
+        let mut empty = true;
+
+        match self.base {
+            Some(Base::Reg(base)) => {
+                fmt.write_str(self.reg_name(base))?;
+                empty = false;
+            }
+            Some(Base::Rip) => {
+                fmt.write_str(if matches!(self.addr_size, Size::Qword) {
+                    "rip"
+                } else {
+                    "eip"
+                })?;
+                empty = false;
+            }
+            None => {}
+        }
+
+        if let Some(index) = self.index {
+            if !empty {
+                fmt.write_str("+")?;
+            }
+
+            write!(fmt, "{}*{}", self.reg_name(index), self.scale)?;
+            empty = false;
+        }
+
+        if empty {
+            write!(fmt, "{:#x}", self.disp as u32)?;
+        } else if self.disp < 0 {
+            write!(fmt, "-{:#x}", self.disp.unsigned_abs())?;
+        } else if self.disp > 0 {
+            write!(fmt, "+{:#x}", self.disp)?;
+        }
+
+        fmt.write_str("]")
+    }
+}
@@ -0,0 +1,51 @@
+use crate::Mem;
+
+/// An access to the memory operand of an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemAccess {
+    /// The memory operand.
+    pub mem: Mem,
+    /// The memory is read.
+    pub read: bool,
+    /// The memory is written.
+    pub write: bool,
+    /// Size of the access in bytes, if it is known.
+    pub size: Option<usize>,
+}
+
+impl MemAccess {
+    /// An access which neither reads nor writes, as by a prefetch or cache line flush.
+    #[inline]
+    pub const fn none(mem: Mem) -> Self {
+        Self::new(mem, false, false, None)
+    }
+
+    /// A read of `size` bytes.
+    #[inline]
+    pub const fn read(mem: Mem, size: usize) -> Self {
+        Self::new(mem, true, false, Some(size))
+    }
+
+    /// A write of `size` bytes.
+    #[inline]
+    pub const fn write(mem: Mem, size: usize) -> Self {
+        Self::new(mem, false, true, Some(size))
+    }
+
+    /// A read, then write of `size` bytes.
+    #[inline]
+    pub const fn read_write(mem: Mem, size: usize) -> Self {
+        Self::new(mem, true, true, Some(size))
+    }
+
+    /// Construct an access.
+    #[inline]
+    pub const fn new(mem: Mem, read: bool, write: bool, size: Option<usize>) -> Self {
+        Self {
+            mem,
+            read,
+            write,
+            size,
+        }
+    }
+}
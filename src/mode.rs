@@ -0,0 +1,60 @@
+use crate::Size;
+
+/// A processor mode, determining default operand and address sizes.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Mode {
+    /// 64-bit long mode.
+    Long64,
+    /// 32-bit protected (or compatibility) mode, without REX prefixes.
+    Compat32,
+    /// 16-bit real mode.
+    Real16,
+}
+
+impl Mode {
+    /// Returns the default address size, also the size of the stack and of indirect branch targets.
+    #[inline]
+    pub const fn addr_size(self) -> Size {
+        match self {
+            Mode::Long64 => Size::Qword,
+            Mode::Compat32 => Size::Dword,
+            Mode::Real16 => Size::Word,
+        }
+    }
+
+    /// Returns the default operand size.
+    #[inline]
+    pub const fn default_size(self) -> Size {
+        match self {
+            Mode::Long64 | Mode::Compat32 => Size::Dword,
+            Mode::Real16 => Size::Word,
+        }
+    }
+
+    /// Returns `true` if an operand-size prefix (`66`) is required for `size`.
+    #[inline]
+    pub const fn needs_size_prefix(self, size: Size) -> bool {
+        match size {
+            Size::Word | Size::Dword => !matches!(
+                (self.default_size(), size),
+                (Size::Word, Size::Word) | (Size::Dword, Size::Dword)
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if an address-size prefix (`67`) is required for addresses of `size`.
+    #[inline]
+    pub const fn needs_addr_prefix(self, size: Size) -> bool {
+        !matches!(
+            (self.addr_size(), size),
+            (Size::Word, Size::Word) | (Size::Dword, Size::Dword) | (Size::Qword, Size::Qword)
+        )
+    }
+
+    /// Returns `true` if REX prefixes are available.
+    #[inline]
+    pub const fn has_rex(self) -> bool {
+        matches!(self, Mode::Long64)
+    }
+}
@@ -0,0 +1,325 @@
+// ty https://wiki.osdev.org/X86-64_Instruction_Encoding#ModR.2FM_and_SIB_bytes
+
+use crate::prefix::Prefixes;
+use crate::rex::Rex;
+use crate::{Mode, Size};
+
+const MOD_SHIFT: u8 = 6;
+const REG_SHIFT: u8 = 3;
+const FIELD_MASK: u8 = 0b111;
+
+pub const MOD_INDIRECT: u8 = 0b00;
+pub const MOD_DISP8: u8 = 0b01;
+pub const MOD_DISP32: u8 = 0b10;
+pub const MOD_DIRECT: u8 = 0b11;
+
+/// `rm` value selecting a SIB byte.
+pub const RM_SIB: u8 = 0b100;
+
+/// `rm` value selecting `[rip+disp32]` (with mod = 00).
+pub const RM_RIP: u8 = 0b101;
+
+/// SIB `index` value meaning no index.
+pub const SIB_NO_INDEX: u8 = 0b100;
+
+/// SIB `base` value meaning no base (with mod = 00).
+pub const SIB_NO_BASE: u8 = 0b101;
+
+/// 16-bit `rm` value selecting `[bp]`, or `[disp16]` (with mod = 00).
+pub const RM16_BP: u8 = 0b110;
+
+const BX: u8 = 3;
+const BP: u8 = 5;
+const SI: u8 = 6;
+const DI: u8 = 7;
+
+/// Base and index registers of each 16-bit `rm` value.
+const RM16: [(u8, Option<u8>); 8] = [
+    (BX, Some(SI)),
+    (BX, Some(DI)),
+    (BP, Some(SI)),
+    (BP, Some(DI)),
+    (SI, None),
+    (DI, None),
+    (BP, None),
+    (BX, None),
+];
+
+/// The `r/m` operand of a ModRM byte.
+///
+/// Register numbers are 4-bit, including any REX extension.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rm {
+    /// Register direct, `mod = 11`.
+    Reg(u8),
+    /// `[base + index * scale + disp]`.
+    Mem {
+        base: Option<u8>,
+        index: Option<u8>,
+        scale: u8,
+        disp: i32,
+    },
+    /// `[rip + disp32]`.
+    Rip(i32),
+}
+
+/// A decoded ModRM byte, along with its SIB byte and displacement.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ModRm {
+    /// The `reg` field, either a register or an opcode extension (`/digit`).
+    pub reg: u8,
+    /// The `r/m` operand.
+    pub rm: Rm,
+    /// Number of bytes consumed (ModRM, SIB and displacement).
+    pub len: usize,
+}
+
+impl ModRm {
+    /// Decode a ModRM byte, and any SIB byte and displacement following it.
+    #[inline]
+    pub const fn from_bytes(bytes: &[u8], prefixes: Prefixes) -> Option<Self> {
+        let [modrm, rest @ ..] = bytes else {
+            return None;
+        };
+
+        if matches!(prefixes.addr_size(), Size::Word) {
+            return Self::from_bytes16(*modrm, rest);
+        }
+
+        let rex = prefixes.rex;
+
+        let modrm = *modrm;
+        let mode = modrm >> MOD_SHIFT;
+        let reg = ((modrm >> REG_SHIFT) & FIELD_MASK) | rex.r();
+        let rm = modrm & FIELD_MASK;
+
+        if mode == MOD_DIRECT {
+            return Some(Self {
+                reg,
+                rm: Rm::Reg(rm | rex.b()),
+                len: 1,
+            });
+        }
+
+        if mode == MOD_INDIRECT && rm == RM_RIP {
+            let disp = match read_i32(rest) {
+                Some(disp) => disp,
+                None => return None,
+            };
+
+            // an absolute address outside of long mode
+            let rm = match prefixes.mode {
+                Mode::Long64 => Rm::Rip(disp),
+                _ => Rm::Mem {
+                    base: None,
+                    index: None,
+                    scale: 1,
+                    disp,
+                },
+            };
+
+            return Some(Self { reg, rm, len: 5 });
+        }
+
+        let (base, index, scale, rest, sib_len) = if rm == RM_SIB {
+            let [sib, rest @ ..] = rest else {
+                return None;
+            };
+
+            let sib = *sib;
+            let scale = 1 << (sib >> MOD_SHIFT);
+            let index = (sib >> REG_SHIFT) & FIELD_MASK;
+            let base = sib & FIELD_MASK;
+
+            // r12 is a valid index, only rsp is not
+            let index = if index == SIB_NO_INDEX && rex.x() == 0 {
+                None
+            } else {
+                Some(index | rex.x())
+            };
+
+            let base = if mode == MOD_INDIRECT && base == SIB_NO_BASE {
+                None
+            } else {
+                Some(base | rex.b())
+            };
+
+            (base, index, scale, rest, 1)
+        } else {
+            (Some(rm | rex.b()), None, 1, rest, 0)
+        };
+
+        let (disp, disp_len) = match mode {
+            MOD_DISP8 => match rest {
+                [disp, ..] => (*disp as i8 as i32, 1),
+                _ => return None,
+            },
+            MOD_DISP32 => match read_i32(rest) {
+                Some(disp) => (disp, 4),
+                None => return None,
+            },
+            // no base implies a disp32
+            _ if base.is_none() => match read_i32(rest) {
+                Some(disp) => (disp, 4),
+                None => return None,
+            },
+            _ => (0, 0),
+        };
+
+        Some(Self {
+            reg,
+            rm: Rm::Mem {
+                base,
+                index,
+                scale,
+                disp,
+            },
+            len: 1 + sib_len + disp_len,
+        })
+    }
+
+    /// Decode a ModRM byte with 16-bit addressing, and any displacement following it.
+    #[inline]
+    const fn from_bytes16(modrm: u8, rest: &[u8]) -> Option<Self> {
+        let mode = modrm >> MOD_SHIFT;
+        let reg = (modrm >> REG_SHIFT) & FIELD_MASK;
+        let rm = modrm & FIELD_MASK;
+
+        if mode == MOD_DIRECT {
+            return Some(Self {
+                reg,
+                rm: Rm::Reg(rm),
+                len: 1,
+            });
+        }
+
+        let (base, index) = RM16[rm as usize];
+
+        let (base, disp, disp_len) = match (mode, rest) {
+            // no base implies a disp16
+            (MOD_INDIRECT, [a, b, ..]) if rm == RM16_BP => {
+                (None, i16::from_le_bytes([*a, *b]) as i32, 2)
+            }
+            (MOD_INDIRECT, _) if rm == RM16_BP => return None,
+            (MOD_INDIRECT, _) => (Some(base), 0, 0),
+            (MOD_DISP8, [disp, ..]) => (Some(base), *disp as i8 as i32, 1),
+            (MOD_DISP32, [a, b, ..]) => (Some(base), i16::from_le_bytes([*a, *b]) as i32, 2),
+            _ => return None,
+        };
+
+        Some(Self {
+            reg,
+            rm: Rm::Mem {
+                base,
+                index,
+                scale: 1,
+                disp,
+            },
+            len: 1 + disp_len,
+        })
+    }
+}
+
+impl Rm {
+    /// Returns the REX prefix required to encode this operand alongside `reg`.
+    #[inline]
+    pub const fn rex(self, w: bool, reg: u8) -> Rex {
+        match self {
+            Rm::Reg(rm) => Rex::new(w, reg, 0, rm),
+            Rm::Mem { base, index, .. } => {
+                let base = match base {
+                    Some(base) => base,
+                    None => 0,
+                };
+
+                let index = match index {
+                    Some(index) => index,
+                    None => 0,
+                };
+
+                Rex::new(w, reg, index, base)
+            }
+            Rm::Rip(_) => Rex::new(w, reg, 0, 0),
+        }
+    }
+}
+
+#[inline]
+const fn read_i32(bytes: &[u8]) -> Option<i32> {
+    match bytes {
+        [a, b, c, d, ..] => Some(i32::from_le_bytes([*a, *b, *c, *d])),
+        _ => None,
+    }
+}
+
+/// Construct a ModRM byte.
+#[inline]
+pub const fn modrm(mode: u8, reg: u8, rm: u8) -> u8 {
+    (mode << MOD_SHIFT) | ((reg & FIELD_MASK) << REG_SHIFT) | (rm & FIELD_MASK)
+}
+
+/// Construct a SIB byte.
+#[inline]
+pub const fn sib(scale: u8, index: u8, base: u8) -> u8 {
+    let scale = match scale {
+        1 => 0b00,
+        2 => 0b01,
+        4 => 0b10,
+        _ => 0b11,
+    };
+
+    (scale << MOD_SHIFT) | ((index & FIELD_MASK) << REG_SHIFT) | (base & FIELD_MASK)
+}
+
+/// Returns the mode required to encode `disp` against `base`.
+///
+/// `rbp`/`r13` cannot be encoded without a displacement, as that slot is taken by RIP-relative (or
+/// no base) addressing.
+#[inline]
+pub const fn disp_mode(base: u8, disp: i32) -> u8 {
+    if disp == 0 && base & FIELD_MASK != RM_RIP {
+        MOD_INDIRECT
+    } else if disp as i8 as i32 == disp {
+        MOD_DISP8
+    } else {
+        MOD_DISP32
+    }
+}
+
+/// Returns the 16-bit `rm` value selecting `base` and `index`, if encodable.
+#[inline]
+pub const fn rm16(base: u8, index: Option<u8>) -> Option<u8> {
+    let mut rm = 0;
+
+    while rm < RM16.len() {
+        let (rm_base, rm_index) = RM16[rm];
+
+        if rm_base == base
+            && match (rm_index, index) {
+                (Some(rm_index), Some(index)) => rm_index == index,
+                (None, None) => true,
+                _ => false,
+            }
+        {
+            return Some(rm as u8);
+        }
+
+        rm += 1;
+    }
+
+    None
+}
+
+/// Returns the mode required to encode `disp` against the 16-bit `rm`.
+///
+/// `[bp]` cannot be encoded without a displacement, as that slot is taken by `[disp16]`.
+#[inline]
+pub const fn disp_mode16(rm: u8, disp: i32) -> u8 {
+    if disp == 0 && rm != RM16_BP {
+        MOD_INDIRECT
+    } else if disp as i8 as i32 == disp {
+        MOD_DISP8
+    } else {
+        MOD_DISP32
+    }
+}
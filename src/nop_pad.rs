@@ -0,0 +1,59 @@
+use crate::{Inst, Mode};
+use core::iter;
+
+/// Length of the longest recommended NOP in `mode`.
+///
+/// NOPs of 5 bytes or more have a SIB byte, which does not exist with 16-bit addressing.
+#[inline]
+pub const fn max_len(mode: Mode) -> usize {
+    match mode {
+        Mode::Real16 => 4,
+        _ => 9,
+    }
+}
+
+/// Iterator of the recommended multi-byte NOPs filling a number of bytes, see [`Inst::nop_pad`].
+#[derive(Clone, Debug)]
+pub struct NopPad {
+    remaining: usize,
+    max_len: usize,
+}
+
+impl NopPad {
+    /// Fill `len` bytes in `mode`.
+    #[inline]
+    pub const fn new(len: usize, mode: Mode) -> Self {
+        Self {
+            remaining: len,
+            max_len: max_len(mode),
+        }
+    }
+}
+
+impl Iterator for NopPad {
+    type Item = Inst;
+
+    #[inline]
+    fn next(&mut self) -> Option<Inst> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let len = self.remaining.min(self.max_len);
+
+        self.remaining -= len;
+
+        Some(Inst::Nop(len as u8))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining.div_ceil(self.max_len);
+
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for NopPad {}
+
+impl iter::FusedIterator for NopPad {}
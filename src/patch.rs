@@ -0,0 +1,123 @@
+use crate::{Guard, HotPatch, PatchError};
+
+/// A replacement of the bytes at an address, recording the original bytes so it can be reverted.
+///
+/// Both are stored in inline buffers of `N` bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Patch<const N: usize = 16> {
+    addr: usize,
+    original: [u8; N],
+    replacement: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Patch<N> {
+    /// Replace `original`, the bytes at `addr`, with `replacement`.
+    ///
+    /// Fails if they differ in length, or do not fit.
+    #[inline]
+    pub fn new(addr: usize, original: &[u8], replacement: &[u8]) -> Result<Self, PatchError> {
+        let len = original.len();
+
+        if replacement.len() != len {
+            return Err(PatchError::LenMismatch);
+        }
+
+        if len > N {
+            return Err(PatchError::BufferTooSmall { needed: len });
+        }
+
+        let mut patch = Self {
+            addr,
+            original: [0; N],
+            replacement: [0; N],
+            len,
+        };
+
+        patch.original[..len].copy_from_slice(original);
+        patch.replacement[..len].copy_from_slice(replacement);
+
+        Ok(patch)
+    }
+
+    /// Returns the address patched.
+    #[inline]
+    pub const fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Returns the number of bytes patched.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the original bytes.
+    #[inline]
+    pub const fn original(&self) -> &[u8] {
+        self.original.split_at(self.len).0
+    }
+
+    /// Returns the replacement bytes.
+    #[inline]
+    pub const fn replacement(&self) -> &[u8] {
+        self.replacement.split_at(self.len).0
+    }
+
+    /// Returns `true` if `region`, the bytes at the patched address, hold the replacement.
+    #[inline]
+    pub fn is_applied(&self, region: &[u8]) -> bool {
+        region.starts_with(self.replacement())
+    }
+
+    /// Write the replacement to `region`, the bytes at the patched address.
+    ///
+    /// Nothing is written unless `region` still holds the original bytes.
+    #[inline]
+    pub fn apply(&self, region: &mut [u8]) -> Result<(), PatchError> {
+        swap(region, self.original(), self.replacement())
+    }
+
+    /// Returns the writes applying the patch to a running process, guarded by `guard`.
+    ///
+    /// The region is not checked to hold the original bytes.
+    #[inline]
+    pub const fn hot_apply(&self, guard: Guard) -> HotPatch<'_> {
+        HotPatch::new(self.addr, self.replacement(), guard)
+    }
+
+    /// Returns the writes reverting the patch in a running process, guarded by `guard`.
+    ///
+    /// The region is not checked to hold the replacement.
+    #[inline]
+    pub const fn hot_revert(&self, guard: Guard) -> HotPatch<'_> {
+        HotPatch::new(self.addr, self.original(), guard)
+    }
+
+    /// Write the original bytes back to `region`, the bytes at the patched address.
+    ///
+    /// Nothing is written unless `region` still holds the replacement.
+    #[inline]
+    pub fn revert(&self, region: &mut [u8]) -> Result<(), PatchError> {
+        swap(region, self.replacement(), self.original())
+    }
+}
+
+/// Overwrite `expected`, the start of `region`, with `bytes`.
+#[inline]
+fn swap(region: &mut [u8], expected: &[u8], bytes: &[u8]) -> Result<(), PatchError> {
+    let Some(region) = region.get_mut(..bytes.len()) else {
+        return Err(PatchError::Truncated {
+            needed: bytes.len(),
+        });
+    };
+
+    if region != expected {
+        return Err(PatchError::Mismatch);
+    }
+
+    region.copy_from_slice(bytes);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::rex::Rex;
+use crate::seg::Seg;
+use crate::{Mode, Size};
+
+const LOCK: u8 = 0xF0;
+const OPERAND_SIZE: u8 = 0x66;
+const ADDRESS_SIZE: u8 = 0x67;
+const REPNE: u8 = 0xF2;
+const REP: u8 = 0xF3;
+
+/// Prefixes of an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Prefixes {
+    /// `lock`.
+    pub lock: bool,
+    /// Segment override, the last one wins.
+    pub seg: Option<Seg>,
+    /// Operand-size override (`66`), selecting 16-bit operands.
+    pub operand_size: bool,
+    /// Address-size override (`67`), selecting 32-bit addressing.
+    pub address_size: bool,
+    /// `repne` (`F2`), or a mandatory prefix.
+    pub repne: bool,
+    /// `rep` (`F3`), or a mandatory prefix.
+    pub rep: bool,
+    /// REX, always immediately preceding the opcode.
+    pub rex: Rex,
+    /// Mode the prefixes were decoded in.
+    pub mode: Mode,
+}
+
+impl Prefixes {
+    /// No prefixes, in `mode`.
+    #[inline]
+    pub const fn none(mode: Mode) -> Self {
+        Self {
+            lock: false,
+            seg: None,
+            operand_size: false,
+            address_size: false,
+            repne: false,
+            rep: false,
+            rex: Rex::NONE,
+            mode,
+        }
+    }
+
+    /// Returns the operand size, without REX.W, taking into account any operand-size override.
+    #[inline]
+    pub const fn operand_size(self) -> Size {
+        match (self.mode.default_size(), self.operand_size) {
+            (Size::Word, true) => Size::Dword,
+            (_, true) => Size::Word,
+            (size, false) => size,
+        }
+    }
+
+    /// Returns the address size, taking into account any address-size override.
+    #[inline]
+    pub const fn addr_size(self) -> Size {
+        match (self.mode, self.address_size) {
+            (Mode::Long64, false) => Size::Qword,
+            (Mode::Long64 | Mode::Real16, true) | (Mode::Compat32, false) => Size::Dword,
+            (Mode::Compat32, true) | (Mode::Real16, false) => Size::Word,
+        }
+    }
+
+    /// Parse the prefixes at the start of `bytes` in `mode`, returning them along with the remaining
+    /// bytes.
+    #[inline]
+    pub const fn from_bytes(mut bytes: &[u8], mode: Mode) -> (Self, &[u8]) {
+        let mut lock = false;
+        let mut seg = None;
+        let mut operand_size = false;
+        let mut address_size = false;
+        let mut repne = false;
+        let mut rep = false;
+
+        while let [prefix, rest @ ..] = bytes {
+            match *prefix {
+                LOCK => lock = true,
+                OPERAND_SIZE => operand_size = true,
+                ADDRESS_SIZE => address_size = true,
+                REPNE => repne = true,
+                REP => rep = true,
+                prefix => match Seg::from_prefix(prefix) {
+                    Some(prefix) => seg = Some(prefix),
+                    None => break,
+                },
+            }
+
+            bytes = rest;
+        }
+
+        let (rex, bytes) = match bytes {
+            // 40 to 4F are inc/dec outside of long mode
+            _ if !mode.has_rex() => (Rex::NONE, bytes),
+            [byte, rest @ ..] => match Rex::from_byte(*byte) {
+                Some(rex) => (rex, rest),
+                None => (Rex::NONE, bytes),
+            },
+            [] => (Rex::NONE, bytes),
+        };
+
+        (
+            Self {
+                lock,
+                seg,
+                operand_size,
+                address_size,
+                repne,
+                rep,
+                rex,
+                mode,
+            },
+            bytes,
+        )
+    }
+}
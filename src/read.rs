@@ -0,0 +1,111 @@
+use crate::{DecodeError, Decoded, Decoder, Mode, WithIp};
+use std::io::{self, ErrorKind, Read};
+
+/// Size of the internal buffer.
+const BUF_LEN: usize = 4096;
+
+/// Streaming decoder, which reads bytes from `R` as they are needed.
+pub struct ReadDecoder<R> {
+    reader: R,
+    decoder: Decoder,
+    buf: [u8; BUF_LEN],
+    start: usize,
+    end: usize,
+    missing: usize,
+    done: bool,
+}
+
+impl<R: Read> ReadDecoder<R> {
+    /// Decode bytes read from `reader` in `mode`, starting at `ip`.
+    #[inline]
+    pub fn new(ip: usize, reader: R, mode: Mode) -> Self {
+        Self {
+            reader,
+            decoder: Decoder::new(ip, mode),
+            buf: [0; BUF_LEN],
+            start: 0,
+            end: 0,
+            missing: 0,
+            done: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Refill the buffer, returning `false` at the end of the reader.
+    #[inline]
+    fn fill(&mut self) -> io::Result<bool> {
+        loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(len) => {
+                    self.start = 0;
+                    self.end = len;
+
+                    return Ok(len > 0);
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for ReadDecoder<R> {
+    type Item = io::Result<WithIp>;
+
+    #[inline]
+    fn next(&mut self) -> Option<io::Result<WithIp>> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            if self.start == self.end {
+                match self.fill() {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.done = true;
+
+                        // an instruction is cut off by the end of the reader
+                        let pending = self.decoder.pending();
+
+                        if pending > 0 {
+                            let needed = pending + self.missing;
+                            let error = DecodeError::TruncatedInstruction { needed };
+
+                            return Some(Err(io::Error::new(ErrorKind::UnexpectedEof, error)));
+                        }
+
+                        return None;
+                    }
+                    Err(error) => {
+                        self.done = true;
+
+                        return Some(Err(error));
+                    }
+                }
+            }
+
+            match self.decoder.decode(&self.buf[self.start..self.end]) {
+                Ok((Decoded::Inst(inst), len)) => {
+                    self.start += len;
+
+                    return Some(Ok(inst));
+                }
+                Ok((Decoded::NeedMoreBytes(missing), len)) => {
+                    self.start += len;
+                    self.missing = missing;
+                }
+                Err(error) => {
+                    self.done = true;
+
+                    return Some(Err(io::Error::new(ErrorKind::InvalidData, error)));
+                }
+            }
+        }
+    }
+}
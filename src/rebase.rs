@@ -0,0 +1,117 @@
+use crate::trampoline;
+use crate::{Inst, RebaseError, Target, WithIp};
+use alloc::vec::Vec;
+
+/// Re-encode `insts`, a block of instructions in order, to run at `new_base`.
+///
+/// Branches to an instruction of the block keep targeting it, other relative branches and `rip`
+/// relative memory operands keep referencing the same address. A short branch becomes a near
+/// branch where it is out of range, moving the instructions which follow it.
+pub fn rebase(insts: &[WithIp], new_base: usize) -> Result<Vec<u8>, RebaseError> {
+    let Some((first, last)) = insts.first().zip(insts.last()) else {
+        return Ok(Vec::new());
+    };
+
+    let block = first.ip()..last.next_ip();
+
+    // the index of the instruction targeted within the block, or the address outside of it
+    let mut targets = Vec::with_capacity(insts.len());
+
+    for inst in insts {
+        let target = match inst.target() {
+            Target::Absolute(target) if block.contains(&target) => {
+                match insts.binary_search_by_key(&target, |inst| inst.ip()) {
+                    Ok(index) => Some(Ok(index)),
+                    Err(_) => return Err(RebaseError::IntoInstruction),
+                }
+            }
+            Target::Absolute(target) => Some(Err(target)),
+            _ => None,
+        };
+
+        targets.push(target);
+    }
+
+    let mut branches: Vec<Option<Inst>> = insts
+        .iter()
+        .zip(&targets)
+        .map(|(inst, target)| target.map(|_| **inst))
+        .collect();
+
+    // branches only grow, until every short branch is in range
+    let offsets = loop {
+        let offsets = offsets(insts, &branches)?;
+        let mut grown = false;
+
+        for (index, target) in targets.iter().enumerate() {
+            let (Some(target), Some(branch)) = (target, branches[index]) else {
+                continue;
+            };
+
+            let here = new_base.wrapping_add(offsets[index]);
+            let target = resolve(*target, &offsets, new_base);
+            let inst = branch.with_target(target, here, first.mode())?;
+
+            if inst.len(first.mode())? != offsets[index + 1] - offsets[index] {
+                grown = true;
+            }
+
+            branches[index] = Some(inst);
+        }
+
+        if !grown {
+            break offsets;
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(offsets[insts.len()]);
+
+    for (index, inst) in insts.iter().enumerate() {
+        let here = new_base.wrapping_add(offsets[index]);
+
+        match (targets[index], branches[index], inst.target()) {
+            (Some(target), Some(branch), _) => {
+                let target = resolve(target, &offsets, new_base);
+                let branch = branch.with_target(target, here, inst.mode())?;
+
+                bytes.extend_from_slice(&branch.to_bytes(inst.mode())?);
+            }
+            (_, _, Target::RipRelativeMem(addr)) => {
+                let relocated = trampoline::rip_relocated(inst, addr, here)?;
+
+                bytes.extend_from_slice(&relocated[..inst.len()]);
+            }
+            _ => bytes.extend_from_slice(inst.bytes()),
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Returns the offset of each instruction, followed by the end.
+fn offsets(insts: &[WithIp], branches: &[Option<Inst>]) -> Result<Vec<usize>, RebaseError> {
+    let mut offsets = Vec::with_capacity(insts.len() + 1);
+    let mut offset = 0;
+
+    for (inst, branch) in insts.iter().zip(branches) {
+        offsets.push(offset);
+
+        offset += match branch {
+            Some(branch) => branch.len(inst.mode())?,
+            None => inst.len(),
+        };
+    }
+
+    offsets.push(offset);
+
+    Ok(offsets)
+}
+
+/// Returns the address of `target`, an instruction of the block or an address outside of it.
+#[inline]
+fn resolve(target: Result<usize, usize>, offsets: &[usize], new_base: usize) -> usize {
+    match target {
+        Ok(index) => new_base.wrapping_add(offsets[index]),
+        Err(addr) => addr,
+    }
+}
@@ -1,20 +1,14 @@
 // ty https://wiki.osdev.org/X86-64_Instruction_Encoding
 
+use crate::{ParseRegError, RegEncodingError, Size, SubReg};
+use core::{fmt, str};
+
 const REG_MASK: u8 = 0b0000_0111;
 
 const HI_BIT: u8 = 0b1000;
 
-const R0: u8 = 0b000;
-const R1: u8 = 0b001;
-const R2: u8 = 0b010;
-const R3: u8 = 0b011;
-const R4: u8 = 0b100;
-const R5: u8 = 0b101;
-const R6: u8 = 0b110;
-const R7: u8 = 0b111;
-
 /// A register.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Reg {
     Rax,
     Rcx,
@@ -35,49 +29,100 @@ pub enum Reg {
 }
 
 impl Reg {
-    /// Create a register from bits.
-    #[inline]
-    pub const fn from_bits(mut bits: u8) -> Option<Self> {
-        let is_lo = bits & HI_BIT != 0;
+    /// All registers, in order of their encoding.
+    pub const ALL: [Reg; 16] = [
+        Reg::Rax,
+        Reg::Rcx,
+        Reg::Rdx,
+        Reg::Rbx,
+        Reg::Rsp,
+        Reg::Rbp,
+        Reg::Rsi,
+        Reg::Rdi,
+        Reg::R8,
+        Reg::R9,
+        Reg::R10,
+        Reg::R11,
+        Reg::R12,
+        Reg::R13,
+        Reg::R14,
+        Reg::R15,
+    ];
 
-        bits &= REG_MASK;
-
-        if is_lo {
-            from_lo(bits)
+    /// Create a register from its 4-bit encoding, extended by REX.
+    ///
+    /// Returns `None` if `encoding` is not `0` to `15`.
+    #[inline]
+    pub const fn from_encoding(encoding: u8) -> Option<Self> {
+        if (encoding as usize) < Self::ALL.len() {
+            Some(Self::ALL[encoding as usize])
         } else {
-            from_hi(bits)
+            None
         }
     }
 
-    /// Create a register from bits, assuming lo.
+    /// Returns the 4-bit encoding of this register, extended by REX.
     #[inline]
-    pub const fn from_lo(mut bits: u8) -> Option<Self> {
-        bits &= REG_MASK;
+    pub const fn encoding(self) -> u8 {
+        self as u8
+    }
 
-        from_lo(bits)
+    /// Returns an iterator over all registers, in order of their encoding.
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = Reg> {
+        Self::ALL.into_iter()
     }
 
-    /// Create a register from bits, assuming hi.
+    /// Create a register from the low 4 bits of `bits`.
     #[inline]
-    pub const fn from_hi(mut bits: u8) -> Option<Self> {
-        bits &= REG_MASK;
+    pub const fn from_bits(bits: u8) -> Option<Self> {
+        Self::from_encoding(bits & (HI_BIT | REG_MASK))
+    }
 
-        from_hi(bits)
+    /// Create a register from bits, assuming lo.
+    #[deprecated(note = "use `Reg::from_encoding`")]
+    #[inline]
+    pub const fn from_lo(bits: u8) -> Option<Self> {
+        Self::from_encoding(bits & REG_MASK)
     }
 
+    /// Create a register from bits, assuming hi.
+    #[deprecated(note = "use `Reg::from_encoding`")]
+    #[inline]
+    pub const fn from_hi(bits: u8) -> Option<Self> {
+        Self::from_encoding(bits & REG_MASK | HI_BIT)
+    }
+
+    /// Create a register from bits.
+    ///
+    /// # Safety
+    ///
+    /// Always safe, `bits` is masked to a register.
     #[inline]
     pub const unsafe fn from_bits_unchecked(bits: u8) -> Self {
         Self::from_bits(bits).unwrap_unchecked()
     }
 
+    /// Create a register from bits, assuming lo.
+    ///
+    /// # Safety
+    ///
+    /// Always safe, `bits` is masked to a register.
+    #[deprecated(note = "use `Reg::from_encoding`")]
     #[inline]
     pub const unsafe fn from_lo_unchecked(bits: u8) -> Self {
-        Self::from_lo(bits).unwrap_unchecked()
+        Self::from_encoding(bits & REG_MASK).unwrap_unchecked()
     }
 
+    /// Create a register from bits, assuming hi.
+    ///
+    /// # Safety
+    ///
+    /// Always safe, `bits` is masked to a register.
+    #[deprecated(note = "use `Reg::from_encoding`")]
     #[inline]
     pub const unsafe fn from_hi_unchecked(bits: u8) -> Self {
-        Self::from_hi(bits).unwrap_unchecked()
+        Self::from_encoding(bits & REG_MASK | HI_BIT).unwrap_unchecked()
     }
 
     #[inline]
@@ -85,97 +130,148 @@ impl Reg {
         !self.is_hi()
     }
 
+    /// Returns `true` if this is `r8` to `r15`, which require REX to encode.
     #[inline]
     pub const fn is_hi(self) -> bool {
-        matches!(
-            self,
-            Reg::R8 | Reg::R9 | Reg::R10 | Reg::R11 | Reg::R12 | Reg::R13 | Reg::R14 | Reg::R15
-        )
+        self.encoding() & HI_BIT != 0
     }
 
+    /// Returns the low 3 bits of the encoding, as encoded in ModRM or an opcode.
     #[inline]
     pub const fn base_bits(self) -> u8 {
-        if self.is_hi() {
-            to_hi(self)
-        } else {
-            to_lo(self)
-        }
+        self.encoding() & REG_MASK
     }
 
+    /// Returns the 4-bit encoding of this register, see [`Reg::encoding`].
     #[inline]
     pub const fn bits(self) -> u8 {
-        if self.is_hi() {
-            to_hi(self) | HI_BIT
-        } else {
-            to_lo(self)
+        self.encoding()
+    }
+
+    /// Returns the low `size` part of this register, such as `eax` of `rax`.
+    #[inline]
+    pub const fn with_size(self, size: Size) -> SubReg {
+        SubReg::new(self, size)
+    }
+
+    /// Returns the name of this register.
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Reg::Rax => "rax",
+            Reg::Rcx => "rcx",
+            Reg::Rdx => "rdx",
+            Reg::Rbx => "rbx",
+            Reg::Rsp => "rsp",
+            Reg::Rbp => "rbp",
+            Reg::Rsi => "rsi",
+            Reg::Rdi => "rdi",
+            Reg::R8 => "r8",
+            Reg::R9 => "r9",
+            Reg::R10 => "r10",
+            Reg::R11 => "r11",
+            Reg::R12 => "r12",
+            Reg::R13 => "r13",
+            Reg::R14 => "r14",
+            Reg::R15 => "r15",
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn name8(self) -> &'static str {
+        match self {
+            Reg::Rax => "al",
+            Reg::Rcx => "cl",
+            Reg::Rdx => "dl",
+            Reg::Rbx => "bl",
+            Reg::Rsp => "spl",
+            Reg::Rbp => "bpl",
+            Reg::Rsi => "sil",
+            Reg::Rdi => "dil",
+            Reg::R8 => "r8b",
+            Reg::R9 => "r9b",
+            Reg::R10 => "r10b",
+            Reg::R11 => "r11b",
+            Reg::R12 => "r12b",
+            Reg::R13 => "r13b",
+            Reg::R14 => "r14b",
+            Reg::R15 => "r15b",
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn name16(self) -> &'static str {
+        match self {
+            Reg::Rax => "ax",
+            Reg::Rcx => "cx",
+            Reg::Rdx => "dx",
+            Reg::Rbx => "bx",
+            Reg::Rsp => "sp",
+            Reg::Rbp => "bp",
+            Reg::Rsi => "si",
+            Reg::Rdi => "di",
+            Reg::R8 => "r8w",
+            Reg::R9 => "r9w",
+            Reg::R10 => "r10w",
+            Reg::R11 => "r11w",
+            Reg::R12 => "r12w",
+            Reg::R13 => "r13w",
+            Reg::R14 => "r14w",
+            Reg::R15 => "r15w",
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn name32(self) -> &'static str {
+        match self {
+            Reg::Rax => "eax",
+            Reg::Rcx => "ecx",
+            Reg::Rdx => "edx",
+            Reg::Rbx => "ebx",
+            Reg::Rsp => "esp",
+            Reg::Rbp => "ebp",
+            Reg::Rsi => "esi",
+            Reg::Rdi => "edi",
+            Reg::R8 => "r8d",
+            Reg::R9 => "r9d",
+            Reg::R10 => "r10d",
+            Reg::R11 => "r11d",
+            Reg::R12 => "r12d",
+            Reg::R13 => "r13d",
+            Reg::R14 => "r14d",
+            Reg::R15 => "r15d",
         }
     }
 }
 
-#[inline]
-const fn from_lo(bits: u8) -> Option<Reg> {
-    let reg = match bits {
-        R0 => Reg::Rax,
-        R1 => Reg::Rcx,
-        R2 => Reg::Rdx,
-        R3 => Reg::Rbx,
-        R4 => Reg::Rsp,
-        R5 => Reg::Rbp,
-        R6 => Reg::Rsi,
-        R7 => Reg::Rdi,
-        _ => return None,
-    };
-
-    Some(reg)
+impl fmt::Display for Reg {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
 }
 
-#[inline]
-const fn from_hi(byte: u8) -> Option<Reg> {
-    let reg = match byte {
-        R0 => Reg::R8,
-        R1 => Reg::R9,
-        R2 => Reg::R10,
-        R3 => Reg::R11,
-        R4 => Reg::R12,
-        R5 => Reg::R13,
-        R6 => Reg::R14,
-        R7 => Reg::R15,
-        _ => return None,
-    };
-
-    Some(reg)
+impl str::FromStr for Reg {
+    type Err = ParseRegError;
+
+    fn from_str(name: &str) -> Result<Self, ParseRegError> {
+        Reg::iter()
+            .find(|reg| reg.name().eq_ignore_ascii_case(name))
+            .ok_or(ParseRegError)
+    }
 }
 
-#[inline]
-const fn to_lo(reg: Reg) -> u8 {
-    let bits = match reg {
-        Reg::Rax => R0,
-        Reg::Rcx => R1,
-        Reg::Rdx => R2,
-        Reg::Rbx => R3,
-        Reg::Rsp => R4,
-        Reg::Rbp => R5,
-        Reg::Rsi => R6,
-        Reg::Rdi => R7,
-        _ => unreachable!(),
-    };
-
-    bits
+impl TryFrom<u8> for Reg {
+    type Error = RegEncodingError;
+
+    #[inline]
+    fn try_from(encoding: u8) -> Result<Self, RegEncodingError> {
+        Reg::from_encoding(encoding).ok_or(RegEncodingError)
+    }
 }
 
-#[inline]
-const fn to_hi(reg: Reg) -> u8 {
-    let bits = match reg {
-        Reg::R8 => R0,
-        Reg::R9 => R1,
-        Reg::R10 => R2,
-        Reg::R11 => R3,
-        Reg::R12 => R4,
-        Reg::R13 => R5,
-        Reg::R14 => R6,
-        Reg::R15 => R7,
-        _ => unreachable!(),
-    };
-
-    bits
+impl From<Reg> for u8 {
+    #[inline]
+    fn from(reg: Reg) -> u8 {
+        reg.encoding()
+    }
 }
@@ -0,0 +1,91 @@
+use crate::Reg;
+
+/// A set of registers.
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct RegSet {
+    bits: u16,
+}
+
+impl RegSet {
+    /// The empty set.
+    pub const EMPTY: Self = Self { bits: 0 };
+
+    /// Construct the set of only `reg`.
+    #[inline]
+    pub const fn of(reg: Reg) -> Self {
+        Self::EMPTY.with(reg)
+    }
+
+    /// Returns this set with `reg` added.
+    #[inline]
+    pub const fn with(self, reg: Reg) -> Self {
+        Self {
+            bits: self.bits | 1 << reg.bits(),
+        }
+    }
+
+    /// Returns this set with `reg` removed.
+    #[inline]
+    pub const fn without(self, reg: Reg) -> Self {
+        Self {
+            bits: self.bits & !(1 << reg.bits()),
+        }
+    }
+
+    /// Returns the registers in either set.
+    #[inline]
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the registers in both sets.
+    #[inline]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self {
+            bits: self.bits & other.bits,
+        }
+    }
+
+    /// Returns `true` if `reg` is in this set.
+    #[inline]
+    pub const fn contains(self, reg: Reg) -> bool {
+        self.bits & 1 << reg.bits() != 0
+    }
+
+    /// Returns `true` if this set is empty.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns the number of registers in this set.
+    #[inline]
+    pub const fn len(self) -> usize {
+        self.bits.count_ones() as usize
+    }
+}
+
+impl Iterator for RegSet {
+    type Item = Reg;
+
+    #[inline]
+    fn next(&mut self) -> Option<Reg> {
+        if self.bits == 0 {
+            return None;
+        }
+
+        let bits = self.bits.trailing_zeros() as u8;
+
+        self.bits &= self.bits - 1;
+
+        Reg::from_encoding(bits)
+    }
+}
+
+impl core::fmt::Debug for RegSet {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.debug_set().entries(*self).finish()
+    }
+}
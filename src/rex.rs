@@ -0,0 +1,122 @@
+// ty https://wiki.osdev.org/X86-64_Instruction_Encoding#REX_prefix
+
+const REX: u8 = 0b0100_0000;
+const REX_MASK: u8 = 0b1111_0000;
+
+const W: u8 = 0b1000;
+const R: u8 = 0b0100;
+const X: u8 = 0b0010;
+const B: u8 = 0b0001;
+
+/// Bit extending a 3-bit register field to 4 bits.
+const EXT: u8 = 0b1000;
+
+/// A REX prefix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rex(u8);
+
+impl Rex {
+    /// An absent REX prefix.
+    pub const NONE: Self = Self(0);
+
+    /// Create a REX prefix from a byte.
+    #[inline]
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        if byte & REX_MASK == REX {
+            Some(Self(byte))
+        } else {
+            None
+        }
+    }
+
+    /// Create a REX prefix from the operand size, and the 4-bit register numbers it extends.
+    #[inline]
+    pub const fn new(w: bool, reg: u8, index: u8, base: u8) -> Self {
+        let mut bits = 0;
+
+        if w {
+            bits |= W;
+        }
+
+        if reg & EXT != 0 {
+            bits |= R;
+        }
+
+        if index & EXT != 0 {
+            bits |= X;
+        }
+
+        if base & EXT != 0 {
+            bits |= B;
+        }
+
+        if bits != 0 {
+            bits |= REX;
+        }
+
+        Self(bits)
+    }
+
+    /// Returns this prefix, present even if no bits are set.
+    ///
+    /// An empty REX prefix selects `spl`, `bpl`, `sil` and `dil` over `ah`, `ch`, `dh` and `bh`.
+    #[inline]
+    pub const fn present(self) -> Self {
+        Self(self.0 | REX)
+    }
+
+    /// Returns `true` if a prefix byte is present.
+    #[inline]
+    pub const fn is_present(self) -> bool {
+        self.0 != 0
+    }
+
+    /// Returns `true` if no bits are set, the prefix only selecting `spl`, `bpl`, `sil` and `dil`
+    /// if present.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 & !REX == 0
+    }
+
+    /// Returns the prefix byte.
+    #[inline]
+    pub const fn byte(self) -> u8 {
+        self.0 | REX
+    }
+
+    /// 64-bit operand size.
+    #[inline]
+    pub const fn w(self) -> bool {
+        self.0 & W != 0
+    }
+
+    /// Extension of ModRM `reg`.
+    #[inline]
+    pub const fn r(self) -> u8 {
+        if self.0 & R != 0 {
+            EXT
+        } else {
+            0
+        }
+    }
+
+    /// Extension of SIB `index`.
+    #[inline]
+    pub const fn x(self) -> u8 {
+        if self.0 & X != 0 {
+            EXT
+        } else {
+            0
+        }
+    }
+
+    /// Extension of ModRM `r/m`, SIB `base`, or an opcode register.
+    #[inline]
+    pub const fn b(self) -> u8 {
+        if self.0 & B != 0 {
+            EXT
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,53 @@
+/// A segment register.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Seg {
+    Es,
+    Cs,
+    Ss,
+    Ds,
+    Fs,
+    Gs,
+}
+
+impl Seg {
+    /// Create a segment from an override prefix.
+    #[inline]
+    pub const fn from_prefix(prefix: u8) -> Option<Self> {
+        let seg = match prefix {
+            0x26 => Seg::Es,
+            0x2E => Seg::Cs,
+            0x36 => Seg::Ss,
+            0x3E => Seg::Ds,
+            0x64 => Seg::Fs,
+            0x65 => Seg::Gs,
+            _ => return None,
+        };
+
+        Some(seg)
+    }
+
+    /// Returns the override prefix for this segment.
+    #[inline]
+    pub const fn prefix(self) -> u8 {
+        match self {
+            Seg::Es => 0x26,
+            Seg::Cs => 0x2E,
+            Seg::Ss => 0x36,
+            Seg::Ds => 0x3E,
+            Seg::Fs => 0x64,
+            Seg::Gs => 0x65,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Seg::Es => "es",
+            Seg::Cs => "cs",
+            Seg::Ss => "ss",
+            Seg::Ds => "ds",
+            Seg::Fs => "fs",
+            Seg::Gs => "gs",
+        }
+    }
+}
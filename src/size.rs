@@ -0,0 +1,27 @@
+/// An operand size.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+}
+
+impl Size {
+    /// Returns the size in bytes.
+    #[inline]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Size::Byte => 1,
+            Size::Word => 2,
+            Size::Dword => 4,
+            Size::Qword => 8,
+        }
+    }
+
+    /// Returns the size in bits.
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bytes() as u32 * 8
+    }
+}
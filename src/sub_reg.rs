@@ -0,0 +1,137 @@
+use crate::{ParseRegError, Reg, Size};
+use core::{fmt, str};
+
+/// A general purpose register of a given width, such as `eax`, `r8w`, `al` or `ah`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SubReg {
+    reg: Reg,
+    size: Size,
+    high: bool,
+}
+
+impl SubReg {
+    /// Construct the low `size` part of `reg`.
+    #[inline]
+    pub const fn new(reg: Reg, size: Size) -> Self {
+        Self {
+            reg,
+            size,
+            high: false,
+        }
+    }
+
+    /// Construct the second byte of `reg`, `ah`, `ch`, `dh` or `bh`.
+    ///
+    /// Returns `None` if `reg` is not `rax`, `rcx`, `rdx` or `rbx`.
+    #[inline]
+    pub const fn high(reg: Reg) -> Option<Self> {
+        if !matches!(reg, Reg::Rax | Reg::Rcx | Reg::Rdx | Reg::Rbx) {
+            return None;
+        }
+
+        Some(Self {
+            reg,
+            size: Size::Byte,
+            high: true,
+        })
+    }
+
+    /// Returns the 64-bit register containing this register.
+    #[inline]
+    pub const fn full_register(self) -> Reg {
+        self.reg
+    }
+
+    /// Returns the size of this register.
+    #[inline]
+    pub const fn size(self) -> Size {
+        self.size
+    }
+
+    /// Returns the width of this register in bits.
+    #[inline]
+    pub const fn width(self) -> u32 {
+        self.size.bits()
+    }
+
+    /// Returns the offset of this register within the full register in bits, `8` for `ah`, `ch`,
+    /// `dh` and `bh`, otherwise `0`.
+    #[inline]
+    pub const fn offset(self) -> u32 {
+        if self.high {
+            8
+        } else {
+            0
+        }
+    }
+
+    /// Returns `true` if this is `ah`, `ch`, `dh` or `bh`.
+    #[inline]
+    pub const fn is_high(self) -> bool {
+        self.high
+    }
+
+    /// Returns `true` if this is the full 64-bit register.
+    #[inline]
+    pub const fn is_full(self) -> bool {
+        matches!(self.size, Size::Qword)
+    }
+
+    /// Returns `true` if a write to this register zero extends into the full register, as for all
+    /// 32-bit registers, otherwise the rest of the full register is kept.
+    #[inline]
+    pub const fn zero_extends(self) -> bool {
+        matches!(self.size, Size::Dword)
+    }
+
+    /// Returns the name of this register.
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        match (self.size, self.high) {
+            (Size::Byte, true) => match self.reg {
+                Reg::Rax => "ah",
+                Reg::Rcx => "ch",
+                Reg::Rdx => "dh",
+                _ => "bh",
+            },
+            (Size::Byte, false) => self.reg.name8(),
+            (Size::Word, _) => self.reg.name16(),
+            (Size::Dword, _) => self.reg.name32(),
+            (Size::Qword, _) => self.reg.name(),
+        }
+    }
+
+    /// Returns `true` if this register shares any bits with `other`.
+    #[inline]
+    pub const fn overlaps(self, other: Self) -> bool {
+        if self.reg as u8 != other.reg as u8 {
+            return false;
+        }
+
+        self.offset() < other.offset() + other.width()
+            && other.offset() < self.offset() + self.width()
+    }
+}
+
+impl fmt::Display for SubReg {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+impl str::FromStr for SubReg {
+    type Err = ParseRegError;
+
+    fn from_str(name: &str) -> Result<Self, ParseRegError> {
+        const SIZES: [Size; 4] = [Size::Byte, Size::Word, Size::Dword, Size::Qword];
+
+        let low = Reg::iter().flat_map(|reg| SIZES.map(|size| SubReg::new(reg, size)));
+        let high = [Reg::Rax, Reg::Rcx, Reg::Rdx, Reg::Rbx]
+            .into_iter()
+            .filter_map(SubReg::high);
+
+        low.chain(high)
+            .find(|reg| reg.name().eq_ignore_ascii_case(name))
+            .ok_or(ParseRegError)
+    }
+}
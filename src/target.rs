@@ -0,0 +1,17 @@
+use crate::{Mem, Reg};
+
+/// Where an instruction transfers control to, or the address it references.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    /// A direct branch to this address.
+    Absolute(usize),
+    /// A memory operand at this `rip` relative address, for an indirect branch this is the
+    /// location of the pointer, not the target.
+    RipRelativeMem(usize),
+    /// An indirect branch through a register.
+    Register(Reg),
+    /// An indirect branch through memory which is not `rip` relative.
+    Memory(Mem),
+    /// Neither a branch nor a `rip` relative memory operand.
+    None,
+}
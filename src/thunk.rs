@@ -0,0 +1,74 @@
+use crate::{Reg, RegSet};
+
+/// How a 64-bit absolute `jmp` or `call` reaches its target, which may be anywhere in the address
+/// space.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Thunk {
+    /// `jmp [rip]` followed by the target, clobbering no register.
+    ///
+    /// A `call` is `call [rip + 2]; jmp +8` followed by the target.
+    Indirect,
+    /// `mov reg, imm64; jmp reg`, clobbering `reg`.
+    Register(Reg),
+}
+
+impl Thunk {
+    /// Returns the shortest thunk which only clobbers registers in `clobber`.
+    ///
+    /// `rsp` is never used.
+    #[inline]
+    pub const fn select(clobber: RegSet) -> Thunk {
+        let clobber = clobber.without(Reg::Rsp);
+        let mut index = 0;
+
+        // registers without REX.B are shorter to jump through
+        while index < Reg::ALL.len() {
+            let reg = Reg::ALL[index];
+
+            if clobber.contains(reg) {
+                return Thunk::Register(reg);
+            }
+
+            index += 1;
+        }
+
+        Thunk::Indirect
+    }
+
+    /// Returns the registers clobbered.
+    #[inline]
+    pub const fn clobbers(self) -> RegSet {
+        match self {
+            Thunk::Indirect => RegSet::EMPTY,
+            Thunk::Register(reg) => RegSet::of(reg),
+        }
+    }
+
+    /// Returns the length of an absolute `jmp`.
+    #[inline]
+    pub const fn jmp_len(self) -> usize {
+        match self {
+            Thunk::Indirect => 6 + 8,
+            Thunk::Register(reg) => 10 + branch_reg_len(reg),
+        }
+    }
+
+    /// Returns the length of an absolute `call`.
+    #[inline]
+    pub const fn call_len(self) -> usize {
+        match self {
+            Thunk::Indirect => 6 + 2 + 8,
+            Thunk::Register(reg) => 10 + branch_reg_len(reg),
+        }
+    }
+}
+
+/// Returns the length of an indirect branch through `reg`.
+#[inline]
+const fn branch_reg_len(reg: Reg) -> usize {
+    if reg.is_hi() {
+        3
+    } else {
+        2
+    }
+}
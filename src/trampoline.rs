@@ -0,0 +1,308 @@
+use crate::len;
+use crate::{
+    DecodeError, Encoder, Inst, InstIter, Mode, Target, TargetError, Thunk, TrampolineError, WithIp,
+};
+
+/// Instructions stolen from the start of a function, relocated to run elsewhere, followed by a
+/// `jmp` back to the rest of the function.
+///
+/// Encoded into an inline buffer of `N` bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Trampoline<const N: usize = 64> {
+    encoder: Encoder<N>,
+    ip: usize,
+    stolen: usize,
+    resume_ip: usize,
+}
+
+impl<const N: usize> Trampoline<N> {
+    /// Steal the whole instructions covering at least `min_len` bytes of `bytes`, the start of a
+    /// function at `ip`, relocated to run at `new_ip` in `mode`, as by [`min_patch_len`].
+    ///
+    /// Relative branches are retargeted, becoming near branches where a short branch is out of
+    /// range, and in long mode absolute branches through [`Thunk::Indirect`] where a near branch
+    /// is out of range. `rip` relative memory operands keep referencing the same address.
+    ///
+    /// No `jmp` back is appended if the last stolen instruction returns or jumps away.
+    pub fn new(
+        bytes: &[u8],
+        ip: usize,
+        min_len: usize,
+        new_ip: usize,
+        mode: Mode,
+    ) -> Result<Self, TrampolineError> {
+        let stolen = min_patch_len(bytes, ip, min_len, mode)?;
+        let resume_ip = ip.wrapping_add(stolen);
+        let mut encoder = Encoder::new(mode);
+        let mut ends = false;
+
+        for inst in InstIter::from_bytes(ip, &bytes[..stolen], mode) {
+            let here = new_ip.wrapping_add(encoder.len());
+
+            match inst.target() {
+                Target::Absolute(target) => relocate_branch(&mut encoder, &inst, target, here)?,
+                Target::RipRelativeMem(addr) => relocate_rip(&mut encoder, &inst, addr, here)?,
+                _ => encoder.write_bytes(inst.bytes())?,
+            }
+
+            ends = ends_function(&inst);
+        }
+
+        if !ends {
+            let here = new_ip.wrapping_add(encoder.len());
+
+            relocate_branch(&mut encoder, &Inst::JmpShort(0), resume_ip, here)?;
+        }
+
+        Ok(Self {
+            encoder,
+            ip: new_ip,
+            stolen,
+            resume_ip,
+        })
+    }
+
+    /// Returns the address the trampoline runs at.
+    #[inline]
+    pub const fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the number of bytes stolen from the start of the function, which may be
+    /// overwritten.
+    #[inline]
+    pub const fn stolen(&self) -> usize {
+        self.stolen
+    }
+
+    /// Returns the address of the first instruction after those stolen.
+    #[inline]
+    pub const fn resume_ip(&self) -> usize {
+        self.resume_ip
+    }
+
+    /// Returns the bytes of the trampoline.
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.encoder.as_bytes()
+    }
+
+    /// Returns the length of the trampoline.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.encoder.len()
+    }
+}
+
+/// Returns `true` if `inst` returns or jumps away, so nothing after it is reached by falling
+/// through.
+#[inline]
+const fn ends_function(inst: &Inst) -> bool {
+    inst.is_terminator() && !inst.is_conditional()
+}
+
+/// Returns the length of the whole instructions covering at least `required` bytes of `bytes`,
+/// the start of a function at `ip` in `mode`.
+///
+/// Fails if the function returns or jumps away first, or a branch anywhere in `bytes`, such as a
+/// loop back to the start, targets the middle of the stolen bytes. `bytes` is to end with the
+/// function, as the branches of whatever follows it are checked too.
+pub fn min_patch_len(
+    bytes: &[u8],
+    ip: usize,
+    required: usize,
+    mode: Mode,
+) -> Result<usize, TrampolineError> {
+    let mut iter = InstIter::from_bytes(ip, bytes, mode);
+    let mut stolen = 0;
+
+    while stolen < required {
+        let Some(inst) = iter.next() else {
+            let needed = required - stolen;
+            let error = iter
+                .error()
+                .unwrap_or(DecodeError::TruncatedInstruction { needed });
+
+            return Err(TrampolineError::Decode(error));
+        };
+
+        stolen += inst.len();
+
+        if ends_function(&inst) && stolen < required {
+            return Err(TrampolineError::FunctionEnds);
+        }
+    }
+
+    for inst in InstIter::from_bytes(ip, bytes, mode).lengths() {
+        let Target::Absolute(target) = inst.target() else {
+            continue;
+        };
+
+        // the start of the function remains a valid target
+        let offset = target.wrapping_sub(ip);
+
+        if offset != 0 && offset < stolen {
+            return Err(TrampolineError::BranchIntoStolen);
+        }
+    }
+
+    Ok(stolen)
+}
+
+/// Write the relative branch `inst` at `here`, retargeted to `target`.
+fn relocate_branch<const N: usize>(
+    encoder: &mut Encoder<N>,
+    inst: &Inst,
+    target: usize,
+    here: usize,
+) -> Result<(), TrampolineError> {
+    let mode = encoder.mode();
+
+    let error = match inst.with_target(target, here, mode) {
+        Ok(inst) => {
+            encoder.write_inst(&inst)?;
+
+            return Ok(());
+        }
+        Err(error) => error,
+    };
+
+    // only long mode has an absolute branch reaching anywhere
+    if !matches!((error, mode), (TargetError::OutOfRange, Mode::Long64)) {
+        return Err(error.into());
+    }
+
+    let target = target as u64;
+
+    match *inst {
+        Inst::Call(_) => encoder.write_call_abs(target, Thunk::Indirect)?,
+        Inst::Jmp(_) | Inst::JmpShort(_) => encoder.write_jmp_abs(target, Thunk::Indirect)?,
+        // skip over the absolute jmp if the condition does not hold
+        Inst::Jcc(cond, _) | Inst::JccShort(cond, _) => {
+            let skip = Thunk::Indirect.jmp_len() as i8;
+
+            encoder.write_inst(&Inst::JccShort(cond.invert(), skip))?;
+            encoder.write_jmp_abs(target, Thunk::Indirect)?
+        }
+        _ => return Err(TrampolineError::OutOfRange),
+    };
+
+    Ok(())
+}
+
+/// Write `inst` at `here`, with the displacement of its `rip` relative memory operand adjusted to
+/// keep referencing `addr`.
+fn relocate_rip<const N: usize>(
+    encoder: &mut Encoder<N>,
+    inst: &WithIp,
+    addr: usize,
+    here: usize,
+) -> Result<(), TrampolineError> {
+    let bytes = rip_relocated(inst, addr, here)?;
+
+    encoder.write_bytes(&bytes[..inst.len()])?;
+
+    Ok(())
+}
+
+/// Returns the bytes of `inst` at `here`, with the displacement of its `rip` relative memory
+/// operand adjusted to keep referencing `addr`.
+pub(crate) fn rip_relocated(
+    inst: &WithIp,
+    addr: usize,
+    here: usize,
+) -> Result<[u8; 15], TargetError> {
+    let mut bytes = [0; 15];
+    let len = inst.len();
+
+    bytes[..len].copy_from_slice(inst.bytes());
+
+    // the displacement directly precedes the immediate
+    let Some(imm_len) = len::imm_len(inst.bytes(), inst.mode()) else {
+        return Err(TargetError::NotRelative);
+    };
+
+    let rel = crate::rel_to(addr, here.wrapping_add(len));
+    let Ok(disp) = i32::try_from(rel) else {
+        return Err(TargetError::OutOfRange);
+    };
+
+    let end = len - imm_len;
+
+    bytes[end - 4..end].copy_from_slice(&disp.to_le_bytes());
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: usize = 0x1000;
+    const NEW_IP: usize = 0x2000;
+
+    /// `jmp` from `NEW_IP + at` to `target`.
+    fn jmp(at: usize, target: usize) -> [u8; 5] {
+        let rel = target.wrapping_sub(NEW_IP + at + 5) as i32;
+        let [a, b, c, d] = rel.to_le_bytes();
+
+        [0xE9, a, b, c, d]
+    }
+
+    #[test]
+    fn copies_stolen_and_jumps_back() {
+        // push rbp; mov rbp, rsp; sub rsp, 0x10; xor eax, eax; ret
+        let bytes = [
+            0x55, 0x48, 0x89, 0xE5, 0x48, 0x83, 0xEC, 0x10, 0x31, 0xC0, 0xC3,
+        ];
+
+        let trampoline = Trampoline::<64>::new(&bytes, IP, 5, NEW_IP, Mode::Long64).unwrap();
+
+        assert_eq!(trampoline.stolen(), 8);
+        assert_eq!(trampoline.resume_ip(), IP + 8);
+        assert_eq!(&trampoline.as_bytes()[..8], &bytes[..8]);
+        assert_eq!(&trampoline.as_bytes()[8..], &jmp(8, IP + 8));
+    }
+
+    #[test]
+    fn function_longer_than_buffer() {
+        // push rbp; mov rbp, rsp; nop * 80; ret
+        let mut bytes = [0x90; 85];
+
+        bytes[..4].copy_from_slice(&[0x55, 0x48, 0x89, 0xE5]);
+        bytes[84] = 0xC3;
+
+        let trampoline = Trampoline::<64>::new(&bytes, IP, 5, NEW_IP, Mode::Long64).unwrap();
+
+        assert_eq!(trampoline.stolen(), 5);
+        assert_eq!(&trampoline.as_bytes()[..5], &bytes[..5]);
+        assert_eq!(&trampoline.as_bytes()[5..], &jmp(5, IP + 5));
+    }
+
+    #[test]
+    fn relocates_rip_relative() {
+        // mov rax, [rip + 0x100]; ret
+        let bytes = [0x48, 0x8B, 0x05, 0x00, 0x01, 0x00, 0x00, 0xC3];
+        let addr = IP + 7 + 0x100;
+
+        let trampoline = Trampoline::<64>::new(&bytes, IP, 5, NEW_IP, Mode::Long64).unwrap();
+        let disp = addr.wrapping_sub(NEW_IP + 7) as i32;
+
+        assert_eq!(trampoline.stolen(), 7);
+        assert_eq!(&trampoline.as_bytes()[..3], &bytes[..3]);
+        assert_eq!(&trampoline.as_bytes()[3..7], &disp.to_le_bytes());
+        assert_eq!(&trampoline.as_bytes()[7..], &jmp(7, IP + 7));
+    }
+
+    #[test]
+    fn branch_into_stolen() {
+        // push rbp; mov rbp, rsp; jmp -5 (to ip + 1); ret
+        let bytes = [0x55, 0x48, 0x89, 0xE5, 0xEB, 0xFB, 0xC3];
+
+        assert_eq!(
+            min_patch_len(&bytes, IP, 5, Mode::Long64),
+            Err(TrampolineError::BranchIntoStolen)
+        );
+    }
+}
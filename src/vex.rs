@@ -0,0 +1,163 @@
+// ty https://en.wikipedia.org/wiki/VEX_prefix
+
+use crate::modrm::Rm;
+use crate::prefix::Prefixes;
+use crate::rex::Rex;
+use crate::writer::Writer;
+
+/// First byte of a 3-byte VEX prefix.
+pub const VEX3: u8 = 0xC4;
+
+/// First byte of a 2-byte VEX prefix.
+pub const VEX2: u8 = 0xC5;
+
+/// Opcode map `0F`.
+pub const MAP_0F: u8 = 1;
+
+/// Opcode map `0F 38`.
+pub const MAP_0F38: u8 = 2;
+
+/// Opcode map `0F 3A`.
+pub const MAP_0F3A: u8 = 3;
+
+/// No implied prefix.
+pub const PP_NONE: u8 = 0;
+
+/// Implied `66` prefix.
+pub const PP_66: u8 = 1;
+
+/// Implied `F3` prefix.
+pub const PP_F3: u8 = 2;
+
+/// Implied `F2` prefix.
+pub const PP_F2: u8 = 3;
+
+const MAP_MASK: u8 = 0b0001_1111;
+const PP_MASK: u8 = 0b0000_0011;
+const VVVV_MASK: u8 = 0b0111_1000;
+const L: u8 = 0b0000_0100;
+const W: u8 = 0b1000_0000;
+
+/// A VEX prefix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Vex {
+    /// The REX equivalent of `R`, `X`, `B` and `W`.
+    pub rex: Rex,
+    /// Opcode map.
+    pub map: u8,
+    /// 4-bit `vvvv` operand.
+    pub vvvv: u8,
+    /// 256-bit vector length.
+    pub l: bool,
+    /// Implied mandatory prefix.
+    pub pp: u8,
+}
+
+impl Vex {
+    /// Create a VEX prefix, with `R`, `X` and `B` to be determined by the operands.
+    #[inline]
+    pub const fn new(map: u8, pp: u8, w: bool, vvvv: u8) -> Self {
+        Self {
+            rex: Rex::new(w, 0, 0, 0),
+            map,
+            vvvv,
+            l: false,
+            pp,
+        }
+    }
+
+    /// Parse a VEX prefix at the start of `bytes`, returning it along with the remaining bytes.
+    #[inline]
+    pub const fn from_bytes(bytes: &[u8], prefixes: Prefixes) -> Option<(Self, &[u8])> {
+        // legacy prefixes which are implied by VEX are not permitted
+        if prefixes.lock
+            || prefixes.operand_size
+            || prefixes.rep
+            || prefixes.repne
+            || prefixes.rex.is_present()
+        {
+            return None;
+        }
+
+        // R, X, B and vvvv are stored inverted
+        let (mut vex, rest) = match bytes {
+            [VEX3, a, b, rest @ ..] => {
+                let rex = Rex::new(
+                    *b & W != 0,
+                    (!*a >> 4) & 0b1000,
+                    (!*a >> 3) & 0b1000,
+                    (!*a >> 2) & 0b1000,
+                );
+
+                (Self::from_parts(rex, *a & MAP_MASK, *b), rest)
+            }
+            [VEX2, a, rest @ ..] => {
+                let rex = Rex::new(false, (!*a >> 4) & 0b1000, 0, 0);
+
+                (Self::from_parts(rex, MAP_0F, *a), rest)
+            }
+            _ => return None,
+        };
+
+        // only 8 registers are addressable outside of long mode
+        if !prefixes.mode.has_rex() {
+            vex.rex = Rex::new(vex.rex.w(), 0, 0, 0);
+            vex.vvvv &= 0b111;
+        }
+
+        Some((vex, rest))
+    }
+
+    /// Decode the fields shared between both forms, from the last byte of the prefix.
+    #[inline]
+    const fn from_parts(rex: Rex, map: u8, byte: u8) -> Self {
+        Self {
+            rex,
+            map,
+            vvvv: (!byte & VVVV_MASK) >> 3,
+            l: byte & L != 0,
+            pp: byte & PP_MASK,
+        }
+    }
+
+    /// Write `opcode`, preceded by this prefix, followed by a ModRM operand.
+    ///
+    /// `prefixes` provides any segment and address-size override, and the 2-byte form is used
+    /// when possible.
+    #[inline]
+    pub const fn encode(
+        self,
+        writer: &mut Writer,
+        prefixes: Prefixes,
+        opcode: u8,
+        reg: u8,
+        rm: Rm,
+    ) {
+        let w = self.rex.w();
+        let rex = rm.rex(w, reg);
+        let last = (!self.vvvv << 3) & VVVV_MASK | (self.l as u8) << 2 | self.pp;
+
+        let r = (rex.r() == 0) as u8;
+        let x = (rex.x() == 0) as u8;
+        let b = (rex.b() == 0) as u8;
+
+        let bytes = if self.map == MAP_0F && !w && x == 1 && b == 1 {
+            [VEX2, r << 7 | last, opcode, 0]
+        } else {
+            let first = r << 7 | x << 6 | b << 5 | self.map;
+
+            [VEX3, first, (w as u8) << 7 | last, opcode]
+        };
+
+        let len = if bytes[0] == VEX2 { 3 } else { 4 };
+        let (bytes, _) = bytes.split_at(len);
+
+        let prefixes = Prefixes {
+            seg: prefixes.seg,
+            address_size: prefixes.address_size,
+            ..Prefixes::none(prefixes.mode)
+        };
+
+        writer.write_op(prefixes, bytes, reg, rm);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::modrm::{self, Rm};
+use crate::prefix::Prefixes;
+use crate::rex::Rex;
+use crate::{EncodeError, InstBytes, Mode, Seg, Size};
+
+/// Recommended multi-byte NOP sequences, indexed by length - 1.
+const NOPS: [&[u8]; 9] = [
+    &[0x90],
+    &[0x66, 0x90],
+    &[0x0F, 0x1F, 0x00],
+    &[0x0F, 0x1F, 0x40, 0x00],
+    &[0x0F, 0x1F, 0x44, 0x00, 0x00],
+    &[0x66, 0x0F, 0x1F, 0x44, 0x00, 0x00],
+    &[0x0F, 0x1F, 0x80, 0x00, 0x00, 0x00, 0x00],
+    &[0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    &[0x66, 0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
+
+/// Writer of a single instruction, the first error is kept until [`Writer::finish`].
+pub struct Writer {
+    bytes: InstBytes,
+    mode: Mode,
+    error: Option<EncodeError>,
+    /// `ah`, `ch`, `dh` and `bh` are written, which no REX prefix may accompany.
+    high: bool,
+}
+
+impl Writer {
+    #[inline]
+    pub const fn new(mode: Mode) -> Self {
+        let bytes = InstBytes::new();
+        let error = None;
+
+        Self {
+            bytes,
+            mode,
+            error,
+            high: false,
+        }
+    }
+
+    /// Returns the mode being encoded for.
+    #[inline]
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Write byte registers 4 to 7 as `ah`, `ch`, `dh` and `bh`, dropping the empty REX prefix
+    /// which would select `spl`, `bpl`, `sil` and `dil` instead, and failing on any other.
+    #[inline]
+    pub const fn write_high(&mut self) {
+        self.high = true;
+    }
+
+    /// Record `error`, the first error is kept.
+    #[inline]
+    pub const fn fail(&mut self, error: EncodeError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    /// Returns this writer, or the first error encountered.
+    #[inline]
+    pub const fn finish(self) -> Result<Self, EncodeError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self),
+        }
+    }
+
+    #[inline]
+    pub const fn write_u8(&mut self, value: u8) {
+        if let Err(error) = self.bytes.push(value) {
+            self.fail(error);
+        }
+    }
+
+    #[inline]
+    pub const fn write_i32(&mut self, value: i32) {
+        let bytes = value.to_le_bytes();
+
+        self.write_bytes(&bytes);
+    }
+
+    #[inline]
+    pub const fn write_i64(&mut self, value: i64) {
+        let bytes = value.to_le_bytes();
+
+        self.write_bytes(&bytes);
+    }
+
+    #[inline]
+    pub const fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut index = 0;
+
+        while index < bytes.len() {
+            self.write_u8(bytes[index]);
+            index += 1;
+        }
+    }
+
+    /// Write a NOP of length `len` (1 to 15).
+    ///
+    /// Lengths beyond 9 are the 8 byte NOP, with a `2E` prefix, and padded with `66` prefixes.
+    #[inline]
+    pub const fn write_nop(&mut self, len: usize) {
+        if len == 0 {
+            self.fail(EncodeError::InvalidOperands);
+        } else if len <= NOPS.len() {
+            self.write_bytes(NOPS[len - 1]);
+        } else {
+            let mut prefixes = len - NOPS.len();
+
+            while prefixes > 0 {
+                self.write_u8(0x66);
+                prefixes -= 1;
+            }
+
+            self.write_u8(0x2E);
+            self.write_bytes(NOPS[7]);
+        }
+    }
+
+    /// Write a segment override prefix, if present.
+    #[inline]
+    pub const fn write_seg(&mut self, seg: Option<Seg>) {
+        if let Some(seg) = seg {
+            self.write_u8(seg.prefix());
+        }
+    }
+
+    /// Write an operand-size prefix, if `size` differs from the default of the mode.
+    #[inline]
+    pub const fn write_size(&mut self, size: Size) {
+        if self.mode.needs_size_prefix(size) {
+            self.write_u8(0x66);
+        }
+    }
+
+    /// Write a 16 or 32-bit immediate, depending on `size`.
+    #[inline]
+    pub const fn write_imm(&mut self, size: Size, imm: i32) {
+        if matches!(size, Size::Word) {
+            self.write_bytes(&(imm as i16).to_le_bytes());
+        } else {
+            self.write_i32(imm);
+        }
+    }
+
+    /// Write a REX prefix, if present.
+    ///
+    /// REX only exists in long mode.
+    #[inline]
+    pub const fn write_rex(&mut self, rex: Rex) {
+        if self.high && rex.is_empty() {
+            return;
+        }
+
+        if rex.is_present() && (self.high || !matches!(self.mode, Mode::Long64)) {
+            self.fail(EncodeError::InvalidOperands);
+        } else if rex.is_present() {
+            self.write_u8(rex.byte());
+        }
+    }
+
+    /// Write legacy prefixes, followed by any REX prefix.
+    #[inline]
+    pub const fn write_prefixes(&mut self, prefixes: Prefixes) {
+        if prefixes.lock {
+            self.write_u8(0xF0);
+        }
+
+        self.write_seg(prefixes.seg);
+
+        if prefixes.operand_size {
+            self.write_u8(0x66);
+        }
+
+        if prefixes.address_size {
+            self.write_u8(0x67);
+        }
+
+        if prefixes.repne {
+            self.write_u8(0xF2);
+        }
+
+        if prefixes.rep {
+            self.write_u8(0xF3);
+        }
+
+        self.write_rex(prefixes.rex);
+    }
+
+    /// Write `opcode` followed by a ModRM operand, preceded by its prefixes.
+    #[inline]
+    pub const fn write_op(&mut self, prefixes: Prefixes, opcode: &[u8], reg: u8, rm: Rm) {
+        self.write_prefixes(prefixes);
+        self.write_bytes(opcode);
+
+        if matches!(prefixes.addr_size(), Size::Word) {
+            self.write_modrm16(reg, rm);
+        } else {
+            self.write_modrm(reg, rm);
+        }
+    }
+
+    /// Write a ModRM byte, and any displacement required by `rm`, with 16-bit addressing.
+    #[inline]
+    pub const fn write_modrm16(&mut self, reg: u8, rm: Rm) {
+        let (base, index, disp) = match rm {
+            Rm::Reg(rm) => {
+                self.write_u8(modrm::modrm(modrm::MOD_DIRECT, reg, rm));
+                return;
+            }
+            Rm::Mem {
+                base: Some(base),
+                index,
+                disp,
+                ..
+            } => (base, index, disp),
+            // an index alone is a base
+            Rm::Mem {
+                base: None,
+                index: Some(index),
+                disp,
+                ..
+            } => (index, None, disp),
+            Rm::Mem {
+                base: None,
+                index: None,
+                disp,
+                ..
+            } => {
+                self.write_u8(modrm::modrm(modrm::MOD_INDIRECT, reg, modrm::RM16_BP));
+                self.write_bytes(&(disp as i16).to_le_bytes());
+                return;
+            }
+            // rip relative addressing only exists in long mode
+            Rm::Rip(_) => {
+                self.fail(EncodeError::InvalidOperands);
+                return;
+            }
+        };
+
+        let Some(rm) = modrm::rm16(base, index) else {
+            self.fail(EncodeError::InvalidOperands);
+            return;
+        };
+
+        let mode = modrm::disp_mode16(rm, disp);
+
+        self.write_u8(modrm::modrm(mode, reg, rm));
+
+        match mode {
+            modrm::MOD_DISP8 => self.write_u8(disp as u8),
+            modrm::MOD_DISP32 => self.write_bytes(&(disp as i16).to_le_bytes()),
+            _ => {}
+        }
+    }
+
+    /// Write a ModRM byte, and any SIB byte and displacement required by `rm`.
+    #[inline]
+    pub const fn write_modrm(&mut self, reg: u8, rm: Rm) {
+        match rm {
+            Rm::Reg(rm) => {
+                self.write_u8(modrm::modrm(modrm::MOD_DIRECT, reg, rm));
+            }
+            // rip relative addressing only exists in long mode
+            Rm::Rip(_) if !matches!(self.mode, Mode::Long64) => {
+                self.fail(EncodeError::InvalidOperands);
+            }
+            Rm::Rip(disp) => {
+                self.write_u8(modrm::modrm(modrm::MOD_INDIRECT, reg, modrm::RM_RIP));
+                self.write_i32(disp);
+            }
+            // an absolute address outside of long mode
+            Rm::Mem {
+                base: None,
+                index: None,
+                disp,
+                ..
+            } if !matches!(self.mode, Mode::Long64) => {
+                self.write_u8(modrm::modrm(modrm::MOD_INDIRECT, reg, modrm::RM_RIP));
+                self.write_i32(disp);
+            }
+            Rm::Mem {
+                base: None,
+                index,
+                scale,
+                disp,
+            } => {
+                let index = match index {
+                    Some(index) => index,
+                    None => modrm::SIB_NO_INDEX,
+                };
+
+                self.write_u8(modrm::modrm(modrm::MOD_INDIRECT, reg, modrm::RM_SIB));
+                self.write_u8(modrm::sib(scale, index, modrm::SIB_NO_BASE));
+                self.write_i32(disp);
+            }
+            Rm::Mem {
+                base: Some(base),
+                index,
+                scale,
+                disp,
+            } => {
+                let mode = modrm::disp_mode(base, disp);
+
+                match index {
+                    Some(index) => {
+                        self.write_u8(modrm::modrm(mode, reg, modrm::RM_SIB));
+                        self.write_u8(modrm::sib(scale, index, base));
+                    }
+                    // rsp/r12 as a base requires a SIB byte
+                    None if base & 0b111 == modrm::RM_SIB => {
+                        self.write_u8(modrm::modrm(mode, reg, modrm::RM_SIB));
+                        self.write_u8(modrm::sib(1, modrm::SIB_NO_INDEX, base));
+                    }
+                    None => {
+                        self.write_u8(modrm::modrm(mode, reg, base));
+                    }
+                }
+
+                match mode {
+                    modrm::MOD_DISP8 => self.write_u8(disp as u8),
+                    modrm::MOD_DISP32 => self.write_i32(disp),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Returns the number of bytes written.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the bytes written.
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Copy the bytes written to the start of `buf`, returning the number of bytes copied.
+    #[inline]
+    pub const fn copy_to(&self, buf: &mut [u8]) -> Result<usize, EncodeError> {
+        let bytes = self.as_bytes();
+
+        if bytes.len() > buf.len() {
+            return Err(EncodeError::BufferTooSmall {
+                needed: bytes.len(),
+            });
+        }
+
+        let mut index = 0;
+
+        while index < bytes.len() {
+            buf[index] = bytes[index];
+            index += 1;
+        }
+
+        Ok(bytes.len())
+    }
+
+    /// Returns the bytes written, owned.
+    #[inline]
+    pub const fn into_bytes(self) -> InstBytes {
+        self.bytes
+    }
+}
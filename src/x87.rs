@@ -0,0 +1,325 @@
+// ty https://www.felixcloutier.com/x86/ and http://ref.x86asm.net/coder64.html#x87
+
+use crate::mem::Mem;
+use crate::modrm::{self, ModRm, Rm};
+use crate::prefix::Prefixes;
+use crate::writer::Writer;
+use crate::{EncodeError, Flags, MemAccess};
+
+/// The `r/m` operand of an x87 instruction.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum X87Rm {
+    /// A stack register, `st(0)` to `st(7)`.
+    St(u8),
+    /// A memory operand.
+    Mem(Mem),
+}
+
+/// An x87 floating-point instruction (`D8` to `DF`).
+///
+/// Only decoded as far as is needed to determine its operands and length.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct X87 {
+    /// Escape opcode, `D8` to `DF`.
+    pub opcode: u8,
+    /// ModRM `reg`, an opcode extension.
+    pub digit: u8,
+    /// `r/m` operand.
+    pub rm: X87Rm,
+}
+
+/// Mnemonics of memory forms, indexed by escape opcode and then `/digit`.
+const MEM: [[Option<&str>; 8]; 8] = [
+    [
+        Some("fadd"),
+        Some("fmul"),
+        Some("fcom"),
+        Some("fcomp"),
+        Some("fsub"),
+        Some("fsubr"),
+        Some("fdiv"),
+        Some("fdivr"),
+    ],
+    [
+        Some("fld"),
+        None,
+        Some("fst"),
+        Some("fstp"),
+        Some("fldenv"),
+        Some("fldcw"),
+        Some("fnstenv"),
+        Some("fnstcw"),
+    ],
+    [
+        Some("fiadd"),
+        Some("fimul"),
+        Some("ficom"),
+        Some("ficomp"),
+        Some("fisub"),
+        Some("fisubr"),
+        Some("fidiv"),
+        Some("fidivr"),
+    ],
+    [
+        Some("fild"),
+        Some("fisttp"),
+        Some("fist"),
+        Some("fistp"),
+        None,
+        Some("fld"),
+        None,
+        Some("fstp"),
+    ],
+    [
+        Some("fadd"),
+        Some("fmul"),
+        Some("fcom"),
+        Some("fcomp"),
+        Some("fsub"),
+        Some("fsubr"),
+        Some("fdiv"),
+        Some("fdivr"),
+    ],
+    [
+        Some("fld"),
+        Some("fisttp"),
+        Some("fst"),
+        Some("fstp"),
+        Some("frstor"),
+        None,
+        Some("fnsave"),
+        Some("fnstsw"),
+    ],
+    [
+        Some("fiadd"),
+        Some("fimul"),
+        Some("ficom"),
+        Some("ficomp"),
+        Some("fisub"),
+        Some("fisubr"),
+        Some("fidiv"),
+        Some("fidivr"),
+    ],
+    [
+        Some("fild"),
+        Some("fisttp"),
+        Some("fist"),
+        Some("fistp"),
+        Some("fbld"),
+        Some("fild"),
+        Some("fbstp"),
+        Some("fistp"),
+    ],
+];
+
+/// Mnemonics of `D9 /4` to `D9 /7` register forms, indexed by the low 5 bits of ModRM.
+const D9: [Option<&str>; 32] = [
+    Some("fchs"),
+    Some("fabs"),
+    None,
+    None,
+    Some("ftst"),
+    Some("fxam"),
+    None,
+    None,
+    Some("fld1"),
+    Some("fldl2t"),
+    Some("fldl2e"),
+    Some("fldpi"),
+    Some("fldlg2"),
+    Some("fldln2"),
+    Some("fldz"),
+    None,
+    Some("f2xm1"),
+    Some("fyl2x"),
+    Some("fptan"),
+    Some("fpatan"),
+    Some("fxtract"),
+    Some("fprem1"),
+    Some("fdecstp"),
+    Some("fincstp"),
+    Some("fprem"),
+    Some("fyl2xp1"),
+    Some("fsqrt"),
+    Some("fsincos"),
+    Some("frndint"),
+    Some("fscale"),
+    Some("fsin"),
+    Some("fcos"),
+];
+
+impl X87 {
+    /// Decode an x87 instruction, `bytes` starting with `D8` to `DF`.
+    #[inline]
+    pub(crate) const fn from_bytes(bytes: &[u8], prefixes: Prefixes) -> Option<Self> {
+        let [opcode @ 0xD8..=0xDF, rest @ ..] = bytes else {
+            return None;
+        };
+
+        let modrm = match ModRm::from_bytes(rest, prefixes) {
+            Some(modrm) => modrm,
+            None => return None,
+        };
+
+        let rm = match modrm.rm {
+            Rm::Reg(bits) => X87Rm::St(bits & 0b111),
+            rm => {
+                let mut mem = match Mem::from_rm(rm) {
+                    Some(mem) => mem,
+                    None => return None,
+                };
+
+                mem.seg = prefixes.seg;
+                mem.addr_size = prefixes.addr_size();
+
+                X87Rm::Mem(mem)
+            }
+        };
+
+        Some(Self {
+            opcode: *opcode,
+            digit: modrm.reg & 0b111,
+            rm,
+        })
+    }
+
+    /// Returns the mnemonic of this instruction, if it is defined.
+    #[inline]
+    pub const fn mnemonic(self) -> Option<&'static str> {
+        let index = (self.opcode & 0b111) as usize;
+
+        let st = match self.rm {
+            X87Rm::St(st) => st,
+            X87Rm::Mem(_) => return MEM[index][self.digit as usize],
+        };
+
+        let mnemonic = match (self.opcode, self.digit) {
+            (0xD8, _) => return MEM[index][self.digit as usize],
+            (0xD9, 0) => "fld",
+            (0xD9, 1) => "fxch",
+            (0xD9, 2) if st == 0 => "fnop",
+            (0xD9, 4..=7) => return D9[((self.digit - 4) << 3 | st) as usize],
+            (0xDA, 0) => "fcmovb",
+            (0xDA, 1) => "fcmove",
+            (0xDA, 2) => "fcmovbe",
+            (0xDA, 3) => "fcmovu",
+            (0xDA, 5) if st == 1 => "fucompp",
+            (0xDB, 0) => "fcmovnb",
+            (0xDB, 1) => "fcmovne",
+            (0xDB, 2) => "fcmovnbe",
+            (0xDB, 3) => "fcmovnu",
+            (0xDB, 4) if st == 2 => "fnclex",
+            (0xDB, 4) if st == 3 => "fninit",
+            (0xDB, 5) => "fucomi",
+            (0xDB, 6) => "fcomi",
+            // the direction of subtraction and division is reversed
+            (0xDC, 0) => "fadd",
+            (0xDC, 1) => "fmul",
+            (0xDC, 4) => "fsubr",
+            (0xDC, 5) => "fsub",
+            (0xDC, 6) => "fdivr",
+            (0xDC, 7) => "fdiv",
+            (0xDD, 0) => "ffree",
+            (0xDD, 2) => "fst",
+            (0xDD, 3) => "fstp",
+            (0xDD, 4) => "fucom",
+            (0xDD, 5) => "fucomp",
+            (0xDE, 0) => "faddp",
+            (0xDE, 1) => "fmulp",
+            (0xDE, 3) if st == 1 => "fcompp",
+            (0xDE, 4) => "fsubrp",
+            (0xDE, 5) => "fsubp",
+            (0xDE, 6) => "fdivrp",
+            (0xDE, 7) => "fdivp",
+            (0xDF, 4) if st == 0 => "fnstsw",
+            (0xDF, 5) => "fucomip",
+            (0xDF, 6) => "fcomip",
+            _ => return None,
+        };
+
+        Some(mnemonic)
+    }
+
+    /// Returns the access to the memory operand of this instruction.
+    #[inline]
+    pub const fn mem_access(self) -> Option<MemAccess> {
+        let X87Rm::Mem(mem) = self.rm else {
+            return None;
+        };
+
+        let write = matches!(
+            (self.opcode, self.digit),
+            (0xD9, 2 | 3 | 6 | 7)
+                | (0xDB, 1 | 2 | 3 | 7)
+                | (0xDD, 1 | 2 | 3 | 6 | 7)
+                | (0xDF, 1 | 2 | 3 | 6 | 7)
+        );
+
+        let size = match (self.opcode, self.digit) {
+            // control word and status word
+            (0xD9, 5 | 7) | (0xDD, 7) => 2,
+            // environment
+            (0xD9, 4 | 6) => 28,
+            // environment and registers
+            (0xDD, 4 | 6) => 108,
+            // 80-bit real and packed bcd
+            (0xDB, 5 | 7) | (0xDF, 4 | 6) => 10,
+            (0xDF, 5 | 7) => 8,
+            (0xD8..=0xDB, _) => 4,
+            (0xDC | 0xDD, _) => 8,
+            _ => 2,
+        };
+
+        Some(MemAccess::new(mem, !write, write, Some(size)))
+    }
+
+    /// Returns the flags read and written by this instruction.
+    #[inline]
+    pub(crate) const fn flags(self) -> (Flags, Flags) {
+        if let X87Rm::Mem(_) = self.rm {
+            return (Flags::NONE, Flags::NONE);
+        }
+
+        match (self.opcode, self.digit) {
+            // fcmovb and fcmovnb
+            (0xDA | 0xDB, 0) => (Flags::CF, Flags::NONE),
+            // fcmove and fcmovne
+            (0xDA | 0xDB, 1) => (Flags::ZF, Flags::NONE),
+            // fcmovbe and fcmovnbe
+            (0xDA | 0xDB, 2) => (Flags::CF.union(Flags::ZF), Flags::NONE),
+            // fcmovu and fcmovnu
+            (0xDA | 0xDB, 3) => (Flags::PF, Flags::NONE),
+            // fucomi, fcomi, fucomip and fcomip
+            (0xDB | 0xDF, 5 | 6) => (Flags::NONE, Flags::STATUS),
+            _ => (Flags::NONE, Flags::NONE),
+        }
+    }
+
+    /// Write this instruction.
+    #[inline]
+    pub(crate) const fn encode(self, writer: &mut Writer) {
+        let mem = match self.rm {
+            X87Rm::St(st) => {
+                let modrm = modrm::modrm(modrm::MOD_DIRECT, self.digit, st);
+
+                writer.write_bytes(&[self.opcode, modrm]);
+                return;
+            }
+            X87Rm::Mem(mem) => mem,
+        };
+
+        let Some(rm) = mem.to_rm() else {
+            writer.fail(EncodeError::InvalidOperands);
+            return;
+        };
+
+        let prefixes = Prefixes {
+            seg: mem.seg,
+            address_size: writer.mode().needs_addr_prefix(mem.addr_size),
+            rex: rm.rex(false, 0),
+            ..Prefixes::none(writer.mode())
+        };
+
+        writer.write_op(prefixes, &[self.opcode], self.digit, rm);
+    }
+}
@@ -0,0 +1,57 @@
+const XMM_MASK: u8 = 0b1111;
+
+/// An SSE register.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Xmm {
+    Xmm0,
+    Xmm1,
+    Xmm2,
+    Xmm3,
+    Xmm4,
+    Xmm5,
+    Xmm6,
+    Xmm7,
+    Xmm8,
+    Xmm9,
+    Xmm10,
+    Xmm11,
+    Xmm12,
+    Xmm13,
+    Xmm14,
+    Xmm15,
+}
+
+impl Xmm {
+    /// Create a register from bits.
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & XMM_MASK {
+            0x0 => Xmm::Xmm0,
+            0x1 => Xmm::Xmm1,
+            0x2 => Xmm::Xmm2,
+            0x3 => Xmm::Xmm3,
+            0x4 => Xmm::Xmm4,
+            0x5 => Xmm::Xmm5,
+            0x6 => Xmm::Xmm6,
+            0x7 => Xmm::Xmm7,
+            0x8 => Xmm::Xmm8,
+            0x9 => Xmm::Xmm9,
+            0xA => Xmm::Xmm10,
+            0xB => Xmm::Xmm11,
+            0xC => Xmm::Xmm12,
+            0xD => Xmm::Xmm13,
+            0xE => Xmm::Xmm14,
+            _ => Xmm::Xmm15,
+        }
+    }
+
+    #[inline]
+    pub const fn base_bits(self) -> u8 {
+        self.bits() & 0b111
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self as u8
+    }
+}
@@ -0,0 +1,494 @@
+use dismal::{Arg, Cond, EncodeError, Inst, InstIter, Mem, Mode, Reg, Size};
+
+/// An encoding of each instruction, which is also the canonical encoding.
+const INSTS: &[(Mode, &[u8])] = &[
+    // Adc
+    (Mode::Long64, &[0x11, 0xDE]),
+    // Add
+    (Mode::Long64, &[0x04, 0x48]),
+    // And
+    (Mode::Long64, &[0x21, 0xDB]),
+    // Andn
+    (Mode::Long64, &[0xC4, 0x62, 0xE8, 0xF2, 0x36]),
+    // Bextr
+    (Mode::Long64, &[0xC4, 0xE2, 0x70, 0xF7, 0xC2]),
+    // Blsi
+    (Mode::Long64, &[0xC4, 0xE2, 0x70, 0xF3, 0xDA]),
+    // Blsmsk
+    (Mode::Long64, &[0xC4, 0xE2, 0x70, 0xF3, 0xD2]),
+    // Blsr
+    (Mode::Long64, &[0xC4, 0xC2, 0xB8, 0xF3, 0x4B, 0x0F]),
+    // Bsf
+    (Mode::Long64, &[0x0F, 0xBC, 0xC5]),
+    // Bsr
+    (Mode::Long64, &[0x0F, 0xBD, 0x31]),
+    // Bt
+    (Mode::Long64, &[0x0F, 0xA3, 0xFC]),
+    // Btc
+    (Mode::Long64, &[0x0F, 0xBB, 0xF2]),
+    // Btr
+    (Mode::Long64, &[0x0F, 0xB3, 0xD8]),
+    // Bts
+    (Mode::Long64, &[0x0F, 0xAB, 0x1E]),
+    // Bzhi
+    (Mode::Long64, &[0xC4, 0xE2, 0x70, 0xF5, 0xC2]),
+    // Call
+    (Mode::Long64, &[0xFF, 0x11]),
+    // Cbw
+    (Mode::Long64, &[0x98]),
+    // Clflush
+    (Mode::Long64, &[0x0F, 0xAE, 0x38]),
+    // Clflushopt
+    (Mode::Long64, &[0x66, 0x0F, 0xAE, 0x78, 0x0F]),
+    // Cli
+    (Mode::Long64, &[0xFA]),
+    // Clrssbsy
+    (Mode::Long64, &[0xF3, 0x0F, 0xAE, 0x72, 0x4E]),
+    // Clwb
+    (Mode::Long64, &[0x66, 0x0F, 0xAE, 0x30]),
+    // Cmovcc
+    (Mode::Long64, &[0x0F, 0x41, 0xD3]),
+    // Cmp
+    (Mode::Long64, &[0x3C, 0xAC]),
+    // Cmpxchg
+    (Mode::Long64, &[0x0F, 0xB0, 0x00]),
+    // Cpuid
+    (Mode::Long64, &[0x0F, 0xA2]),
+    // Cwd
+    (Mode::Long64, &[0x99]),
+    // Dec
+    (Mode::Long64, &[0xFF, 0xCB]),
+    // Div
+    (Mode::Long64, &[0xF6, 0xF2]),
+    // Endbr32
+    (Mode::Long64, &[0xF3, 0x0F, 0x1E, 0xFB]),
+    // Endbr64
+    (Mode::Long64, &[0xF3, 0x0F, 0x1E, 0xFA]),
+    // Evex
+    (Mode::Long64, &[0x62, 0x35, 0x66, 0xB3, 0x57, 0xC9]),
+    // Fxrstor
+    (Mode::Long64, &[0x0F, 0xAE, 0x0E]),
+    // Fxsave
+    (Mode::Long64, &[0x0F, 0xAE, 0x01]),
+    // Hlt
+    (Mode::Long64, &[0xF4]),
+    // Idiv
+    (Mode::Long64, &[0xF7, 0x3A]),
+    // Imul
+    (Mode::Long64, &[0xF6, 0xEA]),
+    // Imul2
+    (Mode::Long64, &[0x0F, 0xAF, 0xDE]),
+    // Imul3
+    (Mode::Long64, &[0x6B, 0xDD, 0xF1]),
+    // In
+    (Mode::Long64, &[0xEC]),
+    // Inc
+    (Mode::Long64, &[0xFF, 0xC1]),
+    // Incssp
+    (Mode::Compat32, &[0xF3, 0x0F, 0xAE, 0xEE]),
+    // Int
+    (Mode::Long64, &[0xCD, 0x77]),
+    // Int3
+    (Mode::Long64, &[0xCC]),
+    // Invlpg
+    (Mode::Long64, &[0x0F, 0x01, 0x39]),
+    // Iret
+    (Mode::Long64, &[0xCF]),
+    // Jcc
+    (Mode::Long64, &[0x0F, 0x86, 0xD3, 0x65, 0x82, 0xBF]),
+    // JccShort
+    (Mode::Long64, &[0x78, 0x47]),
+    // Jmp
+    (Mode::Long64, &[0xFF, 0xE1]),
+    // JmpShort
+    (Mode::Long64, &[0xEB, 0x39]),
+    // Ldmxcsr
+    (Mode::Long64, &[0x0F, 0xAE, 0x11]),
+    // Lea
+    (Mode::Long64, &[0x8D, 0x30]),
+    // Leave
+    (Mode::Long64, &[0xC9]),
+    // Lfence
+    (Mode::Long64, &[0x0F, 0xAE, 0xE8]),
+    // Lzcnt
+    (Mode::Long64, &[0xF3, 0x0F, 0xBD, 0x5B, 0x31]),
+    // Mfence
+    (Mode::Long64, &[0x0F, 0xAE, 0xF0]),
+    // Mov
+    (Mode::Long64, &[0x88, 0xCB]),
+    // Mov
+    (Mode::Long64, &[0x40, 0xB6, 0x7F]),
+    // Mov
+    (Mode::Long64, &[0xC6, 0x00, 0x7F]),
+    // Mov
+    (Mode::Long64, &[0x66, 0xC7, 0x00, 0x34, 0x12]),
+    // Mov
+    (Mode::Long64, &[0x48, 0xC7, 0x00, 0x78, 0x56, 0x34, 0x12]),
+    // Movbe
+    (Mode::Long64, &[0x0F, 0x38, 0xF1, 0x37]),
+    // Movsx
+    (Mode::Long64, &[0x63, 0x0F]),
+    // Movzx
+    (Mode::Long64, &[0x0F, 0xB6, 0xE8]),
+    // Mul
+    (Mode::Long64, &[0xF7, 0xE2]),
+    // Mulx
+    (Mode::Long64, &[0xC4, 0xE2, 0x73, 0xF6, 0xC2]),
+    // Neg
+    (Mode::Long64, &[0xF6, 0xD9]),
+    // Nop
+    (Mode::Long64, &[0x90]),
+    // Not
+    (Mode::Long64, &[0xF7, 0xD5]),
+    // Or
+    (Mode::Long64, &[0x0C, 0x4B]),
+    // Out
+    (Mode::Long64, &[0xEF]),
+    // Pause
+    (Mode::Long64, &[0xF3, 0x90]),
+    // Pdep
+    (Mode::Long64, &[0xC4, 0xE2, 0x73, 0xF5, 0xC2]),
+    // Pext
+    (Mode::Long64, &[0xC4, 0x62, 0xB2, 0xF5, 0xDB]),
+    // Pop
+    (Mode::Long64, &[0x5D]),
+    // Popcnt
+    (Mode::Long64, &[0xF3, 0x0F, 0xB8, 0xFE]),
+    // Popf
+    (Mode::Long64, &[0x9D]),
+    // Prefetchnta
+    (Mode::Long64, &[0x0F, 0x18, 0x00]),
+    // Prefetcht0
+    (Mode::Long64, &[0x0F, 0x18, 0x08]),
+    // Prefetcht1
+    (Mode::Long64, &[0x0F, 0x18, 0x10]),
+    // Prefetcht2
+    (Mode::Long64, &[0x0F, 0x18, 0x1E]),
+    // Prefetchw
+    (Mode::Long64, &[0x0F, 0x0D, 0x09]),
+    // Push
+    (Mode::Long64, &[0x53]),
+    // Pushf
+    (Mode::Long64, &[0x9C]),
+    // Rcl
+    (Mode::Long64, &[0xD1, 0x13]),
+    // Rcr
+    (Mode::Long64, &[0xD0, 0xDA]),
+    // Rdmsr
+    (Mode::Long64, &[0x0F, 0x32]),
+    // Rdrand
+    (Mode::Long64, &[0x0F, 0xC7, 0xF7]),
+    // Rdseed
+    (Mode::Long64, &[0x0F, 0xC7, 0xFF]),
+    // Rdssp
+    (Mode::Compat32, &[0xF3, 0x0F, 0x1E, 0xCA]),
+    // Rdtsc
+    (Mode::Long64, &[0x0F, 0x31]),
+    // Rdtscp
+    (Mode::Compat32, &[0x0F, 0x01, 0xF9]),
+    // Ret
+    (Mode::Long64, &[0xC3]),
+    // Rol
+    (Mode::Long64, &[0xD1, 0x02]),
+    // Ror
+    (Mode::Long64, &[0xD3, 0x0A]),
+    // Rorx
+    (Mode::Long64, &[0xC4, 0xE3, 0x7B, 0xF0, 0xC1, 0x05]),
+    // Rstorssp
+    (Mode::Long64, &[0xF3, 0x0F, 0x01, 0x6A, 0xDA]),
+    // Sar
+    (Mode::Long64, &[0xD0, 0xF8]),
+    // Sarx
+    (Mode::Long64, &[0xC4, 0xE2, 0x72, 0xF7, 0xC2]),
+    // Saveprevssp
+    (Mode::Long64, &[0xF3, 0x0F, 0x01, 0xEA]),
+    // Sbb
+    (Mode::Long64, &[0x19, 0x26]),
+    // Setcc
+    (Mode::Long64, &[0x0F, 0x9F, 0x01]),
+    // Setssbsy
+    (Mode::Long64, &[0xF3, 0x0F, 0x01, 0xE8]),
+    // Sfence
+    (Mode::Long64, &[0x0F, 0xAE, 0xF8]),
+    // Shl
+    (Mode::Long64, &[0xD0, 0x22]),
+    // Shlx
+    (Mode::Long64, &[0xC4, 0xE2, 0x71, 0xF7, 0xC2]),
+    // Shr
+    (Mode::Long64, &[0xD1, 0xED]),
+    // Shrx
+    (Mode::Long64, &[0xC4, 0x62, 0xCB, 0xF7, 0x2A]),
+    // SseAdd
+    (Mode::Long64, &[0x0F, 0x58, 0xE5]),
+    // SseAnd
+    (Mode::Long64, &[0x0F, 0x54, 0x32]),
+    // SseAndn
+    (Mode::Long64, &[0x0F, 0x55, 0xC5]),
+    // SseDiv
+    (Mode::Long64, &[0x0F, 0x5E, 0xF9]),
+    // SseMax
+    (Mode::Long64, &[0x0F, 0x5F, 0xD9]),
+    // SseMin
+    (Mode::Long64, &[0x0F, 0x5D, 0x30]),
+    // SseMova
+    (Mode::Long64, &[0x0F, 0x28, 0x0B]),
+    // SseMovu
+    (Mode::Long64, &[0x0F, 0x10, 0xD0]),
+    // SseMul
+    (Mode::Long64, &[0x0F, 0x59, 0xDE]),
+    // SseOr
+    (Mode::Long64, &[0x0F, 0x56, 0x3B]),
+    // SseSqrt
+    (Mode::Long64, &[0x0F, 0x51, 0xD9]),
+    // SseSub
+    (Mode::Long64, &[0x0F, 0x5C, 0xC4]),
+    // SseXor
+    (Mode::Long64, &[0x0F, 0x57, 0xF2]),
+    // Sti
+    (Mode::Long64, &[0xFB]),
+    // Stmxcsr
+    (Mode::Long64, &[0x0F, 0xAE, 0x1A]),
+    // Sub
+    (Mode::Long64, &[0x29, 0x2A]),
+    // Swapgs
+    (Mode::Long64, &[0x0F, 0x01, 0xF8]),
+    // Syscall
+    (Mode::Long64, &[0x0F, 0x05]),
+    // Sysret
+    (Mode::Long64, &[0x0F, 0x07]),
+    // Test
+    (Mode::Long64, &[0xA8, 0xF2]),
+    // Tzcnt
+    (Mode::Long64, &[0xF3, 0x0F, 0xBC, 0x2A]),
+    // Ud2
+    (Mode::Long64, &[0x0F, 0x0B]),
+    // Wait
+    (Mode::Long64, &[0x9B]),
+    // Wrmsr
+    (Mode::Long64, &[0x0F, 0x30]),
+    // Wrss
+    (Mode::Compat32, &[0x0F, 0x38, 0xF6, 0x12]),
+    // Wruss
+    (Mode::Long64, &[0x66, 0x0F, 0x38, 0xF5, 0x08]),
+    // X87
+    (Mode::Long64, &[0xDD, 0xD3]),
+    // Xadd
+    (Mode::Long64, &[0x0F, 0xC1, 0xC4]),
+    // Xchg
+    (Mode::Long64, &[0x92]),
+    // Xor
+    (Mode::Long64, &[0x34, 0x6C]),
+    // Xrstor
+    (Mode::Long64, &[0x0F, 0xAE, 0x2A]),
+    // Xrstors
+    (Mode::Long64, &[0x0F, 0xC7, 0x1B]),
+    // Xsave
+    (Mode::Long64, &[0x0F, 0xAE, 0x27]),
+    // Xsavec
+    (Mode::Long64, &[0x0F, 0xC7, 0x20]),
+    // Xsaveopt
+    (Mode::Long64, &[0x0F, 0xAE, 0x37]),
+    // Xsaves
+    (Mode::Long64, &[0x0F, 0xC7, 0x29]),
+];
+
+#[test]
+fn round_trip() {
+    let failed: Vec<_> = INSTS
+        .iter()
+        .filter_map(|&(mode, bytes)| {
+            let inst = match Inst::decode(bytes, mode) {
+                Ok((inst, len)) if len == bytes.len() => inst,
+                decoded => return Some(format!("{bytes:02X?} => {decoded:?}")),
+            };
+
+            match inst.to_bytes(mode) {
+                Ok(encoded) if encoded == *bytes => None,
+                encoded => Some(format!("{bytes:02X?} => {inst:?} => {encoded:02X?}")),
+            }
+        })
+        .collect();
+
+    assert!(failed.is_empty(), "{failed:#?}");
+}
+
+#[test]
+fn high_bytes() {
+    const HIGH: &[(Mode, &[u8], Inst)] = &[
+        (
+            Mode::Long64,
+            &[0x88, 0xE0],
+            Inst::Mov(Size::Byte, Arg::Reg(Reg::Rax), Arg::High(Reg::Rax)),
+        ),
+        (
+            Mode::Long64,
+            &[0x8A, 0x3B],
+            Inst::Mov(
+                Size::Byte,
+                Arg::High(Reg::Rbx),
+                Arg::Mem(Mem::base(Reg::Rbx, 0)),
+            ),
+        ),
+        (
+            Mode::Long64,
+            &[0xB5, 0x05],
+            Inst::Mov(Size::Byte, Arg::High(Reg::Rcx), Arg::Imm8(5)),
+        ),
+        (
+            Mode::Long64,
+            &[0xF6, 0xC4, 0x01],
+            Inst::Test(Size::Byte, Arg::High(Reg::Rax), Arg::Imm8(1)),
+        ),
+        (
+            Mode::Long64,
+            &[0x30, 0xF6],
+            Inst::Xor(Size::Byte, Arg::High(Reg::Rdx), Arg::High(Reg::Rdx)),
+        ),
+        (
+            Mode::Long64,
+            &[0x0F, 0xB6, 0xFC],
+            Inst::Movzx(Size::Dword, Reg::Rdi, Size::Byte, Arg::High(Reg::Rax)),
+        ),
+        (
+            Mode::Long64,
+            &[0x0F, 0x94, 0xC7],
+            Inst::Setcc(Cond::E, Arg::High(Reg::Rbx)),
+        ),
+        (
+            Mode::Compat32,
+            &[0x88, 0xE0],
+            Inst::Mov(Size::Byte, Arg::Reg(Reg::Rax), Arg::High(Reg::Rax)),
+        ),
+        // with a REX prefix, spl rather than ah
+        (
+            Mode::Long64,
+            &[0x40, 0x88, 0xE0],
+            Inst::Mov(Size::Byte, Arg::Reg(Reg::Rax), Arg::Reg(Reg::Rsp)),
+        ),
+    ];
+
+    for &(mode, bytes, inst) in HIGH {
+        assert_eq!(
+            Inst::decode(bytes, mode),
+            Ok((inst, bytes.len())),
+            "{bytes:02X?}"
+        );
+        assert_eq!(inst.to_bytes(mode).unwrap(), *bytes, "{inst:?}");
+    }
+}
+
+#[test]
+fn nop_keeps_its_length() {
+    const NOPS: &[&[u8]] = &[&[0x48, 0x90], &[0xF2, 0x48, 0x90], &[0x3E, 0x3E, 0x90]];
+
+    for &bytes in NOPS {
+        let (inst, len) = Inst::decode(bytes, Mode::Long64).unwrap();
+
+        assert_eq!((inst, len), (Inst::Nop(bytes.len() as u8), bytes.len()));
+        assert_eq!(
+            inst.to_bytes(Mode::Long64).unwrap().len(),
+            len,
+            "{bytes:02X?}"
+        );
+    }
+
+    assert_eq!(
+        Inst::decode(&[0xF3, 0x90], Mode::Long64),
+        Ok((Inst::Pause, 2))
+    );
+
+    // pause with any other prefix
+    assert!(Inst::decode(&[0xF3, 0x48, 0x90], Mode::Long64).is_err());
+    assert!(Inst::decode(&[0x2E, 0xF3, 0x90], Mode::Long64).is_err());
+}
+
+#[test]
+fn unknown_becomes_padding() {
+    // unknown bytes, and instructions known only by their length, become padding
+    let bytes = [0x0F, 0x00, 0x00, 0xD6];
+    let encoded: Vec<u8> = InstIter::from_bytes(0, &bytes, Mode::Long64)
+        .lengths()
+        .resync()
+        .flat_map(|inst| inst.to_bytes(Mode::Long64).unwrap().to_vec())
+        .collect();
+
+    assert_eq!(encoded, [0xCC; 4]);
+}
+
+#[test]
+fn invalid_operands() {
+    const INVALID: &[(Mode, Inst)] = &[
+        // an immediate destination
+        (
+            Mode::Long64,
+            Inst::Mov(Size::Qword, Arg::Imm32(1), Arg::Reg(Reg::Rax)),
+        ),
+        // two memory operands
+        (
+            Mode::Long64,
+            Inst::Mov(
+                Size::Qword,
+                Arg::Mem(Mem::base(Reg::Rax, 0)),
+                Arg::Mem(Mem::base(Reg::Rcx, 0)),
+            ),
+        ),
+        (
+            Mode::Long64,
+            Inst::Add(
+                Size::Dword,
+                Arg::Mem(Mem::base(Reg::Rax, 0)),
+                Arg::Mem(Mem::base(Reg::Rcx, 0)),
+            ),
+        ),
+        // a register source of lea
+        (
+            Mode::Long64,
+            Inst::Lea(Size::Qword, Reg::Rax, Arg::Reg(Reg::Rcx)),
+        ),
+        // a 64-bit immediate other than to a register
+        (
+            Mode::Long64,
+            Inst::Mov(Size::Qword, Arg::Mem(Mem::base(Reg::Rax, 0)), Arg::Imm64(1)),
+        ),
+        (Mode::Long64, Inst::Push(Arg::Imm64(1))),
+        // registers beyond rdi, and 64-bit operands, outside of long mode
+        (
+            Mode::Compat32,
+            Inst::Mov(Size::Dword, Arg::Reg(Reg::R8), Arg::Reg(Reg::Rax)),
+        ),
+        (
+            Mode::Compat32,
+            Inst::Add(Size::Qword, Arg::Reg(Reg::Rax), Arg::Reg(Reg::Rcx)),
+        ),
+        // high bytes alongside a REX prefix
+        (
+            Mode::Long64,
+            Inst::Mov(Size::Byte, Arg::High(Reg::Rax), Arg::Reg(Reg::Rsi)),
+        ),
+        (
+            Mode::Long64,
+            Inst::Add(Size::Byte, Arg::Reg(Reg::R8), Arg::High(Reg::Rcx)),
+        ),
+        (
+            Mode::Long64,
+            Inst::Mov(
+                Size::Byte,
+                Arg::Mem(Mem::base(Reg::R9, 0)),
+                Arg::High(Reg::Rbx),
+            ),
+        ),
+        // high bytes only exist of rax, rcx, rdx and rbx
+        (
+            Mode::Long64,
+            Inst::Mov(Size::Byte, Arg::High(Reg::Rsi), Arg::Reg(Reg::Rax)),
+        ),
+    ];
+
+    for (mode, inst) in INVALID {
+        assert_eq!(
+            inst.to_bytes(*mode),
+            Err(EncodeError::InvalidOperands),
+            "{inst:?} in {mode:?}"
+        );
+    }
+}